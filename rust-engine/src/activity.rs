@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use chrono::Utc;
 use std::sync::Arc;
 use dashmap::DashMap;
+use tokio::sync::broadcast;
+
+// Entries a slow /logs/stream subscriber can fall behind before it starts
+// losing them
+const STREAM_CAPACITY: usize = 256;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivityLog {
@@ -11,6 +16,8 @@ pub struct ActivityLog {
     pub category: String,
     pub message: String,
     pub symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,  // Structured payload, e.g. a signal breakdown
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,9 +29,43 @@ pub enum LogLevel {
     Error,
 }
 
+impl LogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "info",
+            LogLevel::Success => "success",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+// Optional level/category/symbol filters, all case-insensitive
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    pub level: Option<String>,
+    pub category: Option<String>,
+    pub symbol: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &ActivityLog) -> bool {
+        self.level.as_ref().is_none_or(|l| l.eq_ignore_ascii_case(log.level.as_str()))
+            && self.category.as_ref().is_none_or(|c| c.eq_ignore_ascii_case(&log.category))
+            && self.symbol.as_ref().is_none_or(|s| log.symbol.as_ref().is_some_and(|ls| ls.eq_ignore_ascii_case(s)))
+    }
+}
+
 pub struct ActivityLogger {
     logs: Arc<DashMap<String, ActivityLog>>,
     max_logs: usize,
+    stream: broadcast::Sender<ActivityLog>,
+}
+
+impl Default for ActivityLogger {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ActivityLogger {
@@ -32,10 +73,22 @@ impl ActivityLogger {
         Self {
             logs: Arc::new(DashMap::new()),
             max_logs: 100,
+            stream: broadcast::channel(STREAM_CAPACITY).0,
         }
     }
 
     pub fn log(&self, level: LogLevel, category: &str, message: &str, symbol: Option<&str>) {
+        self.log_with_details(level, category, message, symbol, None);
+    }
+
+    pub fn log_with_details(
+        &self,
+        level: LogLevel,
+        category: &str,
+        message: &str,
+        symbol: Option<&str>,
+        details: Option<serde_json::Value>,
+    ) {
         let id = uuid::Uuid::new_v4().to_string();
         let log = ActivityLog {
             id: id.clone(),
@@ -44,8 +97,11 @@ impl ActivityLogger {
             category: category.to_string(),
             message: message.to_string(),
             symbol: symbol.map(|s| s.to_string()),
+            details,
         };
 
+        // No subscribers is fine
+        let _ = self.stream.send(log.clone());
         self.logs.insert(id, log);
 
         // Keep only the most recent logs
@@ -67,6 +123,11 @@ impl ActivityLogger {
         logs
     }
 
+    // Every entry logged from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ActivityLog> {
+        self.stream.subscribe()
+    }
+
     pub fn info(&self, category: &str, message: &str) {
         self.log(LogLevel::Info, category, message, None);
     }
@@ -92,7 +153,28 @@ impl ActivityLogger {
         self.log(LogLevel::Info, "Signal", message, Some(symbol));
     }
 
-    pub fn analysis(&self, message: &str, symbol: &str) {
-        self.log(LogLevel::Info, "Analysis", message, Some(symbol));
+    pub fn analysis(&self, message: &str, symbol: &str, details: Option<serde_json::Value>) {
+        self.log_with_details(LogLevel::Info, "Analysis", message, Some(symbol), details);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn new_entries_reach_a_subscriber() {
+        let logger = ActivityLogger::new();
+        logger.info("System", "before anyone listened");
+        let mut stream = logger.subscribe();
+
+        logger.trade(LogLevel::Success, "BUY 10 shares", "AAPL");
+        let log = stream.recv().await.unwrap();
+        assert_eq!((log.category.as_str(), log.message.as_str(), log.symbol.as_deref()), ("Trade", "BUY 10 shares", Some("AAPL")));
+        assert!(stream.try_recv().is_err());
+
+        let filter = LogFilter { symbol: Some("aapl".to_string()), ..Default::default() };
+        assert!(filter.matches(&log));
+        logger.warning("Regime", "volatility high");
+        assert!(!filter.matches(&stream.recv().await.unwrap()));
+    }
+}
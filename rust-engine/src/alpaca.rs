@@ -1,6 +1,19 @@
 use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::entitlement::{self, EntitlementError, EntitlementTracker};
+use crate::http_log::SendLogged;
+use crate::metrics::{Metrics, ALPACA_REQUEST_SECONDS};
+use crate::news;
+use crate::slippage;
+use crate::throttle::{OrderThrottle, RateCategory, RateLimiter};
 
 #[derive(Clone)]
 pub struct AlpacaClient {
@@ -9,6 +22,54 @@ pub struct AlpacaClient {
     api_secret: String,
     base_url: String,
     data_url: String,
+    metrics: Arc<Metrics>,
+    bar_adjustment: BarAdjustment,
+    feed: String,
+    auto_downgrade_feed: bool,
+    feed_downgraded: Arc<AtomicBool>,
+    entitlements: EntitlementTracker,
+    log_requests: bool,
+    news_half_life_mins: f64,
+    limit_band_pct: f64,
+    order_throttle: OrderThrottle,
+    rate_limiter: RateLimiter,
+}
+
+// Corporate action adjustment applied to historical bars. Defaults to
+// `split`: with `raw` a 4:1 split looks like a 75% crash inside the bar
+// window, producing bogus momentum and SMA crossovers until it rolls off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarAdjustment {
+    Raw,
+    Split,
+    Dividend,
+    All,
+}
+
+impl BarAdjustment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BarAdjustment::Raw => "raw",
+            BarAdjustment::Split => "split",
+            BarAdjustment::Dividend => "dividend",
+            BarAdjustment::All => "all",
+        }
+    }
+}
+
+impl FromStr for BarAdjustment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(BarAdjustment::Raw),
+            "split" => Ok(BarAdjustment::Split),
+            "dividend" => Ok(BarAdjustment::Dividend),
+            "all" => Ok(BarAdjustment::All),
+            other => anyhow::bail!("Unknown bar adjustment: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,6 +77,8 @@ pub struct Account {
     pub buying_power: String,
     pub cash: String,
     pub portfolio_value: String,
+    #[serde(default)]
+    pub daytrade_count: u32,  // Alpaca's rolling five-day count, includes trades made outside the engine
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +98,10 @@ pub struct Order {
     pub side: String,
     pub order_type: String,
     pub status: String,
+    #[serde(default)]
+    pub filled_avg_price: Option<String>,
+    #[serde(default)]
+    pub filled_qty: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,25 +112,34 @@ pub struct OrderRequest {
     #[serde(rename = "type")]
     pub order_type: String,
     pub time_in_force: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<String>,
+    #[serde(skip)]
+    pub reference_price: Option<f64>,  // Quote the order was priced from, for the limit sanity check
 }
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 pub struct NewsArticle {
+    #[serde(default)]
+    pub id: u64,
     pub headline: String,
     pub summary: String,
     #[serde(default)]
     pub sentiment: String,
     #[serde(default)]
     pub sentiment_score: f64,
+    #[serde(default)]
+    pub created_at: String,
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct NewsResponse {
     pub news: Vec<NewsArticle>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Bar {
     pub t: String,
@@ -80,6 +156,57 @@ struct BarsResponse {
     bars: Vec<Bar>,
 }
 
+// Top of book from the latest quote. Stock and crypto quotes share the
+// same field names (bp/ap/bs/as/t), so both clients parse through this.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuoteDetail {
+    pub symbol: String,
+    pub bid: f64,
+    pub ask: f64,
+    pub bid_size: f64,
+    pub ask_size: f64,
+    pub spread: f64,
+    pub spread_pct: f64,  // Spread as a percentage of the midpoint
+    pub timestamp: String,
+}
+
+impl QuoteDetail {
+    pub fn from_quote(symbol: &str, quote: &serde_json::Value) -> Option<Self> {
+        let bid = quote["bp"].as_f64()?;
+        let ask = quote["ap"].as_f64()?;
+        let mid = (bid + ask) / 2.0;
+        Some(Self {
+            symbol: symbol.to_string(),
+            bid,
+            ask,
+            bid_size: quote["bs"].as_f64().unwrap_or(0.0),
+            ask_size: quote["as"].as_f64().unwrap_or(0.0),
+            spread: ask - bid,
+            spread_pct: if mid > 0.0 { (ask - bid) / mid * 100.0 } else { 0.0 },
+            timestamp: quote["t"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+// Shared by the stock and crypto clients
+pub fn check_limit_band(
+    symbol: &str,
+    side: &str,
+    limit_price: Option<&str>,
+    reference_price: Option<f64>,
+    band_pct: f64,
+) -> Result<()> {
+    let (Some(limit), Some(quote)) = (limit_price.and_then(|l| l.parse::<f64>().ok()), reference_price) else {
+        return Ok(());
+    };
+    if let Some(deviation) = slippage::limit_outside_band(side, limit, quote, band_pct) {
+        tracing::warn!("🚫 {} {} limit ${} rejected: {:.2}% past the ${:.2} quote (band {:.2}%)",
+                       symbol, side, limit, deviation, quote, band_pct);
+        anyhow::bail!("limit price {} for {} is {:.2}% past the quote (max {:.2}%)", limit, symbol, deviation, band_pct);
+    }
+    Ok(())
+}
+
 impl AlpacaClient {
     pub fn new(api_key: String, api_secret: String, paper: bool) -> Self {
         let base_url = if paper {
@@ -94,19 +221,124 @@ impl AlpacaClient {
             api_secret,
             base_url,
             data_url: "https://data.alpaca.markets/v2".to_string(),
+            metrics: Arc::new(Metrics::default()),
+            bar_adjustment: BarAdjustment::Split,
+            feed: "iex".to_string(),
+            auto_downgrade_feed: false,
+            feed_downgraded: Arc::new(AtomicBool::new(false)),
+            entitlements: EntitlementTracker::default(),
+            log_requests: false,
+            news_half_life_mins: 0.0,
+            limit_band_pct: 0.0,
+            order_throttle: OrderThrottle::default(),
+            rate_limiter: RateLimiter::default(),
+        }
+    }
+
+    // Limit orders priced further than this past their reference quote are
+    // refused before submission. 0 = no check.
+    pub fn with_limit_band(mut self, band_pct: f64) -> Self {
+        self.limit_band_pct = band_pct;
+        self
+    }
+
+    // Spaces out order submissions; share one throttle with the crypto
+    // client so the spacing holds across both
+    pub fn with_order_throttle(mut self, throttle: OrderThrottle) -> Self {
+        self.order_throttle = throttle;
+        self
+    }
+
+    // Per-category request limits; share one limiter with the crypto client
+    // so trading calls from both draw on the same bucket
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    // Recency weighting for get_news_sentiment, 0 = equal weights
+    pub fn with_news_half_life(mut self, half_life_mins: f64) -> Self {
+        self.news_half_life_mins = half_life_mins;
+        self
+    }
+
+    pub fn with_request_logging(mut self, enabled: bool) -> Self {
+        self.log_requests = enabled;
+        self
+    }
+
+    // "iex" works on every plan, "sip" needs a paid subscription. With
+    // auto_downgrade, the first entitlement rejection switches sip -> iex.
+    pub fn with_data_feed(mut self, feed: &str, auto_downgrade: bool) -> Self {
+        self.feed = feed.to_lowercase();
+        self.auto_downgrade_feed = auto_downgrade;
+        self
+    }
+
+    pub fn active_feed(&self) -> String {
+        if self.feed_downgraded.load(Ordering::Relaxed) {
+            "iex".to_string()
+        } else {
+            self.feed.clone()
+        }
+    }
+
+    pub fn entitlement_issues(&self) -> Vec<EntitlementError> {
+        self.entitlements.snapshot()
+    }
+
+    // Returns true if the feed was just downgraded and the request is worth retrying
+    fn downgrade_feed(&self, rejected_feed: &str) -> bool {
+        if !self.auto_downgrade_feed || rejected_feed == "iex" {
+            return false;
+        }
+        if self.feed_downgraded.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        tracing::warn!("🔒 Data plan rejected the '{}' feed, falling back to 'iex'", rejected_feed);
+        true
+    }
+
+    // Points the client at other hosts serving Alpaca's API (a proxy, or a
+    // mock in tests). Empty keeps the Alpaca default.
+    pub fn with_hosts(mut self, trading_host: &str, data_host: &str) -> Self {
+        if !trading_host.is_empty() {
+            self.base_url = format!("{}/v2", trading_host.trim_end_matches('/'));
+        }
+        if !data_host.is_empty() {
+            self.data_url = format!("{}/v2", data_host.trim_end_matches('/'));
         }
+        self
+    }
+
+    pub fn with_bar_adjustment(mut self, adjustment: BarAdjustment) -> Self {
+        self.bar_adjustment = adjustment;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn record_latency(&self, endpoint: &str, start: Instant) {
+        self.metrics.observe_since(ALPACA_REQUEST_SECONDS, &[("endpoint", endpoint)], start);
     }
 
     pub async fn get_account(&self) -> Result<Account> {
         let url = format!("{}/account", self.base_url);
         
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .send()
+            .send_logged(self.log_requests)
             .await
             .context("Failed to get account")?;
+        self.record_latency("account", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -119,12 +351,16 @@ impl AlpacaClient {
     pub async fn get_positions(&self) -> Result<Vec<Position>> {
         let url = format!("{}/positions", self.base_url);
         
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .send()
+            .send_logged(self.log_requests)
             .await?;
+        self.record_latency("positions", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
         if !response.status().is_success() {
             return Ok(vec![]);
@@ -134,15 +370,21 @@ impl AlpacaClient {
     }
 
     pub async fn place_order(&self, request: OrderRequest) -> Result<Order> {
+        check_limit_band(&request.symbol, &request.side, request.limit_price.as_deref(), request.reference_price, self.limit_band_pct)?;
+        self.order_throttle.wait(&request.symbol).await;
         let url = format!("{}/orders", self.base_url);
         
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
         let response = self.client
             .post(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
             .json(&request)
-            .send()
+            .send_logged(self.log_requests)
             .await?;
+        self.record_latency("orders", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -152,27 +394,73 @@ impl AlpacaClient {
         Ok(response.json().await?)
     }
 
-    pub async fn get_bars(&self, symbol: &str, timeframe: &str, limit: u32) -> Result<Vec<Bar>> {
-        let url = format!("{}/stocks/{}/bars", self.data_url, symbol);
+    // Works for both stock and crypto orders
+    pub async fn get_order(&self, order_id: &str) -> Result<Order> {
+        let url = format!("{}/orders/{}", self.base_url, order_id);
         
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .query(&[
-                ("timeframe", timeframe),
-                ("limit", &limit.to_string()),
-                ("adjustment", "raw"),
-                ("feed", "iex")
-            ])
-            .send()
-            .await
-            .context(format!("Failed to fetch bars for {}", symbol))?;
+            .send_logged(self.log_requests)
+            .await?;
+        self.record_latency("order_status", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
         if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to get order {}: {}", order_id, error_text);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    pub async fn get_bars(&self, symbol: &str, timeframe: &str, limit: u32) -> Result<Vec<Bar>> {
+        #[cfg(feature = "frozen-prices")]
+        if let Some(bars) = crate::frozen::bars(symbol, limit) {
+            return Ok(bars);
+        }
+        
+        let url = format!("{}/stocks/{}/bars", self.data_url, symbol);
+        
+        let response = loop {
+            let feed = self.active_feed();
+            self.rate_limiter.acquire(RateCategory::StockData).await;
+            let start = Instant::now();
+            let response = self.client
+                .get(&url)
+                .header("APCA-API-KEY-ID", &self.api_key)
+                .header("APCA-API-SECRET-KEY", &self.api_secret)
+                .query(&[
+                    ("timeframe", timeframe),
+                    ("limit", &limit.to_string()),
+                    ("adjustment", self.bar_adjustment.as_str()),
+                    ("feed", &feed)
+                ])
+                .send_logged(self.log_requests)
+                .await
+                .context(format!("Failed to fetch bars for {}", symbol))?;
+            self.record_latency("bars", start);
+            self.rate_limiter.record_quota(RateCategory::StockData, response.headers());
+
+            if response.status().is_success() {
+                self.entitlements.clear(symbol, "bars");
+                break response;
+            }
+            
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            if entitlement::is_entitlement_error(status, &body) {
+                if self.downgrade_feed(&feed) {
+                    continue;
+                }
+                return Err(self.entitlements.record(symbol, "bars", &feed, &body).into());
+            }
             tracing::warn!("Alpaca data API error for {}", symbol);
             return Ok(vec![]);
-        }
+        };
 
         let text = response.text().await?;
         
@@ -192,20 +480,96 @@ impl AlpacaClient {
         Ok(response_data.bars)
     }
 
-    pub async fn get_latest_quote(&self, symbol: &str) -> Result<f64> {
-        // CRITICAL: Use latest TRADE price, not ask/bid which can be fake
-        let url = format!("{}/stocks/{}/trades/latest", self.data_url, symbol);
+    // Latest trade price for many symbols in one request. Symbols missing from
+    // the response (no trades, not entitled) are simply absent from the map.
+    pub async fn get_latest_quotes(&self, symbols: &[&str]) -> Result<HashMap<String, f64>> {
+        #[cfg(feature = "frozen-prices")]
+        if symbols.iter().all(|s| crate::frozen::price(s).is_some()) {
+            return Ok(symbols.iter().filter_map(|s| Some((s.to_string(), crate::frozen::price(s)?))).collect());
+        }
+        
+        if symbols.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let url = format!("{}/stocks/trades/latest", self.data_url);
+        let feed = self.active_feed();
         
+        self.rate_limiter.acquire(RateCategory::StockData).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .query(&[("feed", "iex")])
-            .send()
+            .query(&[("symbols", symbols.join(",").as_str()), ("feed", feed.as_str())])
+            .send_logged(self.log_requests)
             .await
-            .context(format!("Failed to fetch latest trade for {}", symbol))?;
+            .context("Failed to fetch latest trades")?;
+        self.record_latency("latest_trades", start);
+        self.rate_limiter.record_quota(RateCategory::StockData, response.headers());
 
         if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to get latest trades: {}", error_text);
+        }
+
+        let value: serde_json::Value = response.json().await?;
+        let quotes: HashMap<String, f64> = value["trades"].as_object()
+            .map(|trades| {
+                trades.iter()
+                    .filter_map(|(symbol, trade)| {
+                        let price = trade["p"].as_f64()?;
+                        (price > 0.0).then(|| (symbol.clone(), price))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        // Frozen symbols win over whatever Alpaca returned for them
+        #[cfg(feature = "frozen-prices")]
+        let quotes: HashMap<String, f64> = quotes.into_iter()
+            .chain(symbols.iter().filter_map(|s| Some((s.to_string(), crate::frozen::price(s)?))))
+            .collect();
+        Ok(quotes)
+    }
+
+    pub async fn get_latest_quote(&self, symbol: &str) -> Result<f64> {
+        #[cfg(feature = "frozen-prices")]
+        if let Some(price) = crate::frozen::price(symbol) {
+            return Ok(price);
+        }
+        
+        // CRITICAL: Use latest TRADE price, not ask/bid which can be fake
+        let url = format!("{}/stocks/{}/trades/latest", self.data_url, symbol);
+        
+        let response = loop {
+            let feed = self.active_feed();
+            self.rate_limiter.acquire(RateCategory::StockData).await;
+            let start = Instant::now();
+            let response = self.client
+                .get(&url)
+                .header("APCA-API-KEY-ID", &self.api_key)
+                .header("APCA-API-SECRET-KEY", &self.api_secret)
+                .query(&[("feed", &feed)])
+                .send_logged(self.log_requests)
+                .await
+                .context(format!("Failed to fetch latest trade for {}", symbol))?;
+            self.record_latency("latest_trade", start);
+            self.rate_limiter.record_quota(RateCategory::StockData, response.headers());
+
+            if response.status().is_success() {
+                self.entitlements.clear(symbol, "latest_trade");
+                break response;
+            }
+            
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            if entitlement::is_entitlement_error(status, &body) {
+                if self.downgrade_feed(&feed) {
+                    continue;
+                }
+                return Err(self.entitlements.record(symbol, "latest_trade", &feed, &body).into());
+            }
+            
             tracing::warn!("Failed to get latest trade for {}, falling back to bars", symbol);
             // Fallback to latest bar close price
             let bars = self.get_bars(symbol, "1Min", 1).await?;
@@ -213,7 +577,7 @@ impl AlpacaClient {
                 return Ok(bar.c);
             }
             anyhow::bail!("No price data available for {}", symbol);
-        }
+        };
 
         let text = response.text().await?;
         let value: serde_json::Value = serde_json::from_str(&text)?;
@@ -236,19 +600,72 @@ impl AlpacaClient {
         anyhow::bail!("No price data available for {}", symbol)
     }
 
-    pub async fn close_position(&self, symbol: &str) -> Result<()> {
+    // Full bid/ask with sizes, for spread and liquidity checks. Unlike
+    // get_latest_quote this is the quote itself, not the last trade.
+    pub async fn get_quote_detail(&self, symbol: &str) -> Result<QuoteDetail> {
+        let url = format!("{}/stocks/{}/quotes/latest", self.data_url, symbol);
+        
+        let response = loop {
+            let feed = self.active_feed();
+            self.rate_limiter.acquire(RateCategory::StockData).await;
+            let start = Instant::now();
+            let response = self.client
+                .get(&url)
+                .header("APCA-API-KEY-ID", &self.api_key)
+                .header("APCA-API-SECRET-KEY", &self.api_secret)
+                .query(&[("feed", &feed)])
+                .send_logged(self.log_requests)
+                .await
+                .context(format!("Failed to fetch latest quote for {}", symbol))?;
+            self.record_latency("latest_quote", start);
+            self.rate_limiter.record_quota(RateCategory::StockData, response.headers());
+
+            if response.status().is_success() {
+                self.entitlements.clear(symbol, "latest_quote");
+                break response;
+            }
+            
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            if entitlement::is_entitlement_error(status, &body) {
+                if self.downgrade_feed(&feed) {
+                    continue;
+                }
+                return Err(self.entitlements.record(symbol, "latest_quote", &feed, &body).into());
+            }
+            anyhow::bail!("Alpaca API error fetching quote for {}: {}", symbol, body);
+        };
+
+        let value: serde_json::Value = response.json().await?;
+        QuoteDetail::from_quote(symbol, &value["quote"])
+            .ok_or_else(|| anyhow::anyhow!("No quote available for {}", symbol))
+    }
+
+    // Returns the liquidating order so the fill can be reconciled later
+    pub async fn close_position(&self, symbol: &str) -> Result<Order> {
+        self.order_throttle.wait(symbol).await;
         let url = format!("{}/positions/{}", self.base_url, symbol);
         
-        self.client
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
+        let response = self.client
             .delete(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .send()
+            .send_logged(self.log_requests)
             .await?;
+        self.record_latency("close_position", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
-        Ok(())
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to close position {}: {}", symbol, error_text);
+        }
+
+        Ok(response.json().await?)
     }
 
+    #[allow(dead_code)]
     pub async fn get_news_sentiment(&self, symbol: &str) -> Result<f64> {
         // News API uses data URL, not base_url!
         let url = format!("{}/v1beta1/news", self.data_url);
@@ -256,6 +673,8 @@ impl AlpacaClient {
         tracing::debug!("📰 News API URL: {}", url);
         tracing::debug!("📰 Requesting news for: {}", symbol);
         
+        self.rate_limiter.acquire(RateCategory::StockData).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
@@ -265,9 +684,11 @@ impl AlpacaClient {
                 ("limit", "10"),
                 ("sort", "desc"),
             ])
-            .send()
+            .send_logged(self.log_requests)
             .await
             .context(format!("Failed to fetch news for {}", symbol))?;
+        self.record_latency("news", start);
+        self.rate_limiter.record_quota(RateCategory::StockData, response.headers());
 
         if !response.status().is_success() {
             let status = response.status();
@@ -290,16 +711,147 @@ impl AlpacaClient {
             return Ok(0.0);
         }
 
-        // Average the sentiment scores from recent articles
-        let total_sentiment: f64 = news_response.news.iter()
-            .map(|article| article.sentiment_score)
-            .sum();
+        // Average the sentiment scores from recent articles, decayed by age
+        let scored: Vec<(f64, Option<DateTime<Utc>>)> = news_response.news.iter()
+            .map(|article| {
+                let published = DateTime::parse_from_rfc3339(&article.created_at)
+                    .ok()
+                    .map(|t| t.with_timezone(&Utc));
+                (article.sentiment_score, published)
+            })
+            .collect();
         
-        let avg_sentiment = total_sentiment / news_response.news.len() as f64;
+        let avg_sentiment = news::weighted_sentiment(&scored, Utc::now(), self.news_half_life_mins);
         
         tracing::info!("📰 {} NEWS: {} articles, avg sentiment: {:.3}", 
                       symbol, news_response.news.len(), avg_sentiment);
         
         Ok(avg_sentiment.clamp(-1.0, 1.0))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::technical::TechnicalAnalysis;
+    use crate::test_support;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    // 40 flat-ish bars with a 4:1 split 10 bars ago: raw prices drop from ~400
+    // to ~100, adjusted ones are ~100 throughout
+    async fn split_bars(Query(query): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+        let adjusted = query.get("adjustment").map(String::as_str) != Some("raw");
+        let bars: Vec<serde_json::Value> = (0..40)
+            .map(|i| {
+                let close = 100.0 + (i % 3) as f64 * 0.1;
+                let close = if i < 30 && !adjusted { close * 4.0 } else { close };
+                json!({ "t": format!("2024-06-10T14:{:02}:00Z", i), "o": close, "h": close, "l": close, "c": close, "v": 1000 })
+            })
+            .collect();
+        Json(json!({ "bars": bars, "adjustment": query.get("adjustment") }))
+    }
+
+    async fn client(adjustment: BarAdjustment) -> AlpacaClient {
+        let host = test_support::serve(Router::new().route("/v2/stocks/:symbol/bars", get(split_bars))).await;
+        AlpacaClient::new("key".into(), "secret".into(), true)
+            .with_hosts(&host, &host)
+            .with_bar_adjustment(adjustment)
+    }
+
+    #[tokio::test]
+    async fn bars_request_carries_the_configured_adjustment() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let app = Router::new().route("/v2/stocks/:symbol/bars", get(move |Query(query): Query<HashMap<String, String>>| {
+            recorded.lock().unwrap().push(query.get("adjustment").cloned().unwrap_or_default());
+            async { Json(json!({ "bars": [] })) }
+        }));
+        let host = test_support::serve(app).await;
+
+        for adjustment in [BarAdjustment::Split, BarAdjustment::All, BarAdjustment::Raw] {
+            let client = AlpacaClient::new("key".into(), "secret".into(), true)
+                .with_hosts(&host, &host)
+                .with_bar_adjustment(adjustment);
+            client.get_bars("AAPL", "5Min", 50).await.unwrap();
+        }
+        assert_eq!(*seen.lock().unwrap(), vec!["split", "all", "raw"]);
+    }
+
+    #[tokio::test]
+    async fn split_adjusted_bars_have_no_momentum_spike() {
+        let windows = [5, 10, 20];
+        let raw = client(BarAdjustment::Raw).await.get_bars("AAPL", "5Min", 40).await.unwrap();
+        let adjusted = client(BarAdjustment::Split).await.get_bars("AAPL", "5Min", 40).await.unwrap();
+
+        let raw_momentum = TechnicalAnalysis::calculate_momentum(&raw, &windows, &[]).unwrap();
+        let adjusted_momentum = TechnicalAnalysis::calculate_momentum(&adjusted, &windows, &[]).unwrap();
+        assert!(raw_momentum < -0.2, "raw momentum {}", raw_momentum);
+        assert!(adjusted_momentum.abs() < 0.01, "adjusted momentum {}", adjusted_momentum);
+    }
+
+    #[tokio::test]
+    async fn plan_rejection_maps_to_entitlement_error() {
+        let app = Router::new().route("/v2/stocks/:symbol/bars", get(|| async {
+            (axum::http::StatusCode::FORBIDDEN, r#"{"message":"subscription does not permit querying recent SIP data"}"#)
+        }));
+        let host = test_support::serve(app).await;
+        let client = AlpacaClient::new("key".into(), "secret".into(), true)
+            .with_hosts(&host, &host)
+            .with_data_feed("sip", false);
+
+        let err = client.get_bars("AAPL", "5Min", 50).await.unwrap_err();
+        let issue = err.downcast_ref::<EntitlementError>().expect("entitlement error");
+        assert_eq!((issue.symbol.as_str(), issue.endpoint.as_str(), issue.feed.as_str()), ("AAPL", "bars", "sip"));
+
+        let issues = client.entitlement_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "subscription does not permit querying recent SIP data");
+    }
+
+    #[test]
+    fn parses_adjustment_names() {
+        assert_eq!("SPLIT".parse::<BarAdjustment>().unwrap(), BarAdjustment::Split);
+        assert_eq!("dividend".parse::<BarAdjustment>().unwrap().as_str(), "dividend");
+        assert!("adjusted".parse::<BarAdjustment>().is_err());
+    }
+
+    #[tokio::test]
+    async fn latest_quotes_batch_symbols_and_skip_missing_ones() {
+        let app = Router::new().route("/v2/stocks/trades/latest", get(|Query(query): Query<HashMap<String, String>>| async move {
+            assert_eq!(query["symbols"], "AAPL,MSFT,ZZZZ");
+            Json(json!({ "trades": { "AAPL": { "p": 190.5 }, "MSFT": { "p": 410.25 } } }))
+        }));
+        let host = test_support::serve(app).await;
+        let client = AlpacaClient::new("key".into(), "secret".into(), true).with_hosts(&host, &host);
+
+        let quotes = client.get_latest_quotes(&["AAPL", "MSFT", "ZZZZ"]).await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes["AAPL"], 190.5);
+        assert_eq!(quotes["MSFT"], 410.25);
+        assert!(!quotes.contains_key("ZZZZ"));
+    }
+
+    #[test]
+    fn quote_detail_computes_the_spread() {
+        let quote = json!({ "bp": 99.95, "ap": 100.05, "bs": 3, "as": 7, "t": "2024-06-12T14:30:00Z" });
+        let detail = QuoteDetail::from_quote("AAPL", &quote).unwrap();
+        assert_eq!((detail.bid, detail.ask, detail.bid_size, detail.ask_size), (99.95, 100.05, 3.0, 7.0));
+        assert!((detail.spread - 0.10).abs() < 1e-9);
+        assert!((detail.spread_pct - 0.10).abs() < 1e-9);
+        assert_eq!(detail.timestamp, "2024-06-12T14:30:00Z");
+
+        // A one-sided book has no spread to report
+        assert!(QuoteDetail::from_quote("AAPL", &json!({ "bp": 99.95 })).is_none());
+    }
+
+    #[test]
+    fn order_outside_the_limit_band_is_rejected() {
+        assert!(check_limit_band("AAPL", "buy", Some("103.00"), Some(100.0), 2.0).is_err());
+        assert!(check_limit_band("AAPL", "buy", Some("101.00"), Some(100.0), 2.0).is_ok());
+        // Market orders, or no quote to compare against
+        assert!(check_limit_band("AAPL", "buy", None, Some(100.0), 2.0).is_ok());
+        assert!(check_limit_band("AAPL", "buy", Some("103.00"), None, 2.0).is_ok());
+    }
+}
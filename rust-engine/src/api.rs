@@ -0,0 +1,2148 @@
+use axum::{
+    routing::{get, post},
+    Router, Json,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::Response,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use futures_util::stream::{self, StreamExt};
+use tokio::sync::broadcast;
+use tokio::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::activity::{self, LogFilter};
+use crate::config::IndicatorConfig;
+use crate::engine_state::EngineState;
+use crate::config_history::{self, ConfigChange};
+use crate::correlation;
+use crate::alpaca::{self, QuoteDetail};
+use crate::metrics::{self, TradeStats};
+use crate::money;
+use crate::portfolio;
+use crate::profile::StrategyProfile;
+use crate::promotion;
+use crate::screener::{self, ScreenerFilter, ScreenerRow};
+use crate::session::SessionSummary;
+use crate::sizing;
+use crate::slippage;
+use crate::strategy::StrategyContext;
+use crate::symbols;
+use crate::tax;
+use crate::technical::{MovingAverage, TechnicalAnalysis};
+use crate::decision::{self, decide_action, TradeAction};
+use crate::notes::NO_AUTO_SELL;
+use crate::engine::{is_market_open, profit_target_pct, reconcile_close_pnl, active_stocks, preview_entry_size, restart_grace_period, strategy_for, try_run_cycle, AppState, PortfolioSnapshot, TradeReason, TradeRecord, TradingMode};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Position {
+    symbol: String,
+    quantity: f64,
+    entry_price: f64,
+    current_price: f64,
+    pnl: f64,
+    pnl_percent: f64,
+    market_value: f64,
+    asset_type: String,  // "stock" or "crypto"
+    note: Option<String>,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PositionsQuery {
+    #[serde(default)]
+    live: bool,  // Re-mark against the engine's live prices instead of Alpaca's snapshot
+}
+
+#[derive(Deserialize)]
+struct NoteRequest {
+    #[serde(default)]
+    note: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ToggleRequest {
+    enabled: bool,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(root))
+        .route("/health", get(health))
+        .route("/status", get(status))
+        .route("/positions", get(get_positions))
+        .route("/positions/crypto", get(get_crypto_positions))
+        .route("/positions/:symbol/note", post(set_position_note))
+        .route("/positions/close-all", post(close_all_positions))
+        .route("/toggle", post(toggle_trading))
+        .route("/toggle/crypto", post(toggle_crypto_trading))
+        .route("/maintenance", post(set_maintenance_mode))
+        .route("/cycle/run", post(run_cycle_now))
+        .route("/state/export", get(export_state))
+        .route("/state/import", post(import_state))
+        .route("/mode/live", post(promote_to_live))
+        .route("/account", get(get_account))
+        .route("/quote/:symbol", get(get_quote))
+        .route("/simulate-signal/:symbol", get(simulate_signal))
+        .route("/signals/heatmap", get(get_signal_heatmap))
+        .route("/signal/compare", post(compare_signals))
+        .route("/indicators/:symbol", get(get_indicators))
+        .route("/screener", get(get_screener))
+        .route("/decide", post(decide))
+        .route("/sizer", get(get_sizer))
+        .route("/logs", get(get_logs))
+        .route("/logs/stream", get(stream_logs))
+        .route("/diagnostics", get(get_diagnostics))
+        .route("/metrics", get(get_metrics))
+        .route("/portfolio/history", get(get_portfolio_history))
+        .route("/portfolio/beta", get(get_portfolio_beta))
+        .route("/pnl/attribution", get(get_pnl_attribution))
+        .route("/portfolio/sentiment", get(get_portfolio_sentiment))
+        .route("/trades/history", get(get_trade_history))
+        .route("/trades/stats", get(get_trade_stats))
+        .route("/analytics/by-reason", get(get_analytics_by_reason))
+        .route("/execution/slippage", get(get_slippage_stats))
+        .route("/tax/lots", get(get_tax_lots))
+        .route("/session/summary", get(get_session_summary))
+        .route("/news/symbols", get(get_news_symbols))
+        .route("/news/symbols", post(set_news_symbols))
+        .route("/profiles/:name", get(get_profile))
+        .route("/profiles/:name", post(save_profile))
+        .route("/profiles/:name/activate", post(activate_profile))
+        .route("/config/history", get(get_config_history))
+        .route("/config/diff", get(get_config_diff))
+        .route("/trading-mode", get(get_trading_mode))
+        .route("/trading-mode", post(set_trading_mode))
+        .route("/universe", get(get_universe))
+        .route("/book-profit/:symbol", post(book_profit_single))
+        .route("/book-all-profits", post(book_all_profits))
+        .route("/book-all-profits/preview", get(preview_book_all_profits))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .layer(tower_http::cors::CorsLayer::permissive())
+        .with_state(state)
+}
+
+// Two bearer-token tiers: the read token passes GET/HEAD only, the admin
+// token passes everything. With neither configured the API stays open, and
+// /health is always open for container health checks.
+async fn require_token(State(state): State<AppState>, request: Request, next: Next) -> Result<Response, StatusCode> {
+    let (read_token, admin_token) = {
+        let config = state.config.read().await;
+        (config.api.read_token.clone(), config.api.admin_token.clone())
+    };
+    if (read_token.is_empty() && admin_token.is_empty()) || request.uri().path() == "/health" {
+        return Ok(next.run(request).await);
+    }
+    
+    let presented = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or("");
+    if presented.is_empty() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    
+    let read_only = matches!(*request.method(), Method::GET | Method::HEAD);
+    if presented == admin_token || (read_only && presented == read_token) {
+        Ok(next.run(request).await)
+    } else if presented == read_token {
+        warn!("🔒 Read token used for {} {}", request.method(), request.uri().path());
+        Err(StatusCode::FORBIDDEN)
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+async fn root() -> Json<serde_json::Value> {
+    Json(json!({
+        "name": "LadyBug Trading Engine",
+        "version": "0.2.0",
+        "features": ["stocks", "crypto"]
+    }))
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "status": "healthy" }))
+}
+
+async fn status(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let trading_enabled = *state.trading_enabled.read().await;
+    let crypto_trading_enabled = *state.crypto_trading_enabled.read().await;
+    let maintenance_mode = *state.maintenance_mode.read().await;
+    
+    let positions_count = match state.alpaca.get_positions().await {
+        Ok(positions) => positions.len(),
+        Err(_) => 0,
+    };
+    
+    let crypto_positions_count = match state.alpaca.get_positions().await {
+        Ok(positions) => positions.iter().filter(|p| symbols::is_crypto_symbol(&p.symbol)).count(),
+        Err(_) => 0,
+    };
+    
+    Json(json!({
+        "running": true,
+        "version": "0.2.0",
+        "trading_enabled": trading_enabled,
+        "crypto_trading_enabled": crypto_trading_enabled,
+        "maintenance_mode": maintenance_mode,
+        "active_positions": positions_count,
+        "crypto_positions": crypto_positions_count,
+        "mode": "paper_trading"
+    }))
+}
+
+// Live marks for `?live=true`: the streamed price when we have one, otherwise
+// one batched latest-trade lookup for the stocks the stream hasn't covered.
+// Positions with neither keep Alpaca's figures.
+async fn live_marks(state: &AppState, positions: &[crate::alpaca::Position]) -> HashMap<String, f64> {
+    let mut marks: HashMap<String, f64> = positions.iter()
+        .filter_map(|p| state.live_prices.get(&p.symbol).map(|lp| (p.symbol.clone(), lp.price)))
+        .collect();
+    
+    let missing: Vec<&str> = positions.iter()
+        .filter(|p| !marks.contains_key(&p.symbol) && !symbols::is_crypto_symbol(&p.symbol))
+        .map(|p| p.symbol.as_str())
+        .collect();
+    if !missing.is_empty() {
+        match state.alpaca.get_latest_quotes(&missing).await {
+            Ok(quotes) => marks.extend(quotes),
+            Err(e) => warn!("Live P&L quote lookup failed: {}", e),
+        }
+    }
+    marks
+}
+
+// Alpaca's (current_price, unrealized_pl), or both re-derived from a live mark
+fn mark_position(p: &crate::alpaca::Position, qty: f64, entry: f64, marks: &HashMap<String, f64>) -> (f64, f64) {
+    match marks.get(&p.symbol) {
+        Some(&price) => (price, (price - entry) * qty),
+        None => (p.current_price.parse().unwrap_or(0.0), p.unrealized_pl.parse().unwrap_or(0.0)),
+    }
+}
+
+// Alpaca's position plus parsed numbers, asset type and the position note
+fn enrich_position(state: &AppState, p: &alpaca::Position, marks: &HashMap<String, f64>, dp: u32) -> Position {
+    let qty = p.qty.parse().unwrap_or(0.0);
+    let entry = p.avg_entry_price.parse().unwrap_or(0.0);
+    let (current, pnl) = mark_position(p, qty, entry, marks);
+    let market_value = qty * current;
+    let pnl_percent = if entry > 0.0 { ((current - entry) / entry) * 100.0 } else { 0.0 };
+    
+    let is_crypto = symbols::is_crypto_symbol(&p.symbol);
+    let asset_type = if is_crypto { "crypto" } else { "stock" };
+    let note = state.notes.get(&p.symbol);
+    
+    Position {
+        symbol: p.symbol.clone(),
+        quantity: qty,
+        entry_price: entry,
+        current_price: current,
+        pnl: money::round(pnl, dp),
+        pnl_percent,
+        market_value: money::round(market_value, dp),
+        asset_type: asset_type.to_string(),
+        note: note.as_ref().map(|n| n.note.clone()),
+        tags: note.map(|n| n.tags).unwrap_or_default(),
+    }
+}
+
+async fn get_positions(
+    State(state): State<AppState>,
+    Query(query): Query<PositionsQuery>,
+) -> Json<Vec<Position>> {
+    let mut all_positions = Vec::new();
+    
+    // Get real positions from Alpaca
+    if let Ok(positions) = state.alpaca.get_positions().await {
+        let marks = if query.live { live_marks(&state, &positions).await } else { HashMap::new() };
+        let dp = state.config.read().await.api.money_decimals;
+        let real_positions: Vec<Position> = positions.iter()
+            .map(|p| enrich_position(&state, p, &marks, dp))
+            .collect();
+        all_positions.extend(real_positions);
+    }
+    
+    // ONLY REAL ALPACA POSITIONS - NO TEST DATA
+    Json(all_positions)
+}
+
+// "BTC", "BTCUSD" and "BTC/USD" all mean the BTC/USD pair
+fn crypto_pair(symbol: &str) -> String {
+    if symbol.contains("/") {
+        symbol.to_string()
+    } else {
+        format!("{}/USD", symbol.trim_end_matches("USD"))
+    }
+}
+
+// Bid/ask/sizes/spread. Crypto accepts "BTCUSD" or an encoded "BTC%2FUSD".
+async fn get_quote(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+) -> Result<Json<QuoteDetail>, StatusCode> {
+    let symbol = symbol.to_uppercase();
+    let is_crypto = symbols::is_crypto_symbol(&symbol);
+    
+    let quote = if is_crypto {
+        let pair = crypto_pair(&symbol);
+        state.crypto.get_crypto_quote_detail(&pair).await
+    } else {
+        state.alpaca.get_quote_detail(&symbol).await
+    };
+    
+    match quote {
+        Ok(quote) => Ok(Json(quote)),
+        Err(e) => {
+            error!("❌ Quote lookup failed for {}: {}", symbol, e);
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IndicatorsQuery {
+    timeframe: Option<String>,  // Alpaca bar timeframe, default 5Min
+    limit: Option<u32>,         // Bars to fetch, default 100
+    #[serde(default)]
+    latest: bool,               // Only the last value of each series
+}
+
+// Per-bar values of the indicators the technical strategy uses, aligned to
+// the bar timestamps (null until there are enough bars), for charting
+async fn get_indicators(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(query): Query<IndicatorsQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let symbol = symbol.to_uppercase();
+    let timeframe = query.timeframe.unwrap_or_else(|| "5Min".to_string());
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    
+    let bars = if symbols::is_crypto_symbol(&symbol) {
+        state.crypto.get_crypto_bars(&crypto_pair(&symbol), &timeframe, limit).await
+            .map(|bars| bars.iter().map(|b| alpaca::Bar {
+                t: b.t.clone(), o: b.o, h: b.h, l: b.l, c: b.c, v: b.v as i64,
+            }).collect::<Vec<_>>())
+    } else {
+        state.alpaca.get_bars(&symbol, &timeframe, limit).await
+    };
+    let bars = bars.map_err(|e| {
+        error!("❌ Indicator fetch failed for {}: {}", symbol, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let Some(last) = bars.last() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    
+    let (average, regime_thresholds, atr_period) = {
+        let config = state.config.read().await;
+        (config.indicators.crossover_average, config.indicators.regime.clone(), config.profit_target.atr_period)
+    };
+    let regime = TechnicalAnalysis::classify_regime(&bars, &regime_thresholds);
+    let prefix = match average {
+        MovingAverage::Sma => "sma",
+        MovingAverage::Wma => "wma",
+    };
+    let (macd, macd_signal) = TechnicalAnalysis::macd_series(&bars);
+    let series = [
+        ("rsi".to_string(), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::calculate_rsi(b, 14))),
+        (format!("{}_20", prefix), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::moving_average(b, 20, average))),
+        (format!("{}_50", prefix), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::moving_average(b, 50, average))),
+        ("macd".to_string(), macd),
+        ("macd_signal".to_string(), macd_signal),
+        ("adx".to_string(), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::calculate_adx(b, regime_thresholds.adx_period))),
+        ("bollinger_width_pct".to_string(), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::bollinger_width_pct(b, 20, 2.0))),
+        ("atr".to_string(), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::calculate_atr(b, atr_period))),
+        ("atr_pct".to_string(), TechnicalAnalysis::series(&bars, |b| TechnicalAnalysis::atr_pct(b, atr_period))),
+    ];
+    
+    if query.latest {
+        let values: serde_json::Map<String, serde_json::Value> = series.into_iter()
+            .map(|(name, values)| (name, json!(values.last().copied().flatten())))
+            .collect();
+        return Ok(Json(json!({
+            "symbol": symbol,
+            "timeframe": timeframe,
+            "timestamp": last.t,
+            "close": last.c,
+            "regime": regime,
+            "values": values,
+        })));
+    }
+    
+    let indicators: serde_json::Map<String, serde_json::Value> = series.into_iter()
+        .map(|(name, values)| (name, json!(values)))
+        .collect();
+    Ok(Json(json!({
+        "symbol": symbol,
+        "timeframe": timeframe,
+        "timestamps": bars.iter().map(|b| b.t.clone()).collect::<Vec<_>>(),
+        "close": bars.iter().map(|b| b.c).collect::<Vec<_>>(),
+        "regime": regime,
+        "indicators": indicators,
+    })))
+}
+
+// Bars, RSI and strategy signal for one screener candidate; None if it
+// couldn't be fetched or has too little history
+async fn screen_symbol(state: &AppState, symbol: String) -> Option<ScreenerRow> {
+    let bars = match state.alpaca.get_bars(&symbol, "5Min", 50).await {
+        Ok(bars) if bars.len() >= 20 => bars,
+        Ok(_) => return None,
+        Err(e) => {
+            warn!("Screener skipped {}: {}", symbol, e);
+            return None;
+        }
+    };
+    let price = bars.last()?.c;
+    let volumes: Vec<f64> = bars.iter().map(|b| b.v as f64).collect();
+    let (indicators, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    let ctx = StrategyContext { symbol: &symbol, is_crypto: false, current_price: price, indicators: &indicators, rng_seed };
+    let (signal, _) = strategy_for(state, &symbol).await.evaluate(&bars, state.news.get_sentiment(&symbol), &ctx);
+    Some(ScreenerRow {
+        rsi: TechnicalAnalysis::calculate_rsi(&bars, 14),
+        daily_volume: sizing::estimate_daily_volume(&volumes, sizing::STOCK_BARS_PER_DAY),
+        symbol,
+        price,
+        signal,
+    })
+}
+
+// Scans the candidate stocks (capped, scan cached for SCREENER_CACHE_SECS)
+// and returns the ones matching the filters, strongest signal first
+async fn get_screener(
+    State(state): State<AppState>,
+    Query(filter): Query<ScreenerFilter>,
+) -> Json<serde_json::Value> {
+    let config = state.config.read().await.screener.clone();
+    let ttl = Duration::from_secs(config.cache_secs);
+    
+    let (rows, cached) = match state.screener_cache.get(ttl) {
+        Some(rows) => (rows, true),
+        None => {
+            let mut candidates: Vec<String> = if config.candidates.is_empty() {
+                [TradingMode::Conservative, TradingMode::Volatile, TradingMode::Hybrid].iter()
+                    .flat_map(|m| m.get_stocks())
+                    .map(str::to_string)
+                    .collect()
+            } else {
+                config.candidates.iter().map(|s| s.trim().to_uppercase()).collect()
+            };
+            candidates.sort();
+            candidates.dedup();
+            candidates.truncate(config.max_candidates);
+            
+            let rows: Vec<ScreenerRow> = stream::iter(candidates)
+                .map(|symbol| screen_symbol(&state, symbol))
+                .buffer_unordered(5)
+                .filter_map(|row| async move { row })
+                .collect()
+                .await;
+            state.screener_cache.set(rows.clone());
+            (rows, false)
+        }
+    };
+    
+    Json(json!({
+        "scanned": rows.len(),
+        "cached": cached,
+        "results": screener::rank(&rows, &filter),
+    }))
+}
+
+// Live price and the latest `limit` 5-min bars, crypto bars converted to the stock shape
+async fn fetch_price_and_bars(state: &AppState, symbol: &str, limit: u32) -> anyhow::Result<(f64, Vec<alpaca::Bar>)> {
+    if symbols::is_crypto_symbol(symbol) {
+        let pair = crypto_pair(symbol);
+        match (state.crypto.get_latest_crypto_price(&pair).await, state.crypto.get_crypto_bars(&pair, "5Min", limit).await) {
+            (Ok(price), Ok(bars)) => Ok((price, bars.iter().map(|b| alpaca::Bar {
+                t: b.t.clone(), o: b.o, h: b.h, l: b.l, c: b.c, v: b.v as i64,
+            }).collect())),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        }
+    } else {
+        match (state.alpaca.get_latest_quote(symbol).await, state.alpaca.get_bars(symbol, "5Min", limit).await) {
+            (Ok(price), Ok(bars)) => Ok((price, bars)),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        }
+    }
+}
+
+// One side of POST /signal/compare. Unset fields use the running config.
+#[derive(Deserialize, Default)]
+struct SignalVariant {
+    bars: Option<usize>,  // Score only the most recent N bars, unset = all fetched
+    momentum_windows: Option<Vec<usize>>,
+    momentum_weights: Option<Vec<f64>>,
+    crossover_average: Option<MovingAverage>,
+}
+
+#[derive(Deserialize)]
+struct CompareSignalsRequest {
+    symbol: String,
+    #[serde(default)]
+    a: SignalVariant,
+    #[serde(default)]
+    b: SignalVariant,
+}
+
+// Scores the same fetched bars under two configs side by side, e.g. the last
+// 20 vs the last 50 bars. Both sides share the sentiment and the synthetic
+// noise term, so any difference comes from the configs.
+async fn compare_signals(
+    State(state): State<AppState>,
+    Json(req): Json<CompareSignalsRequest>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let symbol = req.symbol.to_uppercase();
+    let (base, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    let limit = [req.a.bars, req.b.bars].into_iter().flatten().max().unwrap_or(50).clamp(50, 1000);
+    let (price, bars) = fetch_price_and_bars(&state, &symbol, limit as u32).await.map_err(|e| {
+        error!("❌ Signal comparison failed for {}: {}", symbol, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    
+    let sentiment = state.news.get_sentiment(&symbol);
+    let ctx = StrategyContext {
+        symbol: &symbol,
+        is_crypto: symbols::is_crypto_symbol(&symbol),
+        current_price: price,
+        indicators: &base,
+        rng_seed: Some(rng_seed.unwrap_or(0)),
+    };
+    let rng = ctx.rng(&bars);
+    let score = |variant: &SignalVariant| {
+        let indicators = IndicatorConfig {
+            momentum_windows: variant.momentum_windows.clone().unwrap_or_else(|| base.momentum_windows.clone()),
+            momentum_weights: variant.momentum_weights.clone().unwrap_or_else(|| base.momentum_weights.clone()),
+            crossover_average: variant.crossover_average.unwrap_or(base.crossover_average),
+            regime: base.regime.clone(),
+        };
+        let window = variant.bars.unwrap_or(bars.len());
+        let explanation = TechnicalAnalysis::explain_signal_window(&bars, window, sentiment, &indicators, &mut rng.clone());
+        json!({
+            "bars_used": window.min(bars.len()),
+            "indicators": indicators,
+            "signal": explanation.total,
+            "explanation": explanation,
+        })
+    };
+    let (a, b) = (score(&req.a), score(&req.b));
+    
+    Ok(Json(json!({
+        "symbol": symbol,
+        "price": price,
+        "sentiment": sentiment,
+        "bars_fetched": bars.len(),
+        "difference": b["signal"].as_f64().unwrap_or(0.0) - a["signal"].as_f64().unwrap_or(0.0),
+        "a": a,
+        "b": b,
+    })))
+}
+
+#[derive(Deserialize)]
+struct SizerQuery {
+    symbol: String,
+    price: Option<f64>,  // Defaults to the live quote
+    signal: Option<f64>,  // Only used to report which zone it falls in
+}
+
+// The quantity a buy would size to right now, with each stage of the chain
+// and the cap that ended up binding. Places nothing.
+async fn get_sizer(
+    State(state): State<AppState>,
+    Query(query): Query<SizerQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let symbol = query.symbol.to_uppercase();
+    let is_crypto = symbols::is_crypto_symbol(&symbol);
+    
+    let (quote, bars) = fetch_price_and_bars(&state, &symbol, 50).await.map_err(|e| {
+        error!("❌ Sizer failed to fetch {}: {}", symbol, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let price = query.price.unwrap_or(quote);
+    if !price.is_finite() || price <= 0.0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    
+    let (account, positions) = match (state.alpaca.get_account().await, state.alpaca.get_positions().await) {
+        (Ok(a), Ok(p)) => (a, p),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("❌ Sizer failed to load account/positions: {}", e);
+            return Err(StatusCode::BAD_GATEWAY);
+        }
+    };
+    let buying_power: f64 = account.buying_power.parse().unwrap_or(0.0);
+    let size = preview_entry_size(&state, &symbol, price, is_crypto, buying_power, &positions, &bars).await;
+    
+    let thresholds = state.config.read().await.thresholds_for(&symbol, is_crypto);
+    Ok(Json(json!({
+        "symbol": symbol,
+        "asset_class": symbols::asset_class(&symbol),
+        "price": price,
+        "signal": query.signal,
+        "zone": query.signal.map(|s| decision::signal_zone(s, &thresholds)),
+        "qty": size.qty,
+        "notional": size.notional,
+        "binding": size.binding,
+        "sizing": size,
+    })))
+}
+
+#[derive(Deserialize)]
+struct SimulateSignalQuery {
+    #[serde(default)]
+    cached: bool,  // Use the last cycle's bars and signal instead of fetching
+}
+
+// Latest cached signal for every symbol in the active universe, for a
+// color-coded dashboard grid. Never fetches: symbols not analyzed yet come
+// back with no signal, old ones are flagged stale. ATR is the last cycle's,
+// with atr_pct against the cached price.
+async fn get_signal_heatmap(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let mode = state.trading_mode.read().await.clone();
+    let config = state.config.read().await;
+    let now = Utc::now();
+    
+    let cells: Vec<serde_json::Value> = active_stocks(&state).await.into_iter()
+        .chain(mode.get_crypto().into_iter().map(String::from))
+        .map(|symbol| {
+            let symbol = symbol.as_str();
+            let is_crypto = symbols::is_crypto_symbol(symbol);
+            let thresholds = config.thresholds_for(symbol, is_crypto);
+            let key = symbols::normalize(symbol);
+            let cached = state.last_signals.get(&key).map(|c| c.clone());
+            let age_secs = cached.as_ref().map(|c| (now - c.computed_at).num_seconds());
+            let atr = state.atr.get(&key).map(|a| *a);
+            let atr_pct = atr.zip(cached.as_ref().map(|c| c.price))
+                .filter(|(_, price)| *price > 0.0)
+                .map(|(atr, price)| atr / price * 100.0);
+            json!({
+                "symbol": symbol,
+                "asset_class": symbols::asset_class(symbol),
+                "signal": cached.as_ref().map(|c| c.signal),
+                "confidence": cached.as_ref().and_then(|c| c.explanation.as_ref()).map(|e| e.confidence),
+                "zone": cached.as_ref().map_or("no_data", |c| decision::signal_zone(c.signal, &thresholds)),
+                "buy_threshold": thresholds.buy,
+                "sell_threshold": thresholds.sell,
+                "price": cached.as_ref().map(|c| c.price),
+                "atr": atr,
+                "atr_pct": atr_pct,
+                "computed_at": cached.as_ref().map(|c| c.computed_at.to_rfc3339()),
+                "age_secs": age_secs,
+                "stale": age_secs.is_none_or(|age| age > config.api.heatmap_stale_secs),
+            })
+        })
+        .collect();
+    
+    Json(json!({
+        "mode": mode,
+        "generated_at": now.to_rfc3339(),
+        "symbols": cells,
+    }))
+}
+
+// Runs the strategy for one symbol without trading. Live mode fetches a fresh
+// price and bars; cached mode answers from the last analysis cycle with no
+// Alpaca calls at all.
+async fn simulate_signal(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Query(query): Query<SimulateSignalQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let symbol = symbol.to_uppercase();
+    
+    if query.cached {
+        let cached = state.last_signals.get(&symbols::normalize(&symbol))
+            .map(|c| c.clone())
+            .ok_or(StatusCode::NOT_FOUND)?;
+        return Ok(Json(json!({
+            "symbol": symbol,
+            "source": "cached",
+            "cache_age_secs": (Utc::now() - cached.computed_at).num_seconds(),
+            "computed_at": cached.computed_at.to_rfc3339(),
+            "price": cached.price,
+            "signal": cached.signal,
+            "confidence": cached.explanation.as_ref().map(|e| e.confidence),
+            "sentiment": cached.sentiment,
+            "explanation": cached.explanation,
+            "bars": cached.bars.len(),
+        })));
+    }
+    
+    let is_crypto = symbols::is_crypto_symbol(&symbol);
+    let (price, bars) = fetch_price_and_bars(&state, &symbol, 50).await.map_err(|e| {
+        error!("❌ Signal simulation failed for {}: {}", symbol, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    if bars.len() < 20 {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    
+    let sentiment = state.news.get_sentiment(&symbol);
+    let (indicators, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    let ctx = StrategyContext { symbol: &symbol, is_crypto, current_price: price, indicators: &indicators, rng_seed };
+    let (signal, explanation) = strategy_for(&state, &symbol).await.evaluate(&bars, sentiment, &ctx);
+    
+    Ok(Json(json!({
+        "symbol": symbol,
+        "source": "live",
+        "cache_age_secs": 0,
+        "computed_at": Utc::now().to_rfc3339(),
+        "price": price,
+        "signal": signal,
+        "confidence": explanation.as_ref().map(|e| e.confidence),
+        "sentiment": sentiment,
+        "explanation": explanation,
+        "bars": bars.len(),
+    })))
+}
+
+#[derive(Deserialize)]
+struct DecideRequest {
+    symbol: String,
+    signal: f64,
+    #[serde(default)]
+    has_position: bool,
+    profit_percent: Option<f64>,  // Unrealized gain of the hypothetical position
+    buy_streak: Option<u32>,      // Cycles the buy signal has held; defaults to already confirmed
+}
+
+// What the current thresholds and profit target would do with a hypothetical
+// signal - no market data, no orders
+async fn decide(
+    State(state): State<AppState>,
+    Json(req): Json<DecideRequest>,
+) -> Json<serde_json::Value> {
+    let symbol = req.symbol.to_uppercase();
+    let is_crypto = symbols::is_crypto_symbol(&symbol);
+    let thresholds = state.config.read().await.thresholds_for(&symbol, is_crypto);
+    // No entry price to hand, so ATR-based targets fall back to the fixed one
+    let (target_pct, target_source) = profit_target_pct(&state, &symbol, 0.0, is_crypto).await;
+    let no_auto_sell = state.notes.has_tag(&symbol, NO_AUTO_SELL);
+    
+    let take_profit = req.has_position && !no_auto_sell && req.profit_percent.is_some_and(|p| p >= target_pct);
+    let buy_streak = req.buy_streak.unwrap_or(thresholds.confirmation_cycles.max(1));
+    let (action, reason) = if take_profit {
+        (TradeAction::Sell, "profit_target")
+    } else {
+        match decide_action(req.signal, req.has_position, buy_streak, &thresholds) {
+            TradeAction::Sell if no_auto_sell => (TradeAction::Hold, "no_auto_sell"),
+            TradeAction::Hold if !req.has_position && req.signal > thresholds.buy => (TradeAction::Hold, "awaiting_confirmation"),
+            action => (action, "signal"),
+        }
+    };
+    
+    Json(json!({
+        "symbol": symbol,
+        "action": action,
+        "reason": reason,
+        "thresholds": thresholds,
+        "profit_target_pct": target_pct,
+        "profit_target_source": target_source,
+    }))
+}
+
+async fn get_account(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let dp = state.config.read().await.api.money_decimals;
+    match state.alpaca.get_account().await {
+        Ok(account) => Ok(Json(json!({
+            "buying_power": money::parse_round(&account.buying_power, dp),
+            "cash": money::parse_round(&account.cash, dp),
+            "portfolio_value": money::parse_round(&account.portfolio_value, dp),
+        }))),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+async fn get_logs(
+    State(state): State<AppState>,
+    Query(filter): Query<LogFilter>,
+) -> Json<Vec<activity::ActivityLog>> {
+    Json(state.logger.get_logs().into_iter().filter(|l| filter.matches(l)).collect())
+}
+
+// WebSocket tail of the activity log: each new entry matching the filters is
+// pushed as a JSON text frame. A client that falls too far behind loses
+// entries and gets a {"dropped": n} frame instead.
+async fn stream_logs(
+    State(state): State<AppState>,
+    Query(filter): Query<LogFilter>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let logs = state.logger.subscribe();
+    ws.on_upgrade(move |socket| forward_logs(socket, logs, filter))
+}
+
+async fn forward_logs(mut socket: WebSocket, mut logs: broadcast::Receiver<activity::ActivityLog>, filter: LogFilter) {
+    loop {
+        let received = tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,  // Nothing to read from clients
+            },
+            received = logs.recv() => received,
+        };
+        let frame = match received {
+            Ok(log) if filter.matches(&log) => json!(log),
+            Ok(_) => continue,
+            Err(broadcast::error::RecvError::Lagged(dropped)) => json!({ "dropped": dropped }),
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if socket.send(Message::Text(frame.to_string())).await.is_err() {
+            break;  // Client went away
+        }
+    }
+}
+
+async fn get_diagnostics(State(state): State<AppState>) -> Json<serde_json::Value> {
+    Json(json!({
+        "order_backoff": state.order_backoff.snapshot(),
+        "slippage_flags": state.slippage.snapshot(),
+        "data_feed": state.alpaca.active_feed(),
+        "data_entitlements": state.alpaca.entitlement_issues(),
+        "position_notes": state.notes.snapshot(),
+        "halted_symbols": state.halts.snapshot(),
+        "api_quota": state.rate_limiter.quotas(),
+    }))
+}
+
+// Latest end-of-day recap (null until the first close since startup)
+async fn get_session_summary(State(state): State<AppState>) -> Json<Option<SessionSummary>> {
+    Json(state.last_session.read().await.clone())
+}
+
+// Prometheus scrape endpoint
+async fn get_metrics(State(state): State<AppState>) -> Result<(HeaderMap, String), StatusCode> {
+    if !state.metrics.is_enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4"));
+    let quotas = state.rate_limiter.quotas();
+    let remaining: Vec<(&str, f64)> = quotas.iter()
+        .filter_map(|(category, q)| Some((*category, q.remaining? as f64)))
+        .collect();
+    let limits: Vec<(&str, f64)> = quotas.iter()
+        .filter_map(|(category, q)| Some((*category, q.limit? as f64)))
+        .collect();
+    let mut body = state.metrics.render();
+    body.push_str(&metrics::render_gauge(metrics::ALPACA_QUOTA_REMAINING, &remaining));
+    body.push_str(&metrics::render_gauge(metrics::ALPACA_QUOTA_LIMIT, &limits));
+    Ok((headers, body))
+}
+
+#[derive(Deserialize)]
+struct NewsSymbolsRequest {
+    symbols: Vec<String>,
+}
+
+async fn get_news_symbols(State(state): State<AppState>) -> Json<Vec<String>> {
+    let symbols = state.news_symbols.read().await;
+    Json(symbols.clone())
+}
+
+async fn set_news_symbols(
+    State(state): State<AppState>,
+    Json(payload): Json<NewsSymbolsRequest>,
+) -> StatusCode {
+    let mut symbols = state.news_symbols.write().await;
+    *symbols = payload.symbols.clone();
+    state.logger.info("News", &format!("Updated news tracking: {:?}", payload.symbols));
+    StatusCode::OK
+}
+
+#[derive(Serialize)]
+struct PositionBeta {
+    symbol: String,
+    market_value: f64,
+    beta: f64,
+}
+
+#[derive(Deserialize)]
+struct AttributionQuery {
+    symbol: Option<String>,  // Unset = every symbol recorded
+}
+
+// Per-symbol unrealized P&L over time (sampled once a minute), to see which
+// positions drive the open P&L
+async fn get_pnl_attribution(
+    State(state): State<AppState>,
+    Query(query): Query<AttributionQuery>,
+) -> Json<serde_json::Value> {
+    let dp = state.config.read().await.api.money_decimals;
+    let history = state.pnl_history.read().await;
+    let series = |symbol: &str| -> Vec<portfolio::AttributionPoint> {
+        portfolio::attribution_series(&history, symbol).into_iter().map(|p| portfolio::AttributionPoint {
+            unrealized_pnl: money::round(p.unrealized_pnl, dp),
+            total_unrealized_pnl: money::round(p.total_unrealized_pnl, dp),
+            ..p
+        }).collect()
+    };
+    
+    match query.symbol {
+        Some(symbol) => Json(json!({ "symbol": symbol.to_uppercase(), "series": series(&symbol) })),
+        None => {
+            let symbols: std::collections::BTreeSet<&String> = history.iter().flat_map(|s| s.unrealized.keys()).collect();
+            let all: BTreeMap<&String, Vec<portfolio::AttributionPoint>> = symbols.into_iter().map(|s| (s, series(s))).collect();
+            Json(json!(all))
+        }
+    }
+}
+
+// Value-weighted beta of the held positions against BETA_BENCHMARK, from the
+// same 5-min returns the correlation check uses. Positions the engine hasn't
+// analyzed recently have no returns and are excluded; `coverage` is the share
+// of position value that went into the number.
+async fn get_portfolio_beta(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let benchmark = state.config.read().await.portfolio.beta_benchmark.clone();
+    let benchmark_bars = state.alpaca.get_bars(&benchmark, "5Min", 50).await.map_err(|e| {
+        error!("❌ Could not load {} bars for beta: {}", benchmark, e);
+        StatusCode::BAD_GATEWAY
+    })?;
+    let benchmark_returns = correlation::returns(&benchmark_bars.iter().map(|b| b.c).collect::<Vec<_>>());
+    let positions = state.alpaca.get_positions().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
+    
+    let mut covered = Vec::new();
+    let mut excluded = Vec::new();
+    let mut total_value = 0.0;
+    for p in &positions {
+        let qty: f64 = p.qty.parse().unwrap_or(0.0);
+        let price: f64 = p.current_price.parse().unwrap_or(0.0);
+        let market_value = (qty * price).abs();
+        total_value += market_value;
+        let beta = state.correlations.returns_for(&p.symbol)
+            .and_then(|r| correlation::beta(&r, &benchmark_returns));
+        match beta {
+            Some(beta) => covered.push(PositionBeta { symbol: p.symbol.clone(), market_value, beta }),
+            None => excluded.push(p.symbol.clone()),
+        }
+    }
+    
+    let covered_value: f64 = covered.iter().map(|p| p.market_value).sum();
+    let portfolio_beta = portfolio::weighted_average(
+        &covered.iter().map(|p| (p.market_value, p.beta)).collect::<Vec<_>>()
+    );
+    
+    Ok(Json(json!({
+        "benchmark": benchmark,
+        "beta": portfolio_beta,
+        "coverage": if total_value > 0.0 { covered_value / total_value } else { 0.0 },
+        "positions": covered,
+        "excluded": excluded,
+    })))
+}
+
+// Market-value-weighted news sentiment across holdings (-1 to 1). Positions
+// with no scored news are left out rather than counted as neutral.
+async fn get_portfolio_sentiment(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let positions = state.alpaca.get_positions().await.map_err(|_| StatusCode::BAD_GATEWAY)?;
+    
+    let mut weighted = Vec::new();
+    let mut per_symbol = serde_json::Map::new();
+    let mut without_news = Vec::new();
+    for p in &positions {
+        let qty: f64 = p.qty.parse().unwrap_or(0.0);
+        let price: f64 = p.current_price.parse().unwrap_or(0.0);
+        match state.news.sentiment_for(&p.symbol) {
+            Some(score) => {
+                weighted.push(((qty * price).abs(), score));
+                per_symbol.insert(p.symbol.clone(), json!(score));
+            }
+            None => without_news.push(p.symbol.clone()),
+        }
+    }
+    
+    Ok(Json(json!({
+        "sentiment": portfolio::weighted_average(&weighted),
+        "positions": per_symbol,
+        "without_news": without_news,
+    })))
+}
+
+#[derive(Deserialize)]
+struct PortfolioHistoryQuery {
+    resolution: Option<String>,  // e.g. "1m", "5m", "1h", "1d" - raw snapshots if omitted
+    #[serde(rename = "as")]
+    view: Option<String>,  // "candles" for OHLC of total_value per bucket (resolution defaults to 1h)
+}
+
+async fn get_portfolio_history(
+    State(state): State<AppState>,
+    Query(query): Query<PortfolioHistoryQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let dp = state.config.read().await.api.money_decimals;
+    let history = state.portfolio_history.read().await;
+    
+    match query.view.as_deref() {
+        None | Some("snapshots") => {}
+        Some("candles") => {
+            let resolution = query.resolution.as_deref().unwrap_or("1h");
+            let bucket_secs = portfolio::parse_resolution(resolution).ok_or(StatusCode::BAD_REQUEST)?;
+            let candles: Vec<portfolio::EquityCandle> = portfolio::candles(&history, bucket_secs)
+                .into_iter()
+                .map(|c| portfolio::EquityCandle {
+                    open: money::round(c.open, dp),
+                    high: money::round(c.high, dp),
+                    low: money::round(c.low, dp),
+                    close: money::round(c.close, dp),
+                    ..c
+                })
+                .collect();
+            return Ok(Json(json!(candles)));
+        }
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    }
+    
+    let snapshots = match query.resolution {
+        None => history.clone(),
+        Some(resolution) => {
+            let bucket_secs = portfolio::parse_resolution(&resolution).ok_or(StatusCode::BAD_REQUEST)?;
+            portfolio::resample(&history, bucket_secs)
+        }
+    };
+    let snapshots: Vec<PortfolioSnapshot> = snapshots.into_iter().map(|s| PortfolioSnapshot {
+        total_value: money::round(s.total_value, dp),
+        cash: money::round(s.cash, dp),
+        positions_value: money::round(s.positions_value, dp),
+        ..s
+    }).collect();
+    Ok(Json(json!(snapshots)))
+}
+
+#[derive(Deserialize)]
+struct TradeHistoryQuery {
+    asset_class: Option<String>,  // "stock", "crypto" or "all" (default)
+}
+
+async fn get_trade_history(
+    State(state): State<AppState>,
+    Query(query): Query<TradeHistoryQuery>,
+) -> Result<Json<Vec<TradeRecord>>, StatusCode> {
+    let asset_class = query.asset_class.unwrap_or_else(|| "all".to_string()).to_lowercase();
+    if !matches!(asset_class.as_str(), "stock" | "crypto" | "all") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let trades = state.trade_history.read().await;
+    Ok(Json(trades.iter()
+        .filter(|t| asset_class == "all" || t.asset_class() == asset_class)
+        .cloned()
+        .collect()))
+}
+
+async fn get_trade_stats(State(state): State<AppState>) -> Json<TradeStats> {
+    let trades = state.trade_history.read().await;
+    // Only SELLs carry realized P&L
+    let pnls: Vec<f64> = trades.iter().filter(|t| t.action == "SELL").map(|t| t.pnl).collect();
+    Json(metrics::trade_stats(&pnls))
+}
+
+// Paper-to-live gate. Refuses with the full checklist when any item fails.
+// The engine only routes orders to the paper endpoint today, so a passing
+// checklist is reported but can't switch anything yet.
+async fn promote_to_live(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let (config, has_credentials) = {
+        let config = state.config.read().await;
+        (config.promotion.clone(), config.credentials.is_present())
+    };
+    let credentials_valid = has_credentials && state.alpaca.get_account().await.is_ok();
+    let stats = {
+        let trades = state.trade_history.read().await;
+        let pnls: Vec<f64> = trades.iter().filter(|t| t.action == "SELL").map(|t| t.pnl).collect();
+        metrics::trade_stats(&pnls)
+    };
+    
+    let checklist = promotion::checklist(&stats, credentials_valid, &config);
+    let unmet: Vec<&str> = checklist.iter().filter(|c| !c.passed).map(|c| c.name).collect();
+    if !unmet.is_empty() {
+        warn!("🚫 Live promotion refused: {}", unmet.join(", "));
+        state.logger.warning("Mode", &format!("🚫 Live promotion refused ({})", unmet.join(", ")));
+        return (StatusCode::PRECONDITION_FAILED, Json(json!({
+            "promoted": false,
+            "reason": format!("Checklist not met: {}", unmet.join(", ")),
+            "checklist": checklist,
+        })));
+    }
+    
+    info!("✅ Live promotion checklist passed");
+    (StatusCode::NOT_IMPLEMENTED, Json(json!({
+        "promoted": false,
+        "reason": "Checklist passed, but this build only trades on the paper endpoint",
+        "checklist": checklist,
+    })))
+}
+
+// Realized P&L, count and win rate per exit reason. Sells recorded before
+// reasons were tracked are grouped under "unknown".
+async fn get_analytics_by_reason(State(state): State<AppState>) -> Json<BTreeMap<String, metrics::GroupStats>> {
+    let trades = state.trade_history.read().await;
+    Json(metrics::group_stats(trades.iter()
+        .filter(|t| t.action == "SELL")
+        .map(|t| (t.reason.map_or("unknown", |r| r.as_str()), t.pnl))))
+}
+
+#[derive(Deserialize)]
+struct SlippageQuery {
+    days: Option<i64>,  // Only fills from the last N days; all history if omitted
+}
+
+// Execution quality from confirmed fills: slippage vs the pre-trade quote,
+// overall and per symbol
+async fn get_slippage_stats(
+    State(state): State<AppState>,
+    Query(query): Query<SlippageQuery>,
+) -> Json<serde_json::Value> {
+    let cutoff = query.days.map(|d| Utc::now() - chrono::Duration::days(d));
+    let trades = state.trade_history.read().await;
+    let fills: Vec<(&str, f64)> = trades.iter()
+        .filter(|t| cutoff.is_none_or(|c| {
+            chrono::DateTime::parse_from_rfc3339(&t.timestamp).is_ok_and(|ts| ts >= c)
+        }))
+        .filter_map(|t| Some((t.symbol.as_str(), t.slippage_pct?)))
+        .collect();
+    
+    let mut by_symbol: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for (symbol, pct) in &fills {
+        by_symbol.entry(symbol).or_default().push(*pct);
+    }
+    let all: Vec<f64> = fills.iter().map(|(_, pct)| *pct).collect();
+    
+    Json(json!({
+        "overall": slippage::slippage_stats(&all),
+        "by_symbol": by_symbol.iter()
+            .map(|(symbol, values)| (symbol.to_string(), json!(slippage::slippage_stats(values))))
+            .collect::<serde_json::Map<_, _>>(),
+    }))
+}
+
+#[derive(Deserialize)]
+struct TaxLotsQuery {
+    year: Option<i32>,
+}
+
+// Realized gains per matched lot from the engine's own trade history
+async fn get_tax_lots(
+    State(state): State<AppState>,
+    Query(query): Query<TaxLotsQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let method_name = state.config.read().await.api.tax_lot_method.clone();
+    let method = tax::LotMethod::parse(&method_name).ok_or_else(|| {
+        error!("❌ Unknown TAX_LOT_METHOD '{}'", method_name);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let report = tax::match_lots(&state.trade_history.read().await, method, query.year);
+    
+    let total = |long_term: bool| report.realized.iter().filter(|l| l.long_term == long_term).map(|l| l.gain).sum::<f64>();
+    Ok(Json(json!({
+        "method": method,
+        "year": query.year,
+        "short_term_gain": total(false),
+        "long_term_gain": total(true),
+        "lots": report.realized,
+        "unmatched_sales": report.unmatched,
+    })))
+}
+
+async fn toggle_trading(
+    State(state): State<AppState>,
+    Json(payload): Json<ToggleRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut trading_enabled = state.trading_enabled.write().await;
+    *trading_enabled = payload.enabled;
+    
+    if payload.enabled {
+        state.logger.success("System", "Trading ENABLED ✅");
+    } else {
+        state.logger.warning("System", "Trading DISABLED ❌");
+    }
+    
+    info!("Trading {}", if payload.enabled { "ENABLED ✅" } else { "DISABLED ❌" });
+    Ok(StatusCode::OK)
+}
+
+async fn toggle_crypto_trading(
+    State(state): State<AppState>,
+    Json(payload): Json<ToggleRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let mut crypto_trading_enabled = state.crypto_trading_enabled.write().await;
+    *crypto_trading_enabled = payload.enabled;
+    
+    if payload.enabled {
+        state.logger.success("Crypto", "Crypto Trading ENABLED ₿✅");
+    } else {
+        state.logger.warning("Crypto", "Crypto Trading DISABLED ❌");
+    }
+    
+    info!("Crypto Trading {}", if payload.enabled { "ENABLED ₿✅" } else { "DISABLED ❌" });
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct RunCycleQuery {
+    #[serde(default = "default_asset_class")]
+    asset_class: String,  // "stock" or "crypto"
+}
+
+fn default_asset_class() -> String {
+    "stock".to_string()
+}
+
+// Runs one analysis cycle immediately instead of waiting for the interval,
+// e.g. after a config change. The same conditions as the scheduled loop
+// apply, and it refuses to start while a cycle of that class is running.
+async fn run_cycle_now(
+    State(state): State<AppState>,
+    Query(query): Query<RunCycleQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let (is_crypto, asset_class) = match query.asset_class.to_lowercase().as_str() {
+        "stock" | "stocks" => (false, "stock"),
+        "crypto" => (true, "crypto"),
+        _ => return (StatusCode::BAD_REQUEST, Json(json!({ "error": "asset_class must be stock or crypto" }))),
+    };
+    let enabled = if is_crypto { *state.crypto_trading_enabled.read().await } else { *state.trading_enabled.read().await };
+    let refusal = if *state.maintenance_mode.read().await {
+        Some("maintenance mode is on")
+    } else if !enabled {
+        Some("trading is disabled")
+    } else if !is_crypto && !is_market_open(Utc::now()) {
+        Some("market is closed")
+    } else {
+        None
+    };
+    if let Some(reason) = refusal {
+        return (StatusCode::CONFLICT, Json(json!({ "ran": false, "reason": reason })));
+    }
+    
+    info!("▶️  Manual {} cycle requested", asset_class);
+    state.logger.info("System", &format!("▶️ Manual {} cycle started", asset_class));
+    let started = std::time::Instant::now();
+    match try_run_cycle(&state, is_crypto).await {
+        Some(summary) => (StatusCode::OK, Json(json!({
+            "ran": true,
+            "asset_class": asset_class,
+            "duration_ms": started.elapsed().as_millis() as u64,
+            "summary": summary,
+        }))),
+        None => (StatusCode::CONFLICT, Json(json!({ "ran": false, "reason": "a cycle is already running" }))),
+    }
+}
+
+// Pauses every trading loop (analysis, orders, exit watching) without
+// touching the enabled flags, so turning it off resumes exactly as before
+async fn set_maintenance_mode(
+    State(state): State<AppState>,
+    Json(payload): Json<ToggleRequest>,
+) -> StatusCode {
+    let mut maintenance = state.maintenance_mode.write().await;
+    if *maintenance == payload.enabled {
+        return StatusCode::OK;
+    }
+    *maintenance = payload.enabled;
+    
+    if payload.enabled {
+        state.logger.warning("System", "🛠️ Maintenance mode ON - trading loops paused");
+    } else {
+        state.logger.success("System", "🛠️ Maintenance mode OFF - trading loops resumed");
+    }
+    info!("🛠️  Maintenance mode {}", if payload.enabled { "ON" } else { "OFF" });
+    StatusCode::OK
+}
+
+async fn get_crypto_positions(
+    State(state): State<AppState>,
+    Query(query): Query<PositionsQuery>,
+) -> Json<Vec<Position>> {
+    match state.alpaca.get_positions().await {
+        Ok(positions) => {
+            let marks = if query.live { live_marks(&state, &positions).await } else { HashMap::new() };
+            let dp = state.config.read().await.api.money_decimals;
+            let crypto_positions: Vec<Position> = positions.iter()
+                .filter(|p| {
+                    symbols::is_crypto_symbol(&p.symbol)
+                })
+                .map(|p| {
+                    let qty = p.qty.parse().unwrap_or(0.0);
+                    let entry = p.avg_entry_price.parse().unwrap_or(0.0);
+                    let (current, pnl) = mark_position(p, qty, entry, &marks);
+                    let market_value = qty * current;
+                    let pnl_percent = if entry > 0.0 { ((current - entry) / entry) * 100.0 } else { 0.0 };
+                    let note = state.notes.get(&p.symbol);
+                    
+                    Position {
+                        symbol: p.symbol.clone(),
+                        quantity: qty,
+                        entry_price: entry,
+                        current_price: current,
+                        pnl: money::round(pnl, dp),
+                        pnl_percent,
+                        market_value: money::round(market_value, dp),
+                        asset_type: "crypto".to_string(),
+                        note: note.as_ref().map(|n| n.note.clone()),
+                        tags: note.map(|n| n.tags).unwrap_or_default(),
+                    }
+                }).collect();
+            Json(crypto_positions)
+        },
+        Err(_) => Json(vec![]),
+    }
+}
+
+// Annotate a position. Tags are lowercase; "no-auto-sell" exempts the position
+// from automatic exits. An empty note with no tags clears the entry.
+async fn set_position_note(
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Json(payload): Json<NoteRequest>,
+) -> Json<serde_json::Value> {
+    let entry = state.notes.set(&symbol, payload.note, payload.tags);
+    info!("📝 Note updated for {}: {:?}", symbol, entry);
+    state.logger.info("Notes", &format!("📝 {} note updated", symbol));
+    Json(json!({ "symbol": symbol, "note": entry }))
+}
+
+// TEST DATA FUNCTIONS REMOVED - USING ONLY REAL ALPACA PAPER TRADING
+
+
+// Get current trading mode
+async fn get_trading_mode(State(state): State<AppState>) -> Json<TradingMode> {
+    let mode = state.trading_mode.read().await;
+    Json(mode.clone())
+}
+
+// Symbols the cycles trade right now, with any performance rotation swaps
+async fn get_universe(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let mode = state.trading_mode.read().await.clone();
+    let stocks = active_stocks(&state).await;
+    let rotation = state.rotation.read().await.clone();
+    Json(json!({
+        "mode": mode,
+        "stocks": stocks,
+        "crypto": mode.get_crypto(),
+        "rotation": rotation,
+    }))
+}
+
+// One versioned JSON document of all mutable engine state (no secrets)
+async fn export_state(State(state): State<AppState>) -> Json<EngineState> {
+    Json(EngineState::capture(&state).await)
+}
+
+// Restores an export in one step; nothing is applied unless the whole
+// document validates
+async fn import_state(
+    State(state): State<AppState>,
+    Json(imported): Json<EngineState>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    if let Err(reason) = imported.validate() {
+        warn!("⚠️  State import rejected: {}", reason);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": reason })));
+    }
+    let before = serde_json::to_value(&*state.config.read().await).unwrap_or_default();
+    let exported_at = imported.exported_at.clone();
+    let trades = imported.trade_history.len();
+    imported.apply(&state).await;
+    
+    let after = serde_json::to_value(&*state.config.read().await).unwrap_or_default();
+    state.config_history.record("state:import", before, after);
+    state.logger.success("System", &format!("📦 Imported engine state exported at {} ({} trades)", exported_at, trades));
+    info!("📦 Engine state imported (exported at {})", exported_at);
+    (StatusCode::OK, Json(json!({ "imported": true, "exported_at": exported_at, "trades": trades })))
+}
+
+// Set trading mode
+#[derive(Deserialize)]
+struct TradingModeRequest {
+    mode: TradingMode,
+}
+
+async fn set_trading_mode(
+    State(state): State<AppState>,
+    Json(req): Json<TradingModeRequest>,
+) -> StatusCode {
+    let mut mode = state.trading_mode.write().await;
+    let changed = *mode != req.mode;
+    *mode = req.mode.clone();
+    drop(mode);
+    if changed {
+        restart_grace_period(&state).await;
+    }
+    
+    state.logger.success("Config", &format!("Trading mode set to: {:?}", req.mode));
+    info!("🎯 Trading mode changed to: {:?}", req.mode);
+    
+    StatusCode::OK
+}
+
+async fn get_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<StrategyProfile>, StatusCode> {
+    match state.profiles.load(&name) {
+        Ok(Some(profile)) => Ok(Json(profile)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("❌ Failed to load profile {}: {}", name, e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+// Saves the posted profile, or a snapshot of the live settings if no body is sent
+async fn save_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    payload: Option<Json<StrategyProfile>>,
+) -> Result<Json<StrategyProfile>, StatusCode> {
+    let profile = match payload {
+        Some(Json(profile)) => profile,
+        None => StrategyProfile::capture(&*state.config.read().await),
+    };
+    if let Err(e) = state.profiles.save(&name, &profile) {
+        error!("❌ Failed to save profile {}: {}", name, e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    state.logger.info("Config", &format!("💾 Saved strategy profile '{}'", name));
+    Ok(Json(profile))
+}
+
+async fn activate_profile(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<StrategyProfile>, StatusCode> {
+    let profile = match state.profiles.load(&name) {
+        Ok(Some(profile)) => profile,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("❌ Failed to load profile {}: {}", name, e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    
+    // Swap under one write lock so a cycle never sees a half-applied profile
+    let (before, after) = {
+        let mut config = state.config.write().await;
+        let before = StrategyProfile::capture(&config);
+        profile.clone().apply(&mut config);
+        (before, StrategyProfile::capture(&config))
+    };
+    if let (Ok(before), Ok(after)) = (serde_json::to_value(before), serde_json::to_value(after)) {
+        state.config_history.record(&format!("profile:{}", name), before, after);
+    }
+    state.logger.success("Config", &format!("🎛️ Activated strategy profile '{}'", name));
+    info!("🎛️ Strategy profile '{}' activated", name);
+    Ok(Json(profile))
+}
+
+async fn get_config_history(State(state): State<AppState>) -> Json<Vec<ConfigChange>> {
+    Json(state.config_history.entries())
+}
+
+#[derive(Deserialize)]
+struct ConfigDiffQuery {
+    from: Option<u64>,  // Defaults to the version before `to`
+    to: Option<u64>,    // Defaults to the latest version
+}
+
+async fn get_config_diff(
+    State(state): State<AppState>,
+    Query(query): Query<ConfigDiffQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let latest = state.config_history.entries().last().map_or(0, |e| e.version);
+    let to = query.to.unwrap_or(latest);
+    let from = query.from.unwrap_or(to.saturating_sub(1));
+    let before = state.config_history.at_version(from).ok_or(StatusCode::NOT_FOUND)?;
+    let after = state.config_history.at_version(to).ok_or(StatusCode::NOT_FOUND)?;
+    
+    Ok(Json(json!({
+        "from": from,
+        "to": to,
+        "changes": config_history::diff(&before, &after),
+    })))
+}
+
+// Book profit for a single position
+async fn book_profit_single(
+    State(state): State<AppState>,
+    axum::extract::Path(symbol): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    info!("💰 Manual profit booking requested for {}", symbol);
+    
+    // Get position info BEFORE closing
+    let position_info = match state.alpaca.get_positions().await {
+        Ok(positions) => {
+            positions.iter()
+                .find(|p| p.symbol == symbol)
+                .map(|p| {
+                    let qty = p.qty.parse().unwrap_or(0.0);
+                    let entry = p.avg_entry_price.parse().unwrap_or(0.0);
+                    let current = p.current_price.parse().unwrap_or(0.0);
+                    let pnl = p.unrealized_pl.parse().unwrap_or(0.0);
+                    (qty, entry, current, pnl)
+                })
+        },
+        Err(_) => None,
+    };
+    
+    if position_info.is_none() {
+        error!("❌ Position not found: {}", symbol);
+        return Err(StatusCode::NOT_FOUND);
+    }
+    
+    let (qty, entry_price, current_price, pnl) = position_info.unwrap();
+    
+    // Check if it's crypto or stock
+    let is_crypto = symbols::is_crypto_symbol(&symbol);
+    
+    let result = if is_crypto {
+        state.crypto.close_crypto_position(&symbol).await
+    } else {
+        state.alpaca.close_position(&symbol).await
+    };
+    
+    match result {
+        Ok(close_order) => {
+            // Record the trade in history
+            let trade = TradeRecord {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: Utc::now().to_rfc3339(),
+                symbol: symbol.clone(),
+                action: "SELL".to_string(),
+                quantity: qty,
+                price: current_price,
+                pnl,
+                fill_price: None,
+                slippage_flagged: false,
+                slippage_pct: None,
+                reason: Some(TradeReason::Manual),
+                asset_class: symbols::asset_class(&symbol).to_string(),
+            };
+            tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), close_order.id, entry_price));
+            state.trade_history.write().await.push(trade);
+            
+            state.logger.success(
+                "Manual Profit", 
+                &format!("💰 {} position closed by user - P&L: ${:.2}", symbol, pnl)
+            );
+            info!("✅ Manual profit booked: {} - ${:.2}", symbol, pnl);
+            
+            Ok(Json(json!({
+                "success": true,
+                "symbol": symbol,
+                "pnl": pnl,
+                "message": "Position closed successfully"
+            })))
+        },
+        Err(e) => {
+            error!("❌ Failed to book profit for {}: {}", symbol, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// Book profits for ALL positions
+// Closes one position at market and records the SELL, with the realized
+// P&L reconciled from the fill in the background. Returns the unrealized
+// P&L at the time of closing.
+async fn close_and_record(state: &AppState, pos: &alpaca::Position) -> anyhow::Result<f64> {
+    let qty = pos.qty.parse().unwrap_or(0.0);
+    let entry = pos.avg_entry_price.parse().unwrap_or(0.0);
+    let current = pos.current_price.parse().unwrap_or(0.0);
+    let pnl = pos.unrealized_pl.parse().unwrap_or(0.0);
+    
+    let close_order = if symbols::is_crypto_symbol(&pos.symbol) {
+        state.crypto.close_crypto_position(&pos.symbol).await?
+    } else {
+        state.alpaca.close_position(&pos.symbol).await?
+    };
+    
+    // Record trade in history
+    let trade = TradeRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+        symbol: pos.symbol.clone(),
+        action: "SELL".to_string(),
+        quantity: qty,
+        price: current,
+        pnl,
+        fill_price: None,
+        slippage_flagged: false,
+        slippage_pct: None,
+        reason: Some(TradeReason::Manual),
+        asset_class: symbols::asset_class(&pos.symbol).to_string(),
+    };
+    tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), close_order.id, entry));
+    state.trade_history.write().await.push(trade);
+    Ok(pnl)
+}
+
+// Every open position: what POST /book-all-profits closes and what its
+// preview lists
+async fn positions_to_book(state: &AppState) -> Result<Vec<alpaca::Position>, StatusCode> {
+    state.alpaca.get_positions().await.map_err(|e| {
+        error!("❌ Failed to get positions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// What POST /book-all-profits would close right now and the P&L it would
+// book, without placing any orders
+async fn preview_book_all_profits(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    let positions = positions_to_book(&state).await?;
+    let dp = state.config.read().await.api.money_decimals;
+    
+    // Outcomes and counts both read the unrounded P&L
+    let pnls: Vec<f64> = positions.iter().map(|p| p.unrealized_pl.parse().unwrap_or(0.0)).collect();
+    let outcome = |pnl: f64| if pnl > 0.0 { "gain" } else if pnl < 0.0 { "loss" } else { "flat" };
+    let preview: Vec<serde_json::Value> = positions.iter()
+        .zip(&pnls)
+        .map(|(p, pnl)| json!({ "position": enrich_position(&state, p, &HashMap::new(), dp), "outcome": outcome(*pnl) }))
+        .collect();
+    
+    Ok(Json(json!({
+        "would_close": preview.len(),
+        "gains": pnls.iter().filter(|p| outcome(**p) == "gain").count(),
+        "losses": pnls.iter().filter(|p| outcome(**p) == "loss").count(),
+        "total_pnl": money::round(pnls.iter().sum(), dp),
+        "positions": preview,
+    })))
+}
+
+async fn book_all_profits(State(state): State<AppState>) -> Result<Json<serde_json::Value>, StatusCode> {
+    info!("💰💰💰 Manual profit booking requested for ALL positions");
+    
+    let mut closed_count = 0;
+    let mut failed_count = 0;
+    let mut closed_symbols = Vec::new();
+    let mut total_pnl = 0.0;
+    
+    for pos in positions_to_book(&state).await? {
+        match close_and_record(&state, &pos).await {
+            Ok(pnl) => {
+                closed_count += 1;
+                closed_symbols.push(pos.symbol.clone());
+                total_pnl += pnl;
+                info!("✅ Closed {} - P&L: ${:.2}", pos.symbol, pnl);
+            },
+            Err(e) => {
+                failed_count += 1;
+                error!("❌ Failed to close {}: {}", pos.symbol, e);
+            }
+        }
+        
+        // Small delay between orders
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    
+    state.logger.success(
+        "Manual Profit", 
+        &format!("💰 Closed {} positions - Total P&L: ${:.2}", closed_count, total_pnl)
+    );
+    
+    Ok(Json(json!({
+        "success": true,
+        "closed_count": closed_count,
+        "failed_count": failed_count,
+        "total_pnl": total_pnl,
+        "closed_symbols": closed_symbols
+    })))
+}
+
+#[derive(Deserialize)]
+struct CloseAllQuery {
+    asset_class: Option<String>,  // "stock", "crypto" or "all" (default)
+}
+
+#[derive(Serialize)]
+struct CloseResult {
+    symbol: String,
+    closed: bool,
+    pnl: Option<f64>,
+    error: Option<String>,
+}
+
+// Flattens one asset class, e.g. stocks before a weekend while crypto keeps running
+async fn close_all_positions(
+    State(state): State<AppState>,
+    Query(query): Query<CloseAllQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let asset_class = query.asset_class.unwrap_or_else(|| "all".to_string()).to_lowercase();
+    if !matches!(asset_class.as_str(), "stock" | "crypto" | "all") {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let wanted = |symbol: &str| match asset_class.as_str() {
+        "stock" => !symbols::is_crypto_symbol(symbol),
+        "crypto" => symbols::is_crypto_symbol(symbol),
+        _ => true,
+    };
+    info!("🧹 Close-all requested for {} positions", asset_class);
+    
+    let positions = state.alpaca.get_positions().await.map_err(|e| {
+        error!("❌ Failed to get positions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    let mut results = Vec::new();
+    let mut total_pnl = 0.0;
+    for pos in positions.iter().filter(|p| wanted(&p.symbol)) {
+        let result = match close_and_record(&state, pos).await {
+            Ok(pnl) => {
+                total_pnl += pnl;
+                info!("✅ Closed {} - P&L: ${:.2}", pos.symbol, pnl);
+                CloseResult { symbol: pos.symbol.clone(), closed: true, pnl: Some(pnl), error: None }
+            }
+            Err(e) => {
+                error!("❌ Failed to close {}: {}", pos.symbol, e);
+                CloseResult { symbol: pos.symbol.clone(), closed: false, pnl: None, error: Some(e.to_string()) }
+            }
+        };
+        results.push(result);
+        
+        // Small delay between orders
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    
+    let closed_count = results.iter().filter(|r| r.closed).count();
+    state.logger.success(
+        "Manual Profit",
+        &format!("🧹 Closed {} {} positions - Total P&L: ${:.2}", closed_count, asset_class, total_pnl)
+    );
+    
+    Ok(Json(json!({
+        "asset_class": asset_class,
+        "closed_count": closed_count,
+        "failed_count": results.len() - closed_count,
+        "total_pnl": total_pnl,
+        "results": results,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use std::sync::{Arc, Mutex};
+
+    fn stock_position(symbol: &str, qty: f64, entry: f64, current: f64) -> serde_json::Value {
+        json!({
+            "symbol": symbol, "qty": qty.to_string(), "avg_entry_price": entry.to_string(),
+            "current_price": current.to_string(), "unrealized_pl": ((current - entry) * qty).to_string(),
+            "asset_class": "us_equity",
+        })
+    }
+
+    fn crypto_position(symbol: &str, qty: f64, entry: f64, current: f64) -> serde_json::Value {
+        json!({
+            "symbol": symbol, "qty": qty.to_string(), "avg_entry_price": entry.to_string(),
+            "current_price": current.to_string(), "unrealized_pl": ((current - entry) * qty).to_string(),
+            "asset_class": "crypto",
+        })
+    }
+
+    fn trade(symbol: &str, action: &str, pnl: f64, reason: TradeReason) -> TradeRecord {
+        TradeRecord { pnl, reason: Some(reason), ..test_support::trade(symbol, action, 1.0, 100.0, Utc::now()) }
+    }
+
+    // Alpaca holding `positions`; returns its URL and the symbols it was asked to close
+    async fn mock_closes(positions: serde_json::Value) -> (String, Arc<Mutex<Vec<String>>>) {
+        let closed = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route("/v2/positions", get(move || async move { Json(positions) }))
+            .route("/v2/positions/:symbol", axum::routing::delete({
+                let closed = closed.clone();
+                move |Path(symbol): Path<String>| async move {
+                    closed.lock().unwrap().push(symbol.clone());
+                    Json(json!({ "id": format!("close-{}", symbol), "symbol": symbol, "qty": "1", "side": "sell", "order_type": "market", "status": "accepted" }))
+                }
+            }));
+        (test_support::serve(app).await, closed)
+    }
+
+    #[tokio::test]
+    async fn sizer_reports_the_binding_constraint() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.instruments.max_adv_pct = 0.0;
+            config.risk_sizing.risk_per_trade_usd = 0.0;
+        }
+        let size = |buying_power: f64| preview_entry_size(state, "AAPL", 100.0, false, buying_power, &[], &[]);
+
+        let small = size(10_000.0).await;
+        assert_eq!((small.qty, small.binding), (5.0, "buying_power_pct"));
+        let large = size(1_000_000.0).await;
+        assert_eq!((large.qty, large.binding), (50.0, "max_notional"));
+
+        state.config.write().await.instruments.max_units.insert("AAPL".to_string(), 3.0);
+        let capped = size(1_000_000.0).await;
+        assert_eq!((capped.qty, capped.binding), (3.0, "unit_cap"));
+    }
+
+    #[tokio::test]
+    async fn live_positions_are_re_marked() {
+        let app = Router::new()
+            .route("/v2/positions", get(|| async {
+                Json(json!([stock_position("AAPL", 10.0, 90.0, 100.0), stock_position("MSFT", 5.0, 200.0, 200.0)]))
+            }))
+            .route("/v2/stocks/trades/latest", get(|| async { Json(json!({ "trades": { "MSFT": { "p": 210.0 } } })) }));
+        let engine = test_support::engine(&test_support::serve(app).await);
+        let state = engine.state().clone();
+        state.live_prices.update("AAPL", 120.0);
+        let by_symbol = |positions: Vec<Position>| -> HashMap<String, (f64, f64)> {
+            positions.into_iter().map(|p| (p.symbol, (p.current_price, p.pnl))).collect()
+        };
+
+        let Json(snapshot) = get_positions(State(state.clone()), Query(PositionsQuery { live: false })).await;
+        let snapshot = by_symbol(snapshot);
+        assert_eq!(snapshot["AAPL"], (100.0, 100.0));
+        assert_eq!(snapshot["MSFT"], (200.0, 0.0));
+
+        // AAPL from the stream, MSFT from a latest-trade lookup
+        let Json(live) = get_positions(State(state), Query(PositionsQuery { live: true })).await;
+        let live = by_symbol(live);
+        assert_eq!(live["AAPL"], (120.0, 300.0));
+        assert_eq!(live["MSFT"], (210.0, 50.0));
+    }
+
+    // Closes whose returns are `scale` times a fixed zig-zag of benchmark moves
+    fn scaled_closes(scale: f64) -> Vec<f64> {
+        (0..50).scan(100.0, |price, i| {
+            *price *= 1.0 + scale * [0.01, -0.005, 0.002, -0.008, 0.006][i % 5];
+            Some(*price)
+        }).collect()
+    }
+
+    #[tokio::test]
+    async fn sells_aggregate_by_exit_reason() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state().clone();
+        *state.trade_history.write().await = vec![
+            trade("AAPL", "BUY", 0.0, TradeReason::Signal),
+            trade("AAPL", "SELL", 50.0, TradeReason::ProfitTake),
+            trade("MSFT", "SELL", 30.0, TradeReason::ProfitTake),
+            trade("NVDA", "SELL", -40.0, TradeReason::Stop),
+            trade("TSLA", "SELL", -10.0, TradeReason::Signal),
+            trade("AMD", "SELL", 25.0, TradeReason::Signal),
+            TradeRecord { reason: None, ..trade("F", "SELL", -5.0, TradeReason::Signal) },
+        ];
+
+        let Json(groups) = get_analytics_by_reason(State(state)).await;
+        let summary: Vec<(&str, usize, f64, f64)> = groups.iter()
+            .map(|(reason, g)| (reason.as_str(), g.trades, g.realized_pnl, g.win_rate))
+            .collect();
+        assert_eq!(summary, vec![
+            ("profit_take", 2, 80.0, 1.0),
+            ("signal", 2, 15.0, 0.5),
+            ("stop", 1, -40.0, 0.0),
+            ("unknown", 1, -5.0, 0.0),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn trade_history_filters_by_asset_class() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state().clone();
+        *state.trade_history.write().await = vec![
+            trade("AAPL", "BUY", 0.0, TradeReason::Signal),
+            trade("BTC/USD", "BUY", 0.0, TradeReason::Signal),
+            // Recorded before trades carried their class
+            TradeRecord { asset_class: String::new(), ..trade("ETHUSD", "SELL", 12.0, TradeReason::Signal) },
+        ];
+        let symbols_for = |asset_class: &str| {
+            let state = state.clone();
+            let query = TradeHistoryQuery { asset_class: Some(asset_class.to_string()) };
+            async move {
+                let Json(trades) = get_trade_history(State(state), Query(query)).await.unwrap();
+                trades.into_iter().map(|t| t.symbol).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(symbols_for("stock").await, vec!["AAPL"]);
+        assert_eq!(symbols_for("Crypto").await, vec!["BTC/USD", "ETHUSD"]);
+        assert_eq!(symbols_for("all").await.len(), 3);
+        let query = TradeHistoryQuery { asset_class: Some("bonds".to_string()) };
+        assert!(matches!(get_trade_history(State(state), Query(query)).await, Err(StatusCode::BAD_REQUEST)));
+    }
+
+    #[tokio::test]
+    async fn portfolio_beta_is_value_weighted() {
+        let app = Router::new()
+            .route("/v2/positions", get(|| async {
+                Json(json!([
+                    stock_position("AAPL", 10.0, 100.0, 100.0),
+                    stock_position("KO", 30.0, 100.0, 100.0),
+                    stock_position("XOM", 10.0, 100.0, 100.0),
+                ]))
+            }))
+            .route("/v2/stocks/:symbol/bars", get(|| async {
+                let bars: Vec<serde_json::Value> = scaled_closes(1.0).iter().enumerate()
+                    .map(|(i, c)| json!({ "t": format!("2024-06-12T{:02}:{:02}:00Z", 10 + i / 12, i % 12 * 5), "o": c, "h": c, "l": c, "c": c, "v": 1000 }))
+                    .collect();
+                Json(json!({ "bars": bars }))
+            }));
+        let engine = test_support::engine(&test_support::serve(app).await);
+        let state = engine.state().clone();
+        state.config.write().await.portfolio.beta_benchmark = "SPY".to_string();
+        state.correlations.record("AAPL", &scaled_closes(2.0));
+        state.correlations.record("KO", &scaled_closes(0.5));
+
+        let Json(beta) = get_portfolio_beta(State(state)).await.unwrap();
+        // ($1,000 x 2.0 + $3,000 x 0.5) / $4,000; XOM has no returns yet
+        assert!((beta["beta"].as_f64().unwrap() - 0.875).abs() < 1e-9, "{}", beta);
+        assert_eq!(beta["coverage"], 0.8);
+        assert_eq!(beta["excluded"], json!(["XOM"]));
+    }
+
+    #[tokio::test]
+    async fn close_all_can_flatten_only_crypto() {
+        let (host, closed) = mock_closes(json!([
+            stock_position("AAPL", 10.0, 100.0, 110.0),
+            crypto_position("BTCUSD", 0.1, 60_000.0, 61_000.0),
+            crypto_position("ETHUSD", 1.0, 3_000.0, 2_900.0),
+        ])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state().clone();
+
+        let query = CloseAllQuery { asset_class: Some("crypto".to_string()) };
+        let Json(result) = close_all_positions(State(state.clone()), Query(query)).await.unwrap();
+        assert_eq!(*closed.lock().unwrap(), vec!["BTCUSD", "ETHUSD"]);
+        assert_eq!(result["closed_count"], 2);
+        assert_eq!(result["total_pnl"], 0.0);
+        assert!(state.trade_history.read().await.iter().all(|t| t.asset_class == "crypto"));
+
+        let query = CloseAllQuery { asset_class: Some("bonds".to_string()) };
+        assert_eq!(close_all_positions(State(state), Query(query)).await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn book_all_preview_lists_what_booking_closes_without_orders() {
+        let (host, closed) = mock_closes(json!([
+            stock_position("AAPL", 10.0, 100.0, 110.0),
+            stock_position("F", 1.0, 12.0, 12.004),
+            crypto_position("ETHUSD", 1.0, 3_000.0, 2_900.0),
+        ])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state().clone();
+
+        let Json(preview) = preview_book_all_profits(State(state.clone())).await.unwrap();
+        assert!(closed.lock().unwrap().is_empty());
+        let outcomes: Vec<(&str, &str)> = preview["positions"].as_array().unwrap().iter()
+            .map(|p| (p["position"]["symbol"].as_str().unwrap(), p["outcome"].as_str().unwrap()))
+            .collect();
+        // Under a cent, but still a gain
+        assert_eq!(outcomes, vec![("AAPL", "gain"), ("F", "gain"), ("ETHUSD", "loss")]);
+        assert_eq!((preview["gains"].as_u64(), preview["losses"].as_u64()), (Some(2), Some(1)));
+
+        let Json(booked) = book_all_profits(State(state)).await.unwrap();
+        let listed: Vec<&str> = outcomes.iter().map(|(symbol, _)| *symbol).collect();
+        assert_eq!(*closed.lock().unwrap(), listed);
+        assert_eq!(booked["closed_symbols"], json!(listed));
+        assert_eq!(booked["total_pnl"].as_f64().unwrap().round(), preview["total_pnl"].as_f64().unwrap().round());
+    }
+
+    #[tokio::test]
+    async fn cached_simulation_makes_no_alpaca_calls() {
+        let calls = Arc::new(Mutex::new(0));
+        let app = Router::new().fallback({
+            let calls = calls.clone();
+            move || async move {
+                *calls.lock().unwrap() += 1;
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        });
+        let engine = test_support::engine(&test_support::serve(app).await);
+        let state = engine.state().clone();
+        state.last_signals.insert("BTCUSD".to_string(), crate::engine::CachedSignal {
+            computed_at: Utc::now(),
+            price: 61_000.0,
+            signal: 0.42,
+            sentiment: 0.1,
+            explanation: None,
+            bars: vec![],
+        });
+
+        let cached = SimulateSignalQuery { cached: true };
+        let Json(result) = simulate_signal(State(state.clone()), Path("btc/usd".to_string()), Query(cached)).await.unwrap();
+        assert_eq!((result["source"].as_str(), result["signal"].as_f64()), (Some("cached"), Some(0.42)));
+        let missing = simulate_signal(State(state.clone()), Path("ETHUSD".to_string()), Query(SimulateSignalQuery { cached: true })).await;
+        assert_eq!(missing.unwrap_err(), StatusCode::NOT_FOUND);
+        assert_eq!(*calls.lock().unwrap(), 0);
+
+        // Live mode does go to Alpaca
+        let live = simulate_signal(State(state), Path("AAPL".to_string()), Query(SimulateSignalQuery { cached: false })).await;
+        assert_eq!(live.unwrap_err(), StatusCode::BAD_GATEWAY);
+        assert!(*calls.lock().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn heatmap_covers_the_whole_universe_from_the_cache() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state().clone();
+        let universe: Vec<String> = active_stocks(&state).await.into_iter()
+            .chain(state.trading_mode.read().await.get_crypto().into_iter().map(String::from))
+            .collect();
+        let (stock, coin) = (universe[0].clone(), universe.last().unwrap().clone());
+        assert!(symbols::is_crypto_symbol(&coin));
+        let cached = |signal: f64, age_secs: i64| crate::engine::CachedSignal {
+            computed_at: Utc::now() - chrono::Duration::seconds(age_secs),
+            price: 100.0,
+            signal,
+            sentiment: 0.0,
+            explanation: None,
+            bars: vec![],
+        };
+        state.last_signals.insert(symbols::normalize(&stock), cached(0.9, 10));
+        state.last_signals.insert(symbols::normalize(&coin), cached(-0.9, 100_000));
+
+        let Json(heatmap) = get_signal_heatmap(State(state)).await;
+        let cells = heatmap["symbols"].as_array().unwrap();
+        let listed: Vec<&str> = cells.iter().map(|c| c["symbol"].as_str().unwrap()).collect();
+        assert_eq!(listed, universe);
+        let cell = |symbol: &str| cells.iter().find(|c| c["symbol"] == symbol).unwrap();
+        assert_eq!((cell(&stock)["signal"].as_f64(), cell(&stock)["zone"].as_str(), cell(&stock)["stale"].as_bool()), (Some(0.9), Some("buy"), Some(false)));
+        assert_eq!((cell(&coin)["zone"].as_str(), cell(&coin)["stale"].as_bool()), (Some("sell"), Some(true)));
+        assert_eq!((cell(&universe[1])["zone"].as_str(), cell(&universe[1])["stale"].as_bool()), (Some("no_data"), Some(true)));
+    }
+
+    #[tokio::test]
+    async fn compare_scores_two_windows_of_the_same_bars() {
+        // 30 bars sliding, then 20 climbing
+        let app = Router::new()
+            .route("/v2/stocks/:symbol/trades/latest", get(|| async { Json(json!({ "trade": { "p": 110.0 } })) }))
+            .route("/v2/stocks/:symbol/bars", get(|| async {
+                let bars: Vec<serde_json::Value> = (0..50)
+                    .map(|i| {
+                        let c = if i < 30 { 120.0 - i as f64 } else { 90.0 + (i - 30) as f64 };
+                        json!({ "t": format!("2024-06-12T14:{:02}:00Z", i), "o": c, "h": c + 0.5, "l": c - 0.5, "c": c, "v": 1_000 })
+                    })
+                    .collect();
+                Json(json!({ "bars": bars }))
+            }));
+        let engine = test_support::engine(&test_support::serve(app).await);
+        let request = json!({ "symbol": "aapl", "a": { "bars": 20 }, "b": { "bars": 50 } });
+
+        let Json(result) = compare_signals(State(engine.state().clone()), Json(serde_json::from_value(request).unwrap())).await.unwrap();
+        let (a, b) = (&result["a"], &result["b"]);
+        assert_eq!((a["bars_used"].as_u64(), b["bars_used"].as_u64()), (Some(20), Some(50)));
+        // Twenty bars can't fill the 50-bar average; fifty can
+        assert_eq!(a["explanation"]["omitted"][0]["name"], "sma_50");
+        assert_eq!(b["explanation"]["omitted"], json!([]));
+        // Only the long window sees the 20-bar average still under the 50-bar one
+        assert_eq!(a["explanation"]["crossover_score"].as_f64(), Some(0.0));
+        assert!(b["explanation"]["crossover_score"].as_f64().unwrap() < 0.0);
+        // RSI and momentum read the same recent bars either way, noise is shared
+        for component in ["rsi_score", "momentum_score", "noise"] {
+            assert_eq!(a["explanation"][component], b["explanation"][component]);
+        }
+        let difference = result["difference"].as_f64().unwrap();
+        assert!((difference - (b["signal"].as_f64().unwrap() - a["signal"].as_f64().unwrap())).abs() < 1e-12);
+    }
+
+    // Status of `method path` through the full router, with `token` as the bearer
+    async fn status_with(app: &Router, method: Method, path: &str, token: Option<&str>) -> StatusCode {
+        use tower::ServiceExt;
+        let mut request = axum::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = axum::body::Body::from(r#"{"enabled":false}"#);
+        app.clone().oneshot(request.body(body).unwrap()).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn read_token_reads_and_admin_token_writes() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        {
+            let mut config = engine.state().config.write().await;
+            config.api.read_token = "reader".to_string();
+            config.api.admin_token = "admin".to_string();
+        }
+        let app = router(engine.state().clone());
+
+        assert_eq!(status_with(&app, Method::GET, "/", Some("reader")).await, StatusCode::OK);
+        assert_eq!(status_with(&app, Method::POST, "/toggle", Some("reader")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status_with(&app, Method::DELETE, "/toggle", Some("reader")).await, StatusCode::FORBIDDEN);
+        assert_eq!(status_with(&app, Method::POST, "/toggle", Some("admin")).await, StatusCode::OK);
+        assert!(!*engine.state().trading_enabled.read().await);
+        assert_eq!(status_with(&app, Method::GET, "/", Some("admin")).await, StatusCode::OK);
+
+        assert_eq!(status_with(&app, Method::GET, "/", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with(&app, Method::GET, "/", Some("guess")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with(&app, Method::GET, "/health", None).await, StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn maintenance_mode_pauses_cycles_but_serves_reads() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state().clone();
+        let app = router(state.clone());
+        let run_crypto = || run_cycle_now(State(state.clone()), Query(RunCycleQuery { asset_class: "crypto".to_string() }));
+
+        assert_eq!(set_maintenance_mode(State(state.clone()), Json(ToggleRequest { enabled: true })).await, StatusCode::OK);
+        let (code, Json(body)) = run_crypto().await;
+        assert_eq!(code, StatusCode::CONFLICT);
+        assert_eq!(body["reason"], "maintenance mode is on");
+        assert!(state.last_signals.is_empty());
+        assert_eq!(status_with(&app, Method::GET, "/", None).await, StatusCode::OK);
+        assert_eq!(status_with(&app, Method::GET, "/status", None).await, StatusCode::OK);
+        assert_eq!(status_with(&app, Method::GET, "/logs", None).await, StatusCode::OK);
+
+        set_maintenance_mode(State(state.clone()), Json(ToggleRequest { enabled: false })).await;
+        let (code, Json(body)) = run_crypto().await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(body["ran"], true);
+    }
+
+    #[tokio::test]
+    async fn decide_answers_hypothetical_inputs() {
+        let engine = test_support::engine("http://127.0.0.1:9");
+        let state = engine.state().clone();
+        {
+            let mut config = state.config.write().await;
+            config.thresholds.stock_buy = 0.15;
+            config.thresholds.stock_sell = -0.15;
+            config.thresholds.entry_confirmation_cycles = 2;
+            config.profit_target.stock_pct = 15.0;
+            config.profit_target.atr_modes.clear();
+        }
+        let ask = |request: serde_json::Value| {
+            let state = state.clone();
+            async move {
+                let Json(answer) = decide(State(state), Json(serde_json::from_value(request).unwrap())).await;
+                (answer["action"].as_str().unwrap().to_string(), answer["reason"].as_str().unwrap().to_string())
+            }
+        };
+        let expect = |action: &str, reason: &str| (action.to_string(), reason.to_string());
+
+        assert_eq!(ask(json!({ "symbol": "aapl", "signal": 0.4 })).await, expect("buy", "signal"));
+        assert_eq!(ask(json!({ "symbol": "AAPL", "signal": 0.4, "buy_streak": 1 })).await, expect("hold", "awaiting_confirmation"));
+        assert_eq!(ask(json!({ "symbol": "AAPL", "signal": 0.0, "has_position": true, "profit_percent": 20.0 })).await, expect("sell", "profit_target"));
+        assert_eq!(ask(json!({ "symbol": "AAPL", "signal": -0.4, "has_position": true })).await, expect("sell", "signal"));
+
+        state.notes.set("AAPL", String::new(), vec![NO_AUTO_SELL.to_string()]);
+        assert_eq!(ask(json!({ "symbol": "AAPL", "signal": -0.4, "has_position": true })).await, expect("hold", "no_auto_sell"));
+    }
+}
@@ -0,0 +1,92 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::ApprovalConfig;
+
+// What the approval webhook is asked about
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub symbol: String,
+    pub side: String,  // "buy" or "sell"
+    pub qty: f64,
+    pub price: f64,
+    pub signal: f64,
+}
+
+#[derive(Deserialize)]
+struct ApprovalResponse {
+    approved: bool,
+}
+
+// Human-in-the-loop gate: POSTs each trade to APPROVAL_WEBHOOK_URL and waits
+// for {"approved": true|false}. No answer within the timeout (or an error, or
+// an unreadable reply) falls back to APPROVAL_DEFAULT_ALLOW.
+#[derive(Clone, Default)]
+pub struct ApprovalGate {
+    client: Client,
+}
+
+impl ApprovalGate {
+    pub async fn approve(&self, config: &ApprovalConfig, request: &ApprovalRequest) -> bool {
+        if config.webhook_url.is_empty() {
+            return true;
+        }
+        let response = self.client
+            .post(&config.webhook_url)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .json(request)
+            .send()
+            .await;
+        let verdict = match response {
+            Ok(r) if r.status().is_success() => r.json::<ApprovalResponse>().await.map(|a| a.approved).ok(),
+            Ok(r) => {
+                warn!("⚠️  Approval webhook returned {} for {} {}", r.status(), request.side, request.symbol);
+                None
+            }
+            Err(e) => {
+                warn!("⚠️  Approval webhook failed for {} {}: {}", request.side, request.symbol, e);
+                None
+            }
+        };
+        verdict.unwrap_or(config.default_allow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+
+    fn request(symbol: &str) -> ApprovalRequest {
+        ApprovalRequest { symbol: symbol.to_string(), side: "buy".to_string(), qty: 10.0, price: 20.0, signal: 0.5 }
+    }
+
+    #[tokio::test]
+    async fn webhook_verdict_decides_and_failures_use_the_default() {
+        // Denies GME, approves everything else
+        let app = Router::new()
+            .route("/approve", post(|Json(request): Json<Value>| async move {
+                Json(json!({ "approved": request["symbol"] != "GME" }))
+            }))
+            .route("/broken", post(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }));
+        let host = test_support::serve(app).await;
+        let gate = ApprovalGate::default();
+        let config = |path: &str, default_allow: bool| ApprovalConfig {
+            webhook_url: format!("{}{}", host, path),
+            timeout_secs: 5,
+            default_allow,
+        };
+
+        assert!(!gate.approve(&config("/approve", true), &request("GME")).await);
+        assert!(gate.approve(&config("/approve", false), &request("AAPL")).await);
+        assert!(!gate.approve(&config("/broken", false), &request("AAPL")).await);
+        assert!(gate.approve(&config("/broken", true), &request("AAPL")).await);
+        // No webhook configured: everything goes through
+        assert!(gate.approve(&ApprovalConfig { webhook_url: String::new(), ..config("", false) }, &request("GME")).await);
+    }
+}
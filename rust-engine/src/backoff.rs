@@ -0,0 +1,156 @@
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolBackoff {
+    pub symbol: String,
+    pub consecutive_failures: u32,
+    pub retry_after: Option<String>,
+    pub last_error: String,
+}
+
+#[derive(Debug, Clone)]
+struct FailureState {
+    consecutive_failures: u32,
+    retry_after: Option<DateTime<Utc>>,
+    last_error: String,
+}
+
+// Failure-driven backoff: after `threshold` consecutive order failures a symbol
+// is skipped for base_secs, doubling with each further failure up to max_secs.
+// A successful order resets it.
+pub struct OrderBackoff {
+    failures: Arc<DashMap<String, FailureState>>,
+    threshold: u32,
+    base_secs: i64,
+    max_secs: i64,
+}
+
+impl OrderBackoff {
+    pub fn new(threshold: u32, base_secs: i64, max_secs: i64) -> Self {
+        Self {
+            failures: Arc::new(DashMap::new()),
+            threshold: threshold.max(1),
+            base_secs,
+            max_secs,
+        }
+    }
+
+    // Records a failure and returns the backoff applied (in seconds), if any
+    pub fn record_failure(&self, symbol: &str, error: &str) -> Option<i64> {
+        let mut entry = self.failures.entry(symbol.to_string()).or_insert(FailureState {
+            consecutive_failures: 0,
+            retry_after: None,
+            last_error: String::new(),
+        });
+        entry.consecutive_failures += 1;
+        entry.last_error = error.to_string();
+
+        if entry.consecutive_failures < self.threshold {
+            return None;
+        }
+
+        let exponent = (entry.consecutive_failures - self.threshold).min(16);
+        let secs = (self.base_secs * 2_i64.pow(exponent)).min(self.max_secs);
+        entry.retry_after = Some(Utc::now() + Duration::seconds(secs));
+        Some(secs)
+    }
+
+    pub fn record_success(&self, symbol: &str) {
+        self.failures.remove(symbol);
+    }
+
+    // Seconds remaining before the symbol may be retried, if it's backed off
+    pub fn remaining_secs(&self, symbol: &str) -> Option<i64> {
+        let state = self.failures.get(symbol)?;
+        let remaining = (state.retry_after? - Utc::now()).num_seconds();
+        (remaining > 0).then_some(remaining)
+    }
+
+    pub fn snapshot(&self) -> Vec<SymbolBackoff> {
+        let mut entries: Vec<SymbolBackoff> = self.failures.iter()
+            .map(|entry| SymbolBackoff {
+                symbol: entry.key().clone(),
+                consecutive_failures: entry.consecutive_failures,
+                retry_after: entry.retry_after.map(|t| t.to_rfc3339()),
+                last_error: entry.last_error.clone(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        entries
+    }
+
+    // Replaces every entry with `entries` (a snapshot, e.g. from a state
+    // import). An unparseable retry_after leaves the symbol counting failures
+    // but not backed off.
+    pub fn restore(&self, entries: Vec<SymbolBackoff>) {
+        self.failures.clear();
+        for entry in entries {
+            self.failures.insert(entry.symbol, FailureState {
+                consecutive_failures: entry.consecutive_failures,
+                retry_after: entry.retry_after
+                    .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                    .map(|t| t.with_timezone(&Utc)),
+                last_error: entry.last_error,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_after_threshold_failures() {
+        let backoff = OrderBackoff::new(3, 60, 600);
+        assert_eq!(backoff.record_failure("AAPL", "rejected"), None);
+        assert_eq!(backoff.record_failure("AAPL", "rejected"), None);
+        assert!(backoff.remaining_secs("AAPL").is_none());
+
+        assert_eq!(backoff.record_failure("AAPL", "rejected"), Some(60));
+        let remaining = backoff.remaining_secs("AAPL").unwrap();
+        assert!(remaining > 0 && remaining <= 60);
+        assert!(backoff.remaining_secs("MSFT").is_none());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let backoff = OrderBackoff::new(1, 60, 200);
+        assert_eq!(backoff.record_failure("AAPL", "e"), Some(60));
+        assert_eq!(backoff.record_failure("AAPL", "e"), Some(120));
+        assert_eq!(backoff.record_failure("AAPL", "e"), Some(200));
+    }
+
+    #[test]
+    fn success_clears_backoff() {
+        let backoff = OrderBackoff::new(2, 60, 600);
+        backoff.record_failure("AAPL", "e");
+        backoff.record_failure("AAPL", "e");
+        assert!(backoff.remaining_secs("AAPL").is_some());
+
+        backoff.record_success("AAPL");
+        assert!(backoff.remaining_secs("AAPL").is_none());
+        assert!(backoff.snapshot().is_empty());
+    }
+
+    #[test]
+    fn restore_round_trips_a_snapshot() {
+        let backoff = OrderBackoff::new(1, 60, 600);
+        backoff.record_failure("AAPL", "rejected");
+        backoff.record_failure("MSFT", "rejected");
+
+        let restored = OrderBackoff::new(1, 60, 600);
+        restored.record_failure("TSLA", "rejected");
+        restored.restore(backoff.snapshot());
+
+        assert_eq!(
+            serde_json::to_value(restored.snapshot()).unwrap(),
+            serde_json::to_value(backoff.snapshot()).unwrap(),
+        );
+        assert!(restored.remaining_secs("AAPL").is_some());
+        assert!(restored.remaining_secs("TSLA").is_none());
+    }
+}
@@ -0,0 +1,798 @@
+use serde::{Deserialize, Serialize};
+
+use crate::alpaca::BarAdjustment;
+use crate::technical::MovingAverage;
+use crate::decision::Thresholds;
+use crate::symbols;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+// Engine tunables, loaded once at startup from the environment (.env supported)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(skip)]
+    pub credentials: Credentials,
+    pub market_stream: MarketStreamConfig,
+    pub instruments: InstrumentConfig,
+    pub order_backoff: OrderBackoffConfig,
+    pub indicators: IndicatorConfig,
+    pub portfolio: PortfolioConfig,
+    pub thresholds: ThresholdConfig,
+    pub metrics: MetricsConfig,
+    pub data: DataConfig,
+    pub slippage: SlippageConfig,
+    pub strategy: StrategyConfig,
+    pub reconcile: ReconcileConfig,
+    pub profit_target: ProfitTargetConfig,
+    pub session: SessionConfig,
+    pub crypto_hours: CryptoHoursConfig,
+    pub logging: LoggingConfig,
+    pub crypto_exit: CryptoExitConfig,
+    pub equity_floor: EquityFloorConfig,
+    pub persistence: PersistenceConfig,
+    pub halts: HaltConfig,
+    pub news: NewsConfig,
+    pub correlation: CorrelationConfig,
+    pub replay: ReplayConfig,
+    pub recorder: RecorderConfig,
+    pub api: ApiConfig,
+    pub approval: ApprovalConfig,
+    pub mode_schedule: ModeScheduleConfig,
+    pub drawdown_alerts: DrawdownAlertConfig,
+    pub volatility_regime: VolatilityRegimeConfig,
+    pub entry_limits: EntryLimitConfig,
+    pub gaps: GapConfig,
+    pub promotion: PromotionConfig,
+    pub screener: ScreenerConfig,
+    pub risk_sizing: RiskSizingConfig,
+    pub holding: HoldingConfig,
+    pub strategy_groups: Vec<StrategyGroupConfig>,  // First group listing a symbol wins
+    pub pdt: PdtConfig,
+    pub order_throttle: OrderThrottleConfig,
+    pub rotation: RotationConfig,
+    pub rate_limits: RateLimitConfig,
+    pub stops: StopConfig,
+}
+
+#[derive(Clone, Default)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+impl Credentials {
+    pub fn is_present(&self) -> bool {
+        !self.api_key.is_empty() && !self.api_secret.is_empty()
+    }
+}
+
+// Never print the secret, even in debug output
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("api_key", &if self.api_key.is_empty() { "" } else { "<redacted>" })
+            .field("api_secret", &if self.api_secret.is_empty() { "" } else { "<redacted>" })
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStreamConfig {
+    pub enabled: bool,
+    pub feed: String,              // "iex" (free) or "sip"
+    pub watch_interval_secs: u64,  // How often the exit watcher checks live prices
+    pub max_price_age_secs: u64,   // Ignore cached prices older than this
+}
+
+// Valid order increments. Stocks trade in whole lots (1 share unless
+// overridden), crypto in fractional steps. Orders are rounded DOWN to these.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstrumentConfig {
+    pub default_lot_size: f64,
+    pub lot_sizes: HashMap<String, f64>,
+    pub default_crypto_step: f64,
+    pub crypto_steps: HashMap<String, f64>,
+    pub max_units: HashMap<String, f64>,  // Absolute cap on shares/units held per symbol
+    pub max_adv_pct: f64,  // Stock orders capped at this % of estimated daily volume, 0 = off
+    pub entry_windows: HashMap<String, String>,  // "HH:MM-HH:MM" per symbol when entries are allowed
+    pub entry_window_timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBackoffConfig {
+    pub failure_threshold: u32,  // Consecutive failures before backing off
+    pub base_secs: i64,          // First backoff window, doubled per further failure
+    pub max_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorConfig {
+    pub momentum_windows: Vec<usize>,  // Lookbacks (in bars) averaged into the momentum term
+    pub momentum_weights: Vec<f64>,    // Optional per-window weights, equal if empty/mismatched
+    #[serde(default)]
+    pub crossover_average: MovingAverage,  // sma | wma for the 20/50 crossover
+    #[serde(default)]
+    pub regime: RegimeThresholds,
+}
+
+// Cutoffs for labeling a symbol Trending / Ranging / Volatile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegimeThresholds {
+    pub adx_period: usize,
+    pub trend_adx: f64,           // ADX at or above this is Trending
+    pub volatile_width_pct: f64,  // Otherwise, Bollinger band width (% of the middle band) at or above this is Volatile
+}
+
+impl Default for RegimeThresholds {
+    fn default() -> Self {
+        Self { adx_period: 14, trend_adx: 25.0, volatile_width_pct: 3.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioConfig {
+    // Snapshots are taken every 15s; raise this to make coarse
+    // /portfolio/history resolutions (1h, 1d) meaningful
+    pub history_max_snapshots: usize,
+    pub beta_benchmark: String,  // Symbol /portfolio/beta measures against
+    pub demo_starting_cash: f64,  // Demo mode (no credentials) values the portfolio from its own trades, starting here
+    pub pnl_attribution: bool,   // Record per-symbol unrealized P&L once a minute for /pnl/attribution
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdConfig {
+    pub stock_buy: f64,
+    pub stock_sell: f64,
+    pub crypto_buy: f64,
+    pub crypto_sell: f64,
+    pub entry_confirmation_cycles: u32,  // 1 = act on the first qualifying cycle
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub latency_buckets: Vec<f64>,  // Histogram upper bounds, in seconds
+    pub push: String,               // "" = scrape only, "statsd" = also push over UDP
+    pub statsd_addr: String,
+    pub push_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataConfig {
+    pub bar_adjustment: BarAdjustment,  // raw | split | dividend | all
+    pub feed: String,                   // Stock data feed: "iex" (free) or "sip"
+    pub auto_downgrade_feed: bool,      // Fall back to iex if the plan rejects sip
+    pub debug_requests: bool,           // Log every Alpaca request (credentials redacted)
+    pub batch_requests: bool,           // One multi-symbol data request per cycle instead of one per symbol
+    pub trading_host: String,           // Overrides the Alpaca trading API host (e.g. a proxy or mock), empty = Alpaca
+    pub data_host: String,              // Overrides the Alpaca market data host, empty = Alpaca
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlippageConfig {
+    pub max_slippage_pct: f64,           // Adverse fill vs quote before a symbol goes limit-only
+    pub fill_confirm_timeout_secs: u64,  // How long to poll a new order for its fill
+    pub limit_band_pct: f64,             // Refuse limit prices further than this past the quote, 0 = off
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub name: String,           // Built-in signal strategy, see strategy::builtin
+    pub rng_seed: Option<u64>,  // Makes every random element reproducible; unset = random
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileConfig {
+    pub enabled: bool,               // Correct closing P&L from the actual fill
+    pub discrepancy_alert_usd: f64,  // Warn when the correction is bigger than this
+    pub external_positions: bool,    // Record shares bought outside the engine into its history
+}
+
+// Fixed percentage targets by default. In the listed trading modes the target
+// is atr_multiplier x ATR above entry instead, so it scales with volatility.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfitTargetConfig {
+    pub stock_pct: f64,
+    pub crypto_pct: f64,
+    pub atr_modes: Vec<String>,  // e.g. "Volatile,Hybrid"; empty = always fixed
+    pub atr_multiplier: f64,
+    pub atr_period: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    pub summary_enabled: bool,  // Recap when the stock session closes
+    pub webhook_url: String,    // Optional: POST the recap as JSON here
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoHoursConfig {
+    pub window: String,    // "HH:MM-HH:MM" for new crypto entries, empty = 24/7
+    pub timezone: String,  // IANA name, e.g. "America/New_York"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    pub suppress_neutral_analysis: bool,  // Keep dead-band analyses out of the activity log
+    pub signal_details: bool,             // Attach the per-component signal breakdown to analysis logs
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoExitConfig {
+    pub tiers: Vec<(f64, f64)>,  // (percent gain, fraction of holding to sell), ascending
+    pub min_notional: f64,       // Smallest partial sell worth placing, in USD
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquityFloorConfig {
+    pub min_equity: f64,  // No new entries below this account equity, 0 = off
+    pub flatten: bool,    // Also close everything when the floor is breached
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistenceConfig {
+    pub notes_path: String,    // JSON file for position notes/tags
+    pub profiles_dir: String,  // One JSON file per saved strategy profile
+    pub config_history_path: String,  // JSON file for the runtime config change log
+    pub history_format: String,       // Trades/snapshots on disk: "jsonl", "bincode" or "off"
+    pub history_dir: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaltConfig {
+    pub enabled: bool,
+    pub stale_bar_minutes: i64,  // No new bar for this long in market hours = treat as halted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewsConfig {
+    pub sentiment_half_life_mins: f64,  // Article weight halves every this many minutes, 0 = equal weights
+    pub dedup_ttl_mins: i64,            // How long a seen article id is remembered
+    pub debounce_ms: u64,               // Batch a symbol's sentiment updates over this window, 0 = write each
+}
+
+// Entries are blocked when the order plus existing positions correlated with
+// the symbol (by recent 5-min returns) would exceed max_exposure_pct of equity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    pub enabled: bool,
+    pub threshold: f64,         // Correlation at or above this counts as "the same bet"
+    pub max_exposure_pct: f64,  // Of portfolio value
+    pub refresh_secs: u64,      // How often the correlation matrix is rebuilt
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayConfig {
+    pub file: String,        // Recorded events to replay instead of trading live, empty = off
+    pub starting_cash: f64,  // Shadow account size for the replay
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    pub enabled: bool,     // Record bars/quotes/news/decisions for later replay
+    pub dir: String,
+    pub max_file_mb: u64,  // Start a new event file past this size
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub money_decimals: u32,  // Places monetary amounts are rounded to in responses
+    pub tax_lot_method: String,  // "fifo" or "lifo" for /tax/lots
+    pub heatmap_stale_secs: i64,  // /signals/heatmap flags cached signals older than this
+    #[serde(skip)]
+    pub read_token: String,   // Bearer token for GET requests only
+    #[serde(skip)]
+    pub admin_token: String,  // Bearer token for everything; both empty = no auth
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    pub webhook_url: String,  // Asked to approve every trade before it's placed, empty = off
+    pub timeout_secs: u64,
+    pub default_allow: bool,  // Verdict when the webhook doesn't answer in time
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeScheduleConfig {
+    pub windows: HashMap<String, String>,  // "HH:MM-HH:MM" -> trading mode name, empty = no schedule
+    pub timezone: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawdownAlertConfig {
+    pub levels: Vec<f64>,     // % below the session high; the deepest is critical, the rest warnings
+    pub webhook_url: String,  // Optional: POST each alert as JSON here
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolatilityRegimeConfig {
+    pub enabled: bool,
+    pub stock_threshold_pct: f64,   // Average stdev of 5-min returns (%) that counts as high vol
+    pub crypto_threshold_pct: f64,
+    pub size_multiplier: f64,       // Applied to buy sizes while high
+    pub buy_threshold_bump: f64,    // Added to the buy threshold while high
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryLimitConfig {
+    pub max_per_cycle: u32,  // New positions opened per stock/crypto cycle, 0 = unlimited
+    pub stock_min_price: f64,  // No new entries below this price, 0 = off
+    pub crypto_min_price: f64,
+    pub grace_period_secs: i64,  // After startup or a mode switch, signals are logged but entries wait
+    pub max_spread_atr_ratio: f64,  // Skip entries whose bid-ask spread exceeds this multiple of ATR, 0 = off
+    pub max_leverage: f64,  // Total position notional / equity an entry may reach, 0 = off
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GapConfig {
+    pub threshold_pct: f64,  // Open this far (%) from the prior close counts as a gap, 0 = off
+    pub skip_entries: bool,  // Hold off new stock entries until the gap fills or max_bars pass
+    pub max_bars: usize,     // 0 = only the fill releases it
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionConfig {
+    pub min_trades: usize,       // Closed paper trades required before POST /mode/live
+    pub min_win_rate_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenerConfig {
+    pub candidates: Vec<String>,  // Stocks GET /screener scans; empty = every trading mode's universe
+    pub max_candidates: usize,
+    pub cache_secs: u64,
+}
+
+// Fixed dollars-at-risk sizing: qty = risk / (entry - stop), with the stop at
+// stops.stop_loss_pct below entry when that's set, else stop_atr_multiplier x
+// ATR below
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskSizingConfig {
+    pub risk_per_trade_usd: f64,  // 0 = percentage sizing
+    pub stop_atr_multiplier: f64,
+    pub confidence_scaling: bool,  // Scale entries by signal confidence (technical strategy only)
+    pub confidence_floor: f64,     // Size multiplier at zero confidence, rising linearly to 1 at full
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopConfig {
+    pub stop_loss_pct: f64,            // Fixed stop below entry, applies until the trailing stop arms; 0 = off
+    pub trailing_pct: f64,             // Trailing stop distance below the high-water mark, 0 = off
+    pub trailing_activation_pct: f64,  // Gain needed before the trailing stop arms, 0 = trail from entry
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingConfig {
+    pub min_hold_minutes: i64,        // Profit-taking and signal exits wait this long after entry, 0 = off
+    pub stop_override_loss_pct: f64,  // A position down this far (%) exits anyway, 0 = never
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdtConfig {
+    pub enabled: bool,         // Hold same-day stock round trips near the pattern day trader limit
+    pub equity_threshold: f64, // PDT only applies to accounts below this equity
+    pub max_day_trades: u32,   // Day trades allowed per five business days
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderThrottleConfig {
+    pub min_interval_ms: u64,  // Minimum gap between any two order submissions, 0 = off
+}
+
+// Requests per minute for each Alpaca endpoint category, 0 = unlimited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub stock_data_per_min: u32,
+    pub crypto_data_per_min: u32,
+    pub trading_per_min: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationConfig {
+    pub enabled: bool,          // Swap chronically losing stocks for watchlist candidates
+    pub interval_mins: u64,
+    pub min_trades: usize,      // Closed trades before a symbol can be judged
+    pub min_win_rate_pct: f64,  // Losing symbols under this win rate are rotated out
+    pub max_swaps: usize,       // Per run
+    pub watchlist: Vec<String>, // Candidates; empty = SCREENER_CANDIDATES
+}
+
+// Symbols scored by their own strategy with their own thresholds and entry
+// size. The universe still comes from the trading mode, and account-level
+// limits (per-trade caps, entries per cycle, equity floor, correlation) apply
+// across all groups.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyGroupConfig {
+    pub name: String,
+    pub strategy: String,                // Built-in name, or one registered with Engine::with_group_strategy
+    pub symbols: Vec<String>,
+    pub buy_threshold: Option<f64>,      // Unset = the asset class threshold
+    pub sell_threshold: Option<f64>,
+    pub position_size_pct: Option<f64>,  // % of buying power per entry, unset = 5% stocks / 2% crypto
+}
+
+impl ThresholdConfig {
+    pub fn for_asset(&self, is_crypto: bool) -> Thresholds {
+        let (buy, sell) = if is_crypto {
+            (self.crypto_buy, self.crypto_sell)
+        } else {
+            (self.stock_buy, self.stock_sell)
+        };
+        Thresholds { buy, sell, confirmation_cycles: self.entry_confirmation_cycles }
+    }
+}
+
+impl InstrumentConfig {
+    pub fn increment_for(&self, symbol: &str, is_crypto: bool) -> f64 {
+        if is_crypto {
+            per_symbol(&self.crypto_steps, symbol).copied().unwrap_or(self.default_crypto_step)
+        } else {
+            per_symbol(&self.lot_sizes, symbol).copied().unwrap_or(self.default_lot_size)
+        }
+    }
+
+    pub fn max_units_for(&self, symbol: &str) -> Option<f64> {
+        per_symbol(&self.max_units, symbol).copied()
+    }
+
+    pub fn entry_window_for(&self, symbol: &str) -> Option<&str> {
+        per_symbol(&self.entry_windows, symbol).map(String::as_str)
+    }
+}
+
+// A per-symbol setting under either spelling: the map keeps the configured
+// form ("BTC/USD") while Alpaca's positions say "BTCUSD"
+fn per_symbol<'a, V>(map: &'a HashMap<String, V>, symbol: &str) -> Option<&'a V> {
+    map.get(symbol).or_else(|| {
+        map.iter().find(|(key, _)| symbols::same_symbol(key, symbol)).map(|(_, value)| value)
+    })
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            credentials: Credentials {
+                api_key: env::var("ALPACA_API_KEY").unwrap_or_default(),
+                api_secret: env::var("ALPACA_API_SECRET").unwrap_or_default(),
+            },
+            market_stream: MarketStreamConfig {
+                enabled: env_or("MARKET_STREAM_ENABLED", false),
+                feed: env_or("MARKET_STREAM_FEED", "iex".to_string()),
+                watch_interval_secs: env_or("EXIT_WATCH_INTERVAL_SECS", 5),
+                max_price_age_secs: env_or("LIVE_PRICE_MAX_AGE_SECS", 30),
+            },
+            instruments: InstrumentConfig {
+                default_lot_size: env_or("DEFAULT_LOT_SIZE", 1.0),
+                lot_sizes: env_map("LOT_SIZES"),
+                default_crypto_step: env_or("DEFAULT_CRYPTO_STEP", 0.000001),
+                crypto_steps: env_map("CRYPTO_STEP_SIZES"),
+                max_units: env_map("MAX_UNITS_PER_SYMBOL"),
+                max_adv_pct: env_or("MAX_ADV_PARTICIPATION_PCT", 1.0),
+                entry_windows: env_string_map("ENTRY_WINDOWS"),
+                entry_window_timezone: env_or("ENTRY_WINDOW_TIMEZONE", "America/New_York".to_string()),
+            },
+            order_backoff: OrderBackoffConfig {
+                failure_threshold: env_or("ORDER_FAILURE_THRESHOLD", 3),
+                base_secs: env_or("ORDER_BACKOFF_BASE_SECS", 60),
+                max_secs: env_or("ORDER_BACKOFF_MAX_SECS", 3600),
+            },
+            indicators: IndicatorConfig {
+                momentum_windows: env_list("MOMENTUM_WINDOWS", vec![5, 10, 20]),
+                momentum_weights: env_list("MOMENTUM_WEIGHTS", vec![]),
+                crossover_average: env_or("CROSSOVER_AVERAGE", MovingAverage::Sma),
+                regime: RegimeThresholds {
+                    adx_period: env_or("REGIME_ADX_PERIOD", 14),
+                    trend_adx: env_or("REGIME_TREND_ADX", 25.0),
+                    volatile_width_pct: env_or("REGIME_VOLATILE_WIDTH_PCT", 3.0),
+                },
+            },
+            portfolio: PortfolioConfig {
+                history_max_snapshots: env_or("PORTFOLIO_HISTORY_MAX_SNAPSHOTS", 100),
+                beta_benchmark: env_or("BETA_BENCHMARK", "SPY".to_string()),
+                demo_starting_cash: env_or("DEMO_STARTING_CASH", 100000.0),
+                pnl_attribution: env_or("PNL_ATTRIBUTION_ENABLED", true),
+            },
+            thresholds: ThresholdConfig {
+                stock_buy: env_or("STOCK_BUY_THRESHOLD", 0.15),
+                stock_sell: env_or("STOCK_SELL_THRESHOLD", -0.15),
+                crypto_buy: env_or("CRYPTO_BUY_THRESHOLD", 0.20),
+                crypto_sell: env_or("CRYPTO_SELL_THRESHOLD", -0.20),
+                entry_confirmation_cycles: env_or("ENTRY_CONFIRMATION_CYCLES", 1),
+            },
+            data: DataConfig {
+                bar_adjustment: env_or("BAR_ADJUSTMENT", BarAdjustment::Split),
+                feed: env_or("MARKET_DATA_FEED", "iex".to_string()),
+                auto_downgrade_feed: env_or("DATA_FEED_AUTO_DOWNGRADE", true),
+                debug_requests: env_or("ALPACA_DEBUG_REQUESTS", false),
+                batch_requests: env_or("BATCH_MARKET_DATA", true),
+                trading_host: env_or("ALPACA_TRADING_HOST", String::new()),
+                data_host: env_or("ALPACA_DATA_HOST", String::new()),
+            },
+            slippage: SlippageConfig {
+                max_slippage_pct: env_or("MAX_SLIPPAGE_PCT", 1.0),
+                fill_confirm_timeout_secs: env_or("FILL_CONFIRM_TIMEOUT_SECS", 15),
+                limit_band_pct: env_or("LIMIT_PRICE_BAND_PCT", 5.0),
+            },
+            strategy: StrategyConfig {
+                name: env_or("STRATEGY", "technical".to_string()),
+                rng_seed: env::var("RNG_SEED").ok().and_then(|v| v.trim().parse().ok()),
+            },
+            reconcile: ReconcileConfig {
+                enabled: env_or("PNL_RECONCILE_ENABLED", true),
+                discrepancy_alert_usd: env_or("PNL_DISCREPANCY_ALERT_USD", 5.0),
+                external_positions: env_or("RECONCILE_EXTERNAL_POSITIONS", true),
+            },
+            profit_target: ProfitTargetConfig {
+                stock_pct: env_or("STOCK_PROFIT_TARGET_PCT", 15.0),
+                crypto_pct: env_or("CRYPTO_PROFIT_TARGET_PCT", 20.0),
+                atr_modes: env_list("ATR_PROFIT_TARGET_MODES", vec![]),
+                atr_multiplier: env_or("ATR_PROFIT_TARGET_MULTIPLIER", 3.0),
+                atr_period: env_or("ATR_PERIOD", 14),
+            },
+            session: SessionConfig {
+                summary_enabled: env_or("SESSION_SUMMARY_ENABLED", true),
+                webhook_url: env_or("SESSION_SUMMARY_WEBHOOK_URL", String::new()),
+            },
+            crypto_hours: CryptoHoursConfig {
+                window: env_or("CRYPTO_TRADING_HOURS", String::new()),
+                timezone: env_or("CRYPTO_TRADING_TIMEZONE", "America/New_York".to_string()),
+            },
+            logging: LoggingConfig {
+                suppress_neutral_analysis: env_or("SUPPRESS_NEUTRAL_ANALYSIS_LOGS", false),
+                signal_details: env_or("LOG_SIGNAL_DETAILS", true),
+            },
+            crypto_exit: CryptoExitConfig {
+                tiers: env_pairs("CRYPTO_PROFIT_TIERS"),
+                min_notional: env_or("CRYPTO_MIN_NOTIONAL", 1.0),
+            },
+            equity_floor: EquityFloorConfig {
+                min_equity: env_or("MIN_ACCOUNT_EQUITY", 0.0),
+                flatten: env_or("FLATTEN_BELOW_MIN_EQUITY", false),
+            },
+            persistence: PersistenceConfig {
+                notes_path: env_or("POSITION_NOTES_FILE", "data/position_notes.json".to_string()),
+                profiles_dir: env_or("PROFILES_DIR", "data/profiles".to_string()),
+                config_history_path: env_or("CONFIG_HISTORY_FILE", "data/config_history.json".to_string()),
+                history_format: env_or("HISTORY_FORMAT", "jsonl".to_string()),
+                history_dir: env_or("HISTORY_DIR", "data/history".to_string()),
+            },
+            halts: HaltConfig {
+                enabled: env_or("HALT_DETECTION_ENABLED", true),
+                stale_bar_minutes: env_or("HALT_STALE_BAR_MINUTES", 20),
+            },
+            news: NewsConfig {
+                sentiment_half_life_mins: env_or("NEWS_SENTIMENT_HALF_LIFE_MINS", 60.0),
+                dedup_ttl_mins: env_or("NEWS_DEDUP_TTL_MINS", 1440),
+                debounce_ms: env_or("NEWS_DEBOUNCE_MS", 500),
+            },
+            correlation: CorrelationConfig {
+                enabled: env_or("CORRELATION_LIMIT_ENABLED", false),
+                threshold: env_or("CORRELATION_THRESHOLD", 0.8),
+                max_exposure_pct: env_or("MAX_CORRELATED_EXPOSURE_PCT", 25.0),
+                refresh_secs: env_or("CORRELATION_REFRESH_SECS", 300),
+            },
+            replay: ReplayConfig {
+                file: env_or("REPLAY_FILE", String::new()),
+                starting_cash: env_or("REPLAY_STARTING_CASH", 100000.0),
+            },
+            recorder: RecorderConfig {
+                enabled: env_or("RECORD_EVENTS", false),
+                dir: env_or("RECORD_DIR", "data/recordings".to_string()),
+                max_file_mb: env_or("RECORD_MAX_FILE_MB", 50),
+            },
+            api: ApiConfig {
+                money_decimals: env_or("MONEY_DECIMALS", 2),
+                tax_lot_method: env_or("TAX_LOT_METHOD", "fifo".to_string()),
+                heatmap_stale_secs: env_or("SIGNAL_HEATMAP_STALE_SECS", 300),
+                read_token: env::var("API_READ_TOKEN").unwrap_or_default(),
+                admin_token: env::var("API_ADMIN_TOKEN").unwrap_or_default(),
+            },
+            approval: ApprovalConfig {
+                webhook_url: env_or("APPROVAL_WEBHOOK_URL", String::new()),
+                timeout_secs: env_or("APPROVAL_TIMEOUT_SECS", 30),
+                default_allow: env_or("APPROVAL_DEFAULT_ALLOW", false),
+            },
+            mode_schedule: ModeScheduleConfig {
+                windows: env_string_map("TRADING_MODE_SCHEDULE"),
+                timezone: env_or("TRADING_MODE_SCHEDULE_TIMEZONE", "America/New_York".to_string()),
+            },
+            drawdown_alerts: DrawdownAlertConfig {
+                levels: env_list("DRAWDOWN_ALERT_LEVELS", vec![3.0, 6.0]),
+                webhook_url: env_or("DRAWDOWN_ALERT_WEBHOOK_URL", String::new()),
+            },
+            volatility_regime: VolatilityRegimeConfig {
+                enabled: env_or("VOL_REGIME_ENABLED", false),
+                stock_threshold_pct: env_or("VOL_REGIME_STOCK_THRESHOLD_PCT", 0.4),
+                crypto_threshold_pct: env_or("VOL_REGIME_CRYPTO_THRESHOLD_PCT", 0.8),
+                size_multiplier: env_or("VOL_REGIME_SIZE_MULTIPLIER", 0.5),
+                buy_threshold_bump: env_or("VOL_REGIME_BUY_THRESHOLD_BUMP", 0.1),
+            },
+            entry_limits: EntryLimitConfig {
+                max_per_cycle: env_or("MAX_NEW_ENTRIES_PER_CYCLE", 0),
+                stock_min_price: env_or("STOCK_MIN_PRICE", 1.0),
+                crypto_min_price: env_or("CRYPTO_MIN_PRICE", 0.01),
+                grace_period_secs: env_or("ENTRY_GRACE_PERIOD_SECS", 120),
+                max_spread_atr_ratio: env_or("MAX_SPREAD_ATR_RATIO", 0.5),
+                max_leverage: env_or("MAX_LEVERAGE", 0.0),
+            },
+            gaps: GapConfig {
+                threshold_pct: env_or("GAP_THRESHOLD_PCT", 3.0),
+                skip_entries: env_or("GAP_SKIP_ENTRIES", false),
+                max_bars: env_or("GAP_MAX_BARS", 6),
+            },
+            promotion: PromotionConfig {
+                min_trades: env_or("LIVE_PROMOTION_MIN_TRADES", 50),
+                min_win_rate_pct: env_or("LIVE_PROMOTION_MIN_WIN_RATE_PCT", 50.0),
+            },
+            screener: ScreenerConfig {
+                candidates: env_list("SCREENER_CANDIDATES", vec![]),
+                max_candidates: env_or("SCREENER_MAX_CANDIDATES", 50),
+                cache_secs: env_or("SCREENER_CACHE_SECS", 120),
+            },
+            risk_sizing: RiskSizingConfig {
+                risk_per_trade_usd: env_or("RISK_PER_TRADE_USD", 0.0),
+                stop_atr_multiplier: env_or("RISK_STOP_ATR_MULTIPLIER", 2.0),
+                confidence_scaling: env_or("CONFIDENCE_SIZING_ENABLED", false),
+                confidence_floor: env_or("CONFIDENCE_SIZE_FLOOR", 0.5),
+            },
+            holding: HoldingConfig {
+                min_hold_minutes: env_or("MIN_HOLD_MINUTES", 0),
+                stop_override_loss_pct: env_or("MIN_HOLD_STOP_LOSS_PCT", 10.0),
+            },
+            strategy_groups: strategy_groups(),
+            rotation: RotationConfig {
+                enabled: env_or("ROTATION_ENABLED", false),
+                interval_mins: env_or("ROTATION_INTERVAL_MINS", 60),
+                min_trades: env_or("ROTATION_MIN_TRADES", 5),
+                min_win_rate_pct: env_or("ROTATION_MIN_WIN_RATE_PCT", 35.0),
+                max_swaps: env_or("ROTATION_MAX_SWAPS", 1),
+                watchlist: env_list::<String>("ROTATION_WATCHLIST", vec![]).into_iter().map(|s| s.to_uppercase()).collect(),
+            },
+            stops: StopConfig {
+                stop_loss_pct: env_or("STOP_LOSS_PCT", 0.0),
+                trailing_pct: env_or("TRAILING_STOP_PCT", 0.0),
+                trailing_activation_pct: env_or("TRAILING_STOP_ACTIVATION_PCT", 0.0),
+            },
+            rate_limits: RateLimitConfig {
+                stock_data_per_min: env_or("RATE_LIMIT_STOCK_DATA_PER_MIN", 200),
+                crypto_data_per_min: env_or("RATE_LIMIT_CRYPTO_DATA_PER_MIN", 200),
+                trading_per_min: env_or("RATE_LIMIT_TRADING_PER_MIN", 200),
+            },
+            order_throttle: OrderThrottleConfig {
+                min_interval_ms: env_or("ORDER_MIN_INTERVAL_MS", 500),
+            },
+            pdt: PdtConfig {
+                enabled: env_or("PDT_GUARD_ENABLED", true),
+                equity_threshold: env_or("PDT_EQUITY_THRESHOLD", 25000.0),
+                max_day_trades: env_or("PDT_MAX_DAY_TRADES", 3),
+            },
+            metrics: MetricsConfig {
+                enabled: env_or("METRICS_ENABLED", true),
+                latency_buckets: env_list(
+                    "METRICS_LATENCY_BUCKETS",
+                    vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0],
+                ),
+                push: env_or("METRICS_PUSH", String::new()).to_lowercase(),
+                statsd_addr: env_or("STATSD_ADDR", "127.0.0.1:8125".to_string()),
+                push_interval_secs: env_or("METRICS_PUSH_INTERVAL_SECS", 10),
+            },
+        }
+    }
+
+    pub fn strategy_group(&self, symbol: &str) -> Option<&StrategyGroupConfig> {
+        self.strategy_groups.iter().find(|g| g.symbols.iter().any(|s| symbols::same_symbol(s, symbol)))
+    }
+
+    // Asset class thresholds with the symbol's group overrides applied
+    pub fn thresholds_for(&self, symbol: &str, is_crypto: bool) -> Thresholds {
+        let mut thresholds = self.thresholds.for_asset(is_crypto);
+        if let Some(group) = self.strategy_group(symbol) {
+            thresholds.buy = group.buy_threshold.unwrap_or(thresholds.buy);
+            thresholds.sell = group.sell_threshold.unwrap_or(thresholds.sell);
+        }
+        thresholds
+    }
+
+    // Share of buying power (%) a new entry in `symbol` uses
+    pub fn entry_size_pct(&self, symbol: &str, is_crypto: bool) -> f64 {
+        self.strategy_group(symbol)
+            .and_then(|g| g.position_size_pct)
+            .unwrap_or(if is_crypto { 2.0 } else { 5.0 })
+    }
+}
+
+// STRATEGY_GROUPS=reversion,trend with STRATEGY_GROUP_REVERSION_STRATEGY,
+// _SYMBOLS, _BUY_THRESHOLD, _SELL_THRESHOLD and _POSITION_SIZE_PCT per group
+fn strategy_groups() -> Vec<StrategyGroupConfig> {
+    env_list::<String>("STRATEGY_GROUPS", vec![])
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let key = |field: &str| format!("STRATEGY_GROUP_{}_{}", name.to_uppercase(), field);
+            StrategyGroupConfig {
+                strategy: env_or(&key("STRATEGY"), "technical".to_string()),
+                symbols: env_list::<String>(&key("SYMBOLS"), vec![]).into_iter().map(|s| s.to_uppercase()).collect(),
+                buy_threshold: env::var(key("BUY_THRESHOLD")).ok().and_then(|v| v.trim().parse().ok()),
+                sell_threshold: env::var(key("SELL_THRESHOLD")).ok().and_then(|v| v.trim().parse().ok()),
+                position_size_pct: env::var(key("POSITION_SIZE_PCT")).ok().and_then(|v| v.trim().parse().ok()),
+                name,
+            }
+        })
+        .collect()
+}
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+// Parses "5,10,20" style lists, falling back to the default if unset or invalid
+fn env_list<T: FromStr>(key: &str, default: Vec<T>) -> Vec<T> {
+    let raw = env::var(key).unwrap_or_default();
+    if raw.trim().is_empty() {
+        return default;
+    }
+    let parsed: Option<Vec<T>> = raw.split(',').map(|v| v.trim().parse().ok()).collect();
+    parsed.unwrap_or(default)
+}
+
+// Parses "10:0.5,15:0.25" style numeric pairs, sorted by the first value
+fn env_pairs(key: &str) -> Vec<(f64, f64)> {
+    let mut pairs: Vec<(f64, f64)> = env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (a, b) = pair.split_once(':')?;
+            Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    pairs
+}
+
+// Parses "AAPL=13:00-16:00,BTC/USD=08:00-22:00" style per-symbol strings
+fn env_string_map(key: &str) -> HashMap<String, String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (symbol, value) = pair.split_once('=')?;
+            Some((symbol.trim().to_uppercase(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// Parses "AAPL:100,BTC/USD:0.0001" style per-symbol overrides
+fn env_map(key: &str) -> HashMap<String, f64> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|pair| {
+            let (symbol, value) = pair.rsplit_once(':')?;
+            Some((symbol.trim().to_uppercase(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_symbol_instrument_settings_match_either_spelling() {
+        let mut instruments = Config::from_env().instruments;
+        instruments.crypto_steps = HashMap::from([("ETH/USD".to_string(), 0.01)]);
+        instruments.max_units = HashMap::from([("BTC/USD".to_string(), 0.5)]);
+        instruments.entry_windows = HashMap::from([("aapl".to_string(), "10:00-15:00".to_string())]);
+
+        assert_eq!(instruments.increment_for("ETHUSD", true), 0.01);
+        assert_eq!(instruments.increment_for("ETH/USD", true), 0.01);
+        assert_eq!(instruments.increment_for("SOLUSD", true), instruments.default_crypto_step);
+        assert_eq!(instruments.max_units_for("BTCUSD"), Some(0.5));
+        assert_eq!(instruments.entry_window_for("AAPL"), Some("10:00-15:00"));
+    }
+}
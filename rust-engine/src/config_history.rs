@@ -0,0 +1,155 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+// Oldest entries are dropped past this
+const MAX_ENTRIES: usize = 500;
+
+// One runtime config change. `before`/`after` are the tunable settings
+// (StrategyProfile shape) as JSON, so any two versions can be diffed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub version: u64,
+    pub at: String,
+    pub source: String,  // What made the change, e.g. "profile:aggressive"
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub path: String,  // Dotted, e.g. "thresholds.stock.buy"
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+// Leaf values keyed by dotted path; arrays are compared whole
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, v, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
+
+pub fn diff(from: &Value, to: &Value) -> Vec<FieldChange> {
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    flatten("", from, &mut a);
+    flatten("", to, &mut b);
+    let a: BTreeMap<_, _> = a.into_iter().collect();
+    let b: BTreeMap<_, _> = b.into_iter().collect();
+
+    let mut paths: Vec<&String> = a.keys().chain(b.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths.into_iter()
+        .filter(|p| a.get(*p) != b.get(*p))
+        .map(|p| FieldChange { path: p.clone(), from: a.get(p).cloned(), to: b.get(p).cloned() })
+        .collect()
+}
+
+// Versioned log of runtime config changes, rewritten to a JSON file on every
+// change so it survives restarts
+#[derive(Clone)]
+pub struct ConfigHistory {
+    path: PathBuf,
+    entries: Arc<Mutex<Vec<ConfigChange>>>,
+}
+
+impl ConfigHistory {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries: Vec<ConfigChange> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self { path, entries: Arc::new(Mutex::new(entries)) }
+    }
+
+    // No-op changes aren't recorded
+    pub fn record(&self, source: &str, before: Value, after: Value) -> Option<ConfigChange> {
+        if before == after {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let change = ConfigChange {
+            version: entries.last().map_or(1, |e| e.version + 1),
+            at: Utc::now().to_rfc3339(),
+            source: source.to_string(),
+            before,
+            after,
+        };
+        entries.push(change.clone());
+        if entries.len() > MAX_ENTRIES {
+            let excess = entries.len() - MAX_ENTRIES;
+            entries.drain(..excess);
+        }
+        self.save(&entries);
+        Some(change)
+    }
+
+    pub fn entries(&self) -> Vec<ConfigChange> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    // Settings as they stood right after `version`; version 0 is the state
+    // before the oldest change still kept
+    pub fn at_version(&self, version: u64) -> Option<Value> {
+        let entries = self.entries.lock().unwrap();
+        if version == 0 {
+            return entries.first().map(|e| e.before.clone());
+        }
+        entries.iter().find(|e| e.version == version).map(|e| e.after.clone())
+    }
+
+    fn save(&self, entries: &[ConfigChange]) {
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string_pretty(entries)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std::fs::write(&self.path, json).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            warn!("⚠️  Failed to save config history to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn two_changes_are_versioned_diffable_and_persisted() {
+        let path = std::env::temp_dir().join(format!("ladybug-config-history-{}.json", uuid::Uuid::new_v4()));
+        let history = ConfigHistory::load(&path);
+        let v0 = json!({ "thresholds": { "stock_buy": 0.15, "stock_sell": -0.15 }, "profit_target": { "stock_pct": 15.0 } });
+        let v1 = json!({ "thresholds": { "stock_buy": 0.10, "stock_sell": -0.15 }, "profit_target": { "stock_pct": 15.0 } });
+        let v2 = json!({ "thresholds": { "stock_buy": 0.10, "stock_sell": -0.15 }, "profit_target": { "stock_pct": 8.0 } });
+
+        assert_eq!(history.record("api", v0.clone(), v1.clone()).unwrap().version, 1);
+        assert!(history.record("api", v1.clone(), v1.clone()).is_none());
+        assert_eq!(history.record("profile:scalp", v1, v2.clone()).unwrap().version, 2);
+
+        let changes = diff(&history.at_version(0).unwrap(), &history.at_version(2).unwrap());
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["profit_target.stock_pct", "thresholds.stock_buy"]);
+        assert_eq!(changes[0].from, Some(json!(15.0)));
+        assert_eq!(changes[0].to, Some(json!(8.0)));
+
+        // Survives a restart
+        let reloaded = ConfigHistory::load(&path);
+        assert_eq!(reloaded.entries().len(), 2);
+        assert_eq!(reloaded.at_version(2), Some(v2));
+        let _ = std::fs::remove_file(path);
+    }
+}
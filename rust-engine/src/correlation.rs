@@ -0,0 +1,167 @@
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::symbols;
+use crate::volatility;
+
+// Simple returns between consecutive closes
+pub fn returns(closes: &[f64]) -> Vec<f64> {
+    closes.windows(2)
+        .filter(|w| w[0] > 0.0)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect()
+}
+
+// Pearson correlation over the overlapping tail of two return series.
+// None when there's too little overlap or one side doesn't move.
+pub fn pearson(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n < 10 {
+        return None;
+    }
+    let (a, b) = (&a[a.len() - n..], &b[b.len() - n..]);
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return None;
+    }
+    Some(cov / (var_a.sqrt() * var_b.sqrt()))
+}
+
+// Sensitivity of `asset` to `benchmark`: cov(a, b) / var(b) over the
+// overlapping tail. None under the same conditions as `pearson`.
+pub fn beta(asset: &[f64], benchmark: &[f64]) -> Option<f64> {
+    let n = asset.len().min(benchmark.len());
+    if n < 10 {
+        return None;
+    }
+    let (a, b) = (&asset[asset.len() - n..], &benchmark[benchmark.len() - n..]);
+    let mean_a = a.iter().sum::<f64>() / n as f64;
+    let mean_b = b.iter().sum::<f64>() / n as f64;
+    let cov: f64 = a.iter().zip(b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    let var_b: f64 = b.iter().map(|y| (y - mean_b).powi(2)).sum();
+    (var_b > 0.0).then(|| cov / var_b)
+}
+
+#[derive(Default)]
+struct Matrix {
+    built_at: Option<Instant>,
+    pairs: HashMap<(String, String), f64>,
+}
+
+// Recent returns per symbol (fed by the analysis cycles) and a pairwise
+// correlation matrix rebuilt from them at most every `refresh`
+#[derive(Clone, Default)]
+pub struct CorrelationTracker {
+    returns: Arc<DashMap<String, Vec<f64>>>,
+    matrix: Arc<RwLock<Matrix>>,
+}
+
+impl CorrelationTracker {
+    pub fn record(&self, symbol: &str, closes: &[f64]) {
+        self.returns.insert(symbols::normalize(symbol), returns(closes));
+    }
+
+    fn pair_key(a: &str, b: &str) -> (String, String) {
+        let (a, b) = (symbols::normalize(a), symbols::normalize(b));
+        if a <= b { (a, b) } else { (b, a) }
+    }
+
+    fn rebuild_if_stale(&self, refresh: Duration) {
+        let stale = self.matrix.read().unwrap()
+            .built_at
+            .is_none_or(|t| t.elapsed() >= refresh);
+        if !stale {
+            return;
+        }
+
+        let series: Vec<(String, Vec<f64>)> = self.returns.iter()
+            .map(|r| (r.key().clone(), r.value().clone()))
+            .collect();
+        let mut pairs = HashMap::new();
+        for (i, (a, ra)) in series.iter().enumerate() {
+            for (b, rb) in &series[i + 1..] {
+                if let Some(c) = pearson(ra, rb) {
+                    pairs.insert(Self::pair_key(a, b), c);
+                }
+            }
+        }
+        *self.matrix.write().unwrap() = Matrix { built_at: Some(Instant::now()), pairs };
+    }
+
+    // Mean per-symbol volatility (stdev of recent returns, in %) over the
+    // symbols `include` accepts; None if none of them have data yet
+    pub fn average_volatility(&self, include: impl Fn(&str) -> bool) -> Option<f64> {
+        let vols: Vec<f64> = self.returns.iter()
+            .filter(|r| include(r.key()))
+            .filter_map(|r| volatility::stdev(r.value()))
+            .map(|v| v * 100.0)
+            .collect();
+        (!vols.is_empty()).then(|| vols.iter().sum::<f64>() / vols.len() as f64)
+    }
+
+    pub fn returns_for(&self, symbol: &str) -> Option<Vec<f64>> {
+        self.returns.get(&symbols::normalize(symbol)).map(|r| r.clone())
+    }
+
+    pub fn correlation(&self, a: &str, b: &str, refresh: Duration) -> Option<f64> {
+        if symbols::same_symbol(a, b) {
+            return Some(1.0);
+        }
+        self.rebuild_if_stale(refresh);
+        self.matrix.read().unwrap().pairs.get(&Self::pair_key(a, b)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Closes that zig-zag with the given per-step moves
+    fn closes(moves: &[f64]) -> Vec<f64> {
+        moves.iter().scan(100.0, |price, m| {
+            *price *= 1.0 + m;
+            Some(*price)
+        }).collect()
+    }
+
+    #[test]
+    fn pearson_spots_moving_together_and_apart() {
+        let a: Vec<f64> = (0..20).map(|i| ((i * 7) % 5) as f64 - 2.0).collect();
+        let scaled: Vec<f64> = a.iter().map(|x| x * 3.0 + 1.0).collect();
+        let inverse: Vec<f64> = a.iter().map(|x| -x).collect();
+        assert!((pearson(&a, &scaled).unwrap() - 1.0).abs() < 1e-9);
+        assert!((pearson(&a, &inverse).unwrap() + 1.0).abs() < 1e-9);
+        assert!(pearson(&a[..5], &scaled[..5]).is_none());
+        assert!(pearson(&a, &[0.0; 20]).is_none());
+    }
+
+    #[test]
+    fn tracker_correlates_symbols_across_naming_forms() {
+        let moves: Vec<f64> = (0..30).map(|i| if i % 3 == 0 { 0.02 } else { -0.01 }).collect();
+        let tracker = CorrelationTracker::default();
+        tracker.record("BTC/USD", &closes(&moves));
+        tracker.record("ETH/USD", &closes(&moves));
+
+        let corr = tracker.correlation("BTCUSD", "ETHUSD", Duration::ZERO).unwrap();
+        assert!((corr - 1.0).abs() < 1e-9);
+        assert_eq!(tracker.correlation("BTC/USD", "BTCUSD", Duration::ZERO), Some(1.0));
+        assert_eq!(tracker.correlation("BTC/USD", "SOL/USD", Duration::ZERO), None);
+    }
+
+    #[test]
+    fn beta_of_a_scaled_series_is_the_scale() {
+        let benchmark: Vec<f64> = (0..20).map(|i| [0.01, -0.005, 0.002, -0.008][i % 4]).collect();
+        let levered: Vec<f64> = benchmark.iter().map(|r| r * 1.5).collect();
+        assert!((beta(&levered, &benchmark).unwrap() - 1.5).abs() < 1e-9);
+        assert!(beta(&levered[..5], &benchmark[..5]).is_none());
+    }
+}
@@ -1,6 +1,14 @@
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::alpaca::{check_limit_band, Order, Position, QuoteDetail};
+use crate::http_log::SendLogged;
+use crate::metrics::{Metrics, ALPACA_REQUEST_SECONDS};
+use crate::throttle::{OrderThrottle, RateCategory, RateLimiter};
 
 #[derive(Clone)]
 pub struct CryptoClient {
@@ -9,10 +17,14 @@ pub struct CryptoClient {
     api_secret: String,
     base_url: String,
     data_url: String,
+    metrics: Arc<Metrics>,
+    log_requests: bool,
+    limit_band_pct: f64,
+    order_throttle: OrderThrottle,
+    rate_limiter: RateLimiter,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct CryptoPosition {
     pub symbol: String,
     pub qty: String,
@@ -22,6 +34,19 @@ pub struct CryptoPosition {
     pub asset_class: String,
 }
 
+// The exit helpers work on the shared position shape
+impl From<CryptoPosition> for Position {
+    fn from(p: CryptoPosition) -> Self {
+        Position {
+            symbol: p.symbol,
+            qty: p.qty,
+            avg_entry_price: p.avg_entry_price,
+            current_price: p.current_price,
+            unrealized_pl: p.unrealized_pl,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
 pub struct CryptoBar {
@@ -36,7 +61,9 @@ pub struct CryptoBar {
 
 #[derive(Debug, Deserialize)]
 struct CryptoBarsResponse {
-    bars: std::collections::HashMap<String, Vec<CryptoBar>>,
+    bars: HashMap<String, Vec<CryptoBar>>,
+    #[serde(default)]
+    next_page_token: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,6 +74,10 @@ pub struct CryptoOrderRequest {
     #[serde(rename = "type")]
     pub order_type: String,
     pub time_in_force: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_price: Option<String>,
+    #[serde(skip)]
+    pub reference_price: Option<f64>,  // Quote the order was priced from, for the limit sanity check
 }
 
 impl CryptoClient {
@@ -63,13 +94,68 @@ impl CryptoClient {
             api_secret,
             base_url,
             data_url: "https://data.alpaca.markets/v1beta3".to_string(),
+            metrics: Arc::new(Metrics::default()),
+            log_requests: false,
+            limit_band_pct: 0.0,
+            order_throttle: OrderThrottle::default(),
+            rate_limiter: RateLimiter::default(),
         }
     }
 
+    // Same sanity band as AlpacaClient::with_limit_band
+    pub fn with_limit_band(mut self, band_pct: f64) -> Self {
+        self.limit_band_pct = band_pct;
+        self
+    }
+
+    // See AlpacaClient::with_order_throttle
+    pub fn with_order_throttle(mut self, throttle: OrderThrottle) -> Self {
+        self.order_throttle = throttle;
+        self
+    }
+
+    // See AlpacaClient::with_rate_limiter
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = limiter;
+        self
+    }
+
+    // See AlpacaClient::with_hosts
+    pub fn with_hosts(mut self, trading_host: &str, data_host: &str) -> Self {
+        if !trading_host.is_empty() {
+            self.base_url = format!("{}/v2", trading_host.trim_end_matches('/'));
+        }
+        if !data_host.is_empty() {
+            self.data_url = format!("{}/v1beta3", data_host.trim_end_matches('/'));
+        }
+        self
+    }
+
+    pub fn with_request_logging(mut self, enabled: bool) -> Self {
+        self.log_requests = enabled;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn record_latency(&self, endpoint: &str, start: Instant) {
+        self.metrics.observe_since(ALPACA_REQUEST_SECONDS, &[("endpoint", endpoint)], start);
+    }
+
     pub async fn get_crypto_bars(&self, symbol: &str, timeframe: &str, limit: u32) -> Result<Vec<CryptoBar>> {
+        #[cfg(feature = "frozen-prices")]
+        if let Some(bars) = crate::frozen::bars(symbol, limit) {
+            return Ok(bars.into_iter().map(frozen_bar).collect());
+        }
+        
         // Alpaca crypto symbols format: BTC/USD, ETH/USD, etc.
         let url = format!("{}/crypto/us/bars", self.data_url);
         
+        self.rate_limiter.acquire(RateCategory::CryptoData).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
@@ -79,9 +165,11 @@ impl CryptoClient {
                 ("timeframe", timeframe),
                 ("limit", &limit.to_string()),
             ])
-            .send()
+            .send_logged(self.log_requests)
             .await
             .context(format!("Failed to fetch crypto bars for {}", symbol))?;
+        self.record_latency("crypto_bars", start);
+        self.rate_limiter.record_quota(RateCategory::CryptoData, response.headers());
 
         if !response.status().is_success() {
             tracing::warn!("Alpaca crypto data API error for {}", symbol);
@@ -106,17 +194,109 @@ impl CryptoClient {
         }
     }
 
+    // Bars for several crypto symbols in one request (following pagination).
+    // Every requested symbol gets an entry - empty if the response had none -
+    // holding its most recent `limit` bars, oldest first.
+    pub async fn get_crypto_bars_multi(
+        &self,
+        symbols: &[&str],
+        timeframe: &str,
+        limit: u32,
+    ) -> Result<HashMap<String, Vec<CryptoBar>>> {
+        #[cfg(feature = "frozen-prices")]
+        if symbols.iter().all(|s| crate::frozen::price(s).is_some()) {
+            return Ok(symbols.iter()
+                .map(|s| (s.to_string(), crate::frozen::bars(s, limit).unwrap_or_default().into_iter().map(frozen_bar).collect()))
+                .collect());
+        }
+        
+        let mut result: HashMap<String, Vec<CryptoBar>> = symbols.iter()
+            .map(|s| (s.to_string(), vec![]))
+            .collect();
+        if symbols.is_empty() {
+            return Ok(result);
+        }
+        
+        let url = format!("{}/crypto/us/bars", self.data_url);
+        let joined = symbols.join(",");
+        // The page limit counts bars across all symbols
+        let page_limit = (limit as usize * symbols.len()).min(10_000).to_string();
+        let mut page_token: Option<String> = None;
+        
+        for _ in 0..10 {
+            let mut query = vec![
+                ("symbols", joined.as_str()),
+                ("timeframe", timeframe),
+                ("limit", page_limit.as_str()),
+            ];
+            if let Some(token) = &page_token {
+                query.push(("page_token", token.as_str()));
+            }
+            
+            self.rate_limiter.acquire(RateCategory::CryptoData).await;
+            let start = Instant::now();
+            let response = self.client
+                .get(&url)
+                .header("APCA-API-KEY-ID", &self.api_key)
+                .header("APCA-API-SECRET-KEY", &self.api_secret)
+                .query(&query)
+                .send_logged(self.log_requests)
+                .await
+                .context("Failed to fetch crypto bars")?;
+            self.record_latency("crypto_bars", start);
+            self.rate_limiter.record_quota(RateCategory::CryptoData, response.headers());
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                anyhow::bail!("Alpaca crypto data API error: {}", error_text);
+            }
+
+            let page: CryptoBarsResponse = response.json().await?;
+            for (symbol, bars) in page.bars {
+                result.entry(symbol).or_default().extend(bars);
+            }
+            
+            page_token = page.next_page_token.filter(|t| !t.is_empty());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        
+        for bars in result.values_mut() {
+            if bars.len() > limit as usize {
+                bars.drain(..bars.len() - limit as usize);
+            }
+        }
+        
+        #[cfg(feature = "frozen-prices")]
+        for (symbol, bars) in result.iter_mut() {
+            if let Some(frozen) = crate::frozen::bars(symbol, limit) {
+                *bars = frozen.into_iter().map(frozen_bar).collect();
+            }
+        }
+        Ok(result)
+    }
+
     pub async fn get_latest_crypto_price(&self, symbol: &str) -> Result<f64> {
+        #[cfg(feature = "frozen-prices")]
+        if let Some(price) = crate::frozen::price(symbol) {
+            return Ok(price);
+        }
+        
         let url = format!("{}/crypto/us/latest/quotes", self.data_url);
         
+        self.rate_limiter.acquire(RateCategory::CryptoData).await;
+        let start = Instant::now();
         let response = self.client
             .get(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
             .query(&[("symbols", symbol)])
-            .send()
+            .send_logged(self.log_requests)
             .await
             .context(format!("Failed to fetch crypto quote for {}", symbol))?;
+        self.record_latency("crypto_latest_quotes", start);
+        self.rate_limiter.record_quota(RateCategory::CryptoData, response.headers());
 
         if !response.status().is_success() {
             anyhow::bail!("Failed to get latest crypto quote");
@@ -150,16 +330,47 @@ impl CryptoClient {
         anyhow::bail!("No crypto price data available")
     }
 
+    pub async fn get_crypto_quote_detail(&self, symbol: &str) -> Result<QuoteDetail> {
+        let url = format!("{}/crypto/us/latest/quotes", self.data_url);
+        
+        self.rate_limiter.acquire(RateCategory::CryptoData).await;
+        let start = Instant::now();
+        let response = self.client
+            .get(&url)
+            .header("APCA-API-KEY-ID", &self.api_key)
+            .header("APCA-API-SECRET-KEY", &self.api_secret)
+            .query(&[("symbols", symbol)])
+            .send_logged(self.log_requests)
+            .await
+            .context(format!("Failed to fetch crypto quote for {}", symbol))?;
+        self.record_latency("crypto_latest_quotes", start);
+        self.rate_limiter.record_quota(RateCategory::CryptoData, response.headers());
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to get latest crypto quote for {}", symbol);
+        }
+
+        let value: serde_json::Value = response.json().await?;
+        QuoteDetail::from_quote(symbol, &value["quotes"][symbol])
+            .ok_or_else(|| anyhow::anyhow!("No crypto quote available for {}", symbol))
+    }
+
     pub async fn place_crypto_order(&self, request: CryptoOrderRequest) -> Result<serde_json::Value> {
+        check_limit_band(&request.symbol, &request.side, request.limit_price.as_deref(), request.reference_price, self.limit_band_pct)?;
+        self.order_throttle.wait(&request.symbol).await;
         let url = format!("{}/orders", self.base_url);
         
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
         let response = self.client
             .post(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
             .json(&request)
-            .send()
+            .send_logged(self.log_requests)
             .await?;
+        self.record_latency("crypto_orders", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
 
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -169,16 +380,116 @@ impl CryptoClient {
         Ok(response.json().await?)
     }
 
-    pub async fn close_crypto_position(&self, symbol: &str) -> Result<()> {
+    // Crypto positions only, classified by Alpaca's asset_class rather than
+    // by symbol shape. Symbols come back without the "/" (e.g. "BTCUSD").
+    pub async fn get_positions(&self) -> Result<Vec<Position>> {
+        let url = format!("{}/positions", self.base_url);
+        
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
+        let response = self.client
+            .get(&url)
+            .header("APCA-API-KEY-ID", &self.api_key)
+            .header("APCA-API-SECRET-KEY", &self.api_secret)
+            .send_logged(self.log_requests)
+            .await
+            .context("Failed to fetch crypto positions")?;
+        self.record_latency("crypto_positions", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to get crypto positions: {}", error_text);
+        }
+
+        let positions: Vec<CryptoPosition> = response.json().await?;
+        Ok(positions.into_iter()
+            .filter(|p| p.asset_class == "crypto")
+            .map(Position::from)
+            .collect())
+    }
+
+    pub async fn close_crypto_position(&self, symbol: &str) -> Result<Order> {
+        self.order_throttle.wait(symbol).await;
         let url = format!("{}/positions/{}", self.base_url, symbol);
         
-        self.client
+        self.rate_limiter.acquire(RateCategory::Trading).await;
+        let start = Instant::now();
+        let response = self.client
             .delete(&url)
             .header("APCA-API-KEY-ID", &self.api_key)
             .header("APCA-API-SECRET-KEY", &self.api_secret)
-            .send()
+            .send_logged(self.log_requests)
             .await?;
+        self.record_latency("crypto_close_position", start);
+        self.rate_limiter.record_quota(RateCategory::Trading, response.headers());
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            anyhow::bail!("Failed to close crypto position {}: {}", symbol, error_text);
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(feature = "frozen-prices")]
+fn frozen_bar(bar: crate::alpaca::Bar) -> CryptoBar {
+    CryptoBar { t: bar.t, o: bar.o, h: bar.h, l: bar.l, c: bar.c, v: bar.v as f64, vw: bar.c }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+
+    fn bar(minute: u32, close: f64) -> serde_json::Value {
+        json!({ "t": format!("2024-06-12T14:{:02}:00Z", minute), "o": close, "h": close, "l": close, "c": close, "v": 1.0, "vw": close })
+    }
+
+    // Two pages: BTC and ETH split across them, SOL never returned
+    async fn paged_bars(Query(query): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+        match query.get("page_token").map(String::as_str) {
+            None => Json(json!({
+                "bars": { "BTC/USD": [bar(0, 100.0), bar(1, 101.0)], "ETH/USD": [bar(0, 50.0)] },
+                "next_page_token": "page-2",
+            })),
+            _ => Json(json!({
+                "bars": { "BTC/USD": [bar(2, 102.0)], "ETH/USD": [bar(1, 51.0)] },
+                "next_page_token": null,
+            })),
+        }
+    }
+
+    #[tokio::test]
+    async fn multi_symbol_bars_come_back_per_symbol_in_order() {
+        let host = test_support::serve(Router::new().route("/v1beta3/crypto/us/bars", get(paged_bars))).await;
+        let client = CryptoClient::new("key".into(), "secret".into(), true).with_hosts(&host, &host);
+
+        let bars = client.get_crypto_bars_multi(&["BTC/USD", "ETH/USD", "SOL/USD"], "5Min", 10).await.unwrap();
+        let closes = |symbol: &str| bars[symbol].iter().map(|b| b.c).collect::<Vec<_>>();
+        assert_eq!(closes("BTC/USD"), vec![100.0, 101.0, 102.0]);
+        assert_eq!(closes("ETH/USD"), vec![50.0, 51.0]);
+        assert!(bars["SOL/USD"].is_empty());
+    }
+
+    #[tokio::test]
+    async fn positions_are_limited_to_crypto() {
+        let app = Router::new().route("/v2/positions", get(|| async {
+            let position = |symbol: &str, asset_class: &str| json!({
+                "symbol": symbol, "qty": "1", "avg_entry_price": "100", "current_price": "100",
+                "unrealized_pl": "0", "asset_class": asset_class,
+            });
+            Json(json!([position("AAPL", "us_equity"), position("BTCUSD", "crypto"), position("ETHUSD", "crypto")]))
+        }));
+        let host = test_support::serve(app).await;
+        let client = CryptoClient::new("key".into(), "secret".into(), true).with_hosts(&host, &host);
 
-        Ok(())
+        let symbols: Vec<String> = client.get_positions().await.unwrap().into_iter().map(|p| p.symbol).collect();
+        assert_eq!(symbols, vec!["BTCUSD", "ETHUSD"]);
     }
 }
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeAction {
+    Buy,
+    Sell,
+    Hold,
+}
+
+// Entry/exit thresholds for one asset class
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub buy: f64,                  // Enter when signal > buy
+    pub sell: f64,                 // Exit when signal < sell (negative)
+    pub confirmation_cycles: u32,  // Consecutive cycles the buy condition must hold
+}
+
+// Pure signal -> action mapping shared by the stock and crypto paths.
+// `buy_streak` is how many consecutive cycles (including this one) the
+// signal has been above the buy threshold.
+pub fn decide_action(signal: f64, has_position: bool, buy_streak: u32, thresholds: &Thresholds) -> TradeAction {
+    if !has_position && signal > thresholds.buy {
+        if buy_streak >= thresholds.confirmation_cycles.max(1) {
+            TradeAction::Buy
+        } else {
+            TradeAction::Hold
+        }
+    } else if has_position && signal < thresholds.sell {
+        TradeAction::Sell
+    } else {
+        TradeAction::Hold
+    }
+}
+
+// Which side of the thresholds a signal sits on, ignoring positions and
+// confirmation: "buy", "sell" or "neutral" (the dead-band)
+pub fn signal_zone(signal: f64, thresholds: &Thresholds) -> &'static str {
+    if signal > thresholds.buy {
+        "buy"
+    } else if signal < thresholds.sell {
+        "sell"
+    } else {
+        "neutral"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_CYCLES: Thresholds = Thresholds { buy: 0.15, sell: -0.15, confirmation_cycles: 2 };
+
+    #[test]
+    fn single_cycle_signal_does_not_enter() {
+        assert_eq!(decide_action(0.4, false, 1, &TWO_CYCLES), TradeAction::Hold);
+    }
+
+    #[test]
+    fn two_cycle_streak_enters() {
+        assert_eq!(decide_action(0.4, false, 2, &TWO_CYCLES), TradeAction::Buy);
+        assert_eq!(decide_action(0.4, false, 3, &TWO_CYCLES), TradeAction::Buy);
+    }
+
+    #[test]
+    fn exits_need_no_confirmation() {
+        assert_eq!(decide_action(-0.4, true, 0, &TWO_CYCLES), TradeAction::Sell);
+        assert_eq!(decide_action(0.4, true, 5, &TWO_CYCLES), TradeAction::Hold);
+    }
+
+    #[test]
+    fn zero_confirmation_cycles_acts_like_one() {
+        let thresholds = Thresholds { confirmation_cycles: 0, ..TWO_CYCLES };
+        assert_eq!(decide_action(0.4, false, 1, &thresholds), TradeAction::Buy);
+    }
+}
@@ -0,0 +1,3583 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{interval, Duration};
+use tracing::{debug, error, info, warn};
+
+use crate::activity::{ActivityLogger, LogLevel};
+use crate::alpaca::{self, AlpacaClient, OrderRequest};
+use crate::approval::{ApprovalGate, ApprovalRequest};
+use crate::backoff::OrderBackoff;
+use crate::config::Config;
+use crate::crypto::{CryptoBar, CryptoClient, CryptoOrderRequest};
+use crate::correlation::CorrelationTracker;
+use crate::decision::{decide_action, Thresholds, TradeAction};
+use crate::entitlement::EntitlementError;
+use crate::external;
+use crate::market_stream::{LivePriceCache, MarketStream};
+use crate::gap;
+use crate::halt::{self, HaltTracker};
+use crate::history_store::{HistoryFormat, HistoryStore};
+use crate::holding;
+use crate::metrics::{self, Metrics, CYCLE_DURATION_SECONDS, SYMBOL_PROCESSING_SECONDS};
+use crate::news::NewsAggregator;
+use crate::config_history::ConfigHistory;
+use crate::notes::{NoteStore, NO_AUTO_SELL};
+use crate::pdt;
+use crate::portfolio::{self, DrawdownAlerts};
+use crate::profile::ProfileStore;
+use crate::recorder::EventRecorder;
+use crate::replay::ReplayEvent;
+use crate::rotation::{self, Rotation};
+use crate::screener::ScreenerCache;
+use crate::session::{self, SessionSummary};
+use crate::sizing;
+use crate::slippage::{self, SlippageGuard};
+use crate::statsd::StatsdExporter;
+use crate::stops::{StopHit, StopRules};
+use crate::strategy::{self, Strategy, StrategyContext};
+use crate::symbols;
+use crate::technical::{SignalExplanation, TechnicalAnalysis};
+use crate::throttle::{OrderThrottle, RateCategory, RateLimiter};
+use crate::volatility::VolatilityRegime;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub alpaca: Arc<AlpacaClient>,
+    pub crypto: Arc<CryptoClient>,
+    pub news: Arc<NewsAggregator>,
+    pub trading_enabled: Arc<RwLock<bool>>,
+    pub crypto_trading_enabled: Arc<RwLock<bool>>,
+    pub maintenance_mode: Arc<RwLock<bool>>,  // Loops keep ticking but do no work; reads still served
+    pub drawdown_alerts: Arc<RwLock<DrawdownAlerts>>,
+    pub volatility_regime: VolatilityRegime,
+    pub logger: Arc<ActivityLogger>,
+    pub portfolio_history: Arc<RwLock<Vec<PortfolioSnapshot>>>,
+    pub pnl_history: Arc<RwLock<Vec<PnlSnapshot>>>,
+    pub trade_history: Arc<RwLock<Vec<TradeRecord>>>,
+    pub news_symbols: Arc<RwLock<Vec<String>>>,
+    pub trading_mode: Arc<RwLock<TradingMode>>,
+    pub config: Arc<RwLock<Config>>,
+    pub live_prices: LivePriceCache,
+    pub order_backoff: Arc<OrderBackoff>,
+    pub buy_streaks: Arc<DashMap<String, u32>>,
+    pub cycle_entries: Arc<DashMap<&'static str, u32>>,  // Buys placed in the running cycle, per asset class
+    pub rate_limiter: RateLimiter,  // Shared with both clients; holds the latest quota headers too
+    pub stock_cycle_lock: Arc<Mutex<()>>,   // Held for a whole stock cycle, scheduled or manual
+    pub crypto_cycle_lock: Arc<Mutex<()>>,
+    pub grace_started: Arc<RwLock<DateTime<Utc>>>,  // Startup or the last mode switch
+    pub metrics: Arc<Metrics>,
+    pub slippage: Arc<SlippageGuard>,
+    pub strategy: Arc<dyn Strategy>,
+    pub group_strategies: Arc<DashMap<String, Arc<dyn Strategy>>>,  // Registered by embedders, by group name
+    pub atr: Arc<DashMap<String, f64>>,  // Latest ATR per symbol ("/" stripped), from the analysis cycles
+    pub last_session: Arc<RwLock<Option<SessionSummary>>>,
+    pub profit_tiers_taken: Arc<DashMap<String, usize>>,  // Crypto partial exits done per position ("/" stripped)
+    pub high_water: Arc<DashMap<String, (f64, f64)>>,  // Per position ("/" stripped): (entry, highest price seen)
+    pub exits_deferred: Arc<DashMap<String, DateTime<Utc>>>,  // "symbol:exit" -> holding open time (or PDT day) last logged as deferred
+    pub equity_floor_breached: Arc<RwLock<bool>>,
+    pub notes: NoteStore,
+    pub profiles: ProfileStore,
+    pub config_history: ConfigHistory,
+    pub halts: HaltTracker,
+    pub correlations: CorrelationTracker,
+    pub recorder: EventRecorder,
+    pub last_signals: Arc<DashMap<String, CachedSignal>>,  // Latest analysis per symbol ("/" stripped)
+    pub approval: ApprovalGate,
+    pub history_store: HistoryStore,
+    pub screener_cache: ScreenerCache,
+    pub rotation: Arc<RwLock<Rotation>>,  // Performance-based stock swaps, applied to every mode
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub enum TradingMode {
+    Conservative,
+    Volatile,
+    Hybrid,
+}
+
+impl TradingMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "conservative" => Some(TradingMode::Conservative),
+            "volatile" => Some(TradingMode::Volatile),
+            "hybrid" => Some(TradingMode::Hybrid),
+            _ => None,
+        }
+    }
+
+    pub fn get_stocks(&self) -> Vec<&'static str> {
+        match self {
+            TradingMode::Conservative => vec![
+                "AAPL", "GOOGL", "MSFT", "TSLA", "AMZN",
+                "NVDA", "META", "NFLX", "AMD", "INTC",
+                "PYPL", "ADBE", "CRM", "ORCL", "QCOM",
+                "TXN", "AVGO", "CSCO", "ASML", "AMAT"
+            ],
+            TradingMode::Volatile => vec![
+                "TSLA", "GME", "PLTR", "RIOT",
+                "MARA", "MSTR", "COIN", "ROKU", "SNAP",
+                "SQ", "SHOP", "ARKK", "UPST", "CRWD",
+                "ZM", "UBER", "LYFT", "DKNG", "HOOD", "SOFI"
+            ],
+            TradingMode::Hybrid => vec![
+                // 10 stable
+                "AAPL", "GOOGL", "MSFT", "AMZN", "META",
+                "NFLX", "ADBE", "CRM", "ORCL", "CSCO",
+                // 10 volatile (NO DUPLICATES)
+                "TSLA", "GME", "PLTR", "RIOT", "COIN",
+                "MSTR", "SNAP", "ROKU", "MARA", "ARKK"
+            ],
+        }
+    }
+
+    pub fn get_crypto(&self) -> Vec<&'static str> {
+        match self {
+            TradingMode::Conservative => vec![
+                "BTC/USD", "ETH/USD", "XRP/USD"
+            ],
+            TradingMode::Volatile => vec![
+                "BTC/USD", "ETH/USD", "SOL/USD",
+                "DOGE/USD", "AVAX/USD", "MATIC/USD"
+            ],
+            TradingMode::Hybrid => vec![
+                "BTC/USD", "ETH/USD", "SOL/USD",
+                "DOGE/USD", "AVAX/USD"
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub timestamp: String,
+    pub total_value: f64,
+    pub cash: f64,
+    pub positions_value: f64,
+}
+
+// Each open position's unrealized P&L at one moment, for GET /pnl/attribution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PnlSnapshot {
+    pub timestamp: String,
+    pub unrealized: BTreeMap<String, f64>,  // Normalized symbol -> unrealized P&L
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub id: String,
+    pub timestamp: String,
+    pub symbol: String,
+    pub action: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub pnl: f64,
+    #[serde(default)]
+    pub fill_price: Option<f64>,    // Confirmed average fill, when known
+    #[serde(default)]
+    pub slippage_flagged: bool,     // Filled worse than MAX_SLIPPAGE_PCT vs the quote
+    #[serde(default)]
+    pub slippage_pct: Option<f64>,  // Adverse % of the fill vs the pre-trade quote (negative = better)
+    #[serde(default)]
+    pub reason: Option<TradeReason>,  // None for trades recorded before reasons were tracked
+    #[serde(default)]
+    pub asset_class: String,  // "stock" or "crypto", empty on older records
+}
+
+impl TradeRecord {
+    // Older records weren't tagged, so fall back to classifying the symbol
+    pub fn asset_class(&self) -> &str {
+        if self.asset_class.is_empty() {
+            symbols::asset_class(&self.symbol)
+        } else {
+            &self.asset_class
+        }
+    }
+}
+
+// Why a trade was placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeReason {
+    Signal,      // Strategy buy/sell signal
+    ProfitTake,  // Profit target or a crypto profit tier
+    Flatten,     // Equity floor breached with FLATTEN_BELOW_MIN_EQUITY
+    Manual,      // Closed through the API
+    External,    // Shares Alpaca holds that the engine didn't buy, recorded on reconcile
+    Stop,        // Fixed or trailing stop
+}
+
+impl TradeReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeReason::Signal => "signal",
+            TradeReason::ProfitTake => "profit_take",
+            TradeReason::Flatten => "flatten",
+            TradeReason::Manual => "manual",
+            TradeReason::External => "external",
+            TradeReason::Stop => "stop",
+        }
+    }
+}
+
+// What the last analysis cycle saw and computed for a symbol, so debugging
+// endpoints can answer without refetching from Alpaca
+#[derive(Clone, Serialize)]
+pub struct CachedSignal {
+    pub computed_at: DateTime<Utc>,
+    pub price: f64,
+    pub signal: f64,
+    pub sentiment: f64,
+    pub explanation: Option<SignalExplanation>,
+    pub bars: Vec<alpaca::Bar>,
+}
+
+// Outcome counts for one analysis cycle
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CycleSummary {
+    pub analyzed: u32,
+    pub buy_signals: u32,
+    pub sell_signals: u32,
+    pub neutral: u32,
+    pub failed: u32,
+}
+
+impl CycleSummary {
+    fn record(&mut self, result: &str) {
+        self.analyzed += 1;
+        match result {
+            "buy" => self.buy_signals += 1,
+            "sell" => self.sell_signals += 1,
+            "neutral" => self.neutral += 1,
+            _ => {}
+        }
+    }
+}
+
+// The trading engine without the HTTP layer. `run` spawns the background
+// loops; `state` gives embedders (and the API) access to the shared state.
+pub struct Engine {
+    state: AppState,
+    has_credentials: bool,
+}
+
+impl Engine {
+    pub fn new(config: Config) -> Self {
+        let has_credentials = config.credentials.is_present();
+        
+        if !has_credentials {
+            info!("⚠️  No Alpaca credentials found - running in demo mode");
+        } else {
+            info!("✓ Alpaca credentials loaded");
+        }
+        
+        let api_key = config.credentials.api_key.clone();
+        let api_secret = config.credentials.api_secret.clone();
+        
+        let metrics = Arc::new(Metrics::new(config.metrics.enabled, config.metrics.latency_buckets.clone()));
+        
+        let order_throttle = OrderThrottle::new(Duration::from_millis(config.order_throttle.min_interval_ms));
+        let rate_limiter = RateLimiter::new(&[
+            (RateCategory::StockData, config.rate_limits.stock_data_per_min),
+            (RateCategory::CryptoData, config.rate_limits.crypto_data_per_min),
+            (RateCategory::Trading, config.rate_limits.trading_per_min),
+        ]);
+        let alpaca = Arc::new(
+            AlpacaClient::new(api_key.clone(), api_secret.clone(), true)
+                .with_metrics(metrics.clone())
+                .with_hosts(&config.data.trading_host, &config.data.data_host)
+                .with_bar_adjustment(config.data.bar_adjustment)
+                .with_data_feed(&config.data.feed, config.data.auto_downgrade_feed)
+                .with_request_logging(config.data.debug_requests)
+                .with_news_half_life(config.news.sentiment_half_life_mins)
+                .with_limit_band(config.slippage.limit_band_pct)
+                .with_order_throttle(order_throttle.clone())
+                .with_rate_limiter(rate_limiter.clone())
+        );
+        let crypto = Arc::new(
+            CryptoClient::new(api_key, api_secret, true)
+                .with_metrics(metrics.clone())
+                .with_hosts(&config.data.trading_host, &config.data.data_host)
+                .with_request_logging(config.data.debug_requests)
+                .with_limit_band(config.slippage.limit_band_pct)
+                .with_order_throttle(order_throttle)
+                .with_rate_limiter(rate_limiter.clone())
+        );
+        let recorder = if config.recorder.enabled {
+            EventRecorder::new(&config.recorder.dir, config.recorder.max_file_mb * 1024 * 1024)
+        } else {
+            EventRecorder::disabled()
+        };
+        let news = Arc::new(
+            NewsAggregator::new() // Yahoo RSS + local sentiment
+                .with_half_life(config.news.sentiment_half_life_mins)
+                .with_dedup_ttl(config.news.dedup_ttl_mins)
+                .with_debounce(config.news.debounce_ms)
+                .with_recorder(recorder.clone())
+        );
+        let logger = Arc::new(ActivityLogger::new());
+        
+        logger.success("System", "LadyBug Trading Engine started");
+        
+        let strategy = strategy::builtin(&config.strategy.name).unwrap_or_else(|| {
+            warn!("⚠️  Unknown strategy '{}', using technical", config.strategy.name);
+            Arc::new(TechnicalAnalysis)
+        });
+        info!("🧠 Signal strategy: {}", strategy.name());
+        for group in &config.strategy_groups {
+            info!("🧠 Strategy group '{}': {} for {}", group.name, group.strategy, group.symbols.join(", "));
+        }
+        
+        let format = HistoryFormat::parse(&config.persistence.history_format).unwrap_or_else(|| {
+            warn!("⚠️  Unknown HISTORY_FORMAT '{}', using jsonl", config.persistence.history_format);
+            HistoryFormat::Jsonl
+        });
+        let history_store = HistoryStore::new(&config.persistence.history_dir, format);
+        let trades = history_store.load_trades();
+        let mut snapshots = history_store.load_snapshots();
+        if snapshots.is_empty() {
+            // Initialize with starting portfolio value
+            snapshots.push(PortfolioSnapshot {
+                timestamp: Utc::now().to_rfc3339(),
+                total_value: 100000.0,
+                cash: 100000.0,
+                positions_value: 0.0,
+            });
+        }
+        
+        let state = AppState {
+            alpaca,
+            crypto,
+            news,
+            trading_enabled: Arc::new(RwLock::new(true)),  // AUTO-ENABLED
+            crypto_trading_enabled: Arc::new(RwLock::new(true)),  // AUTO-ENABLED
+            maintenance_mode: Arc::new(RwLock::new(false)),
+            drawdown_alerts: Arc::new(RwLock::new(DrawdownAlerts::default())),
+            volatility_regime: VolatilityRegime::default(),
+            logger,
+            portfolio_history: Arc::new(RwLock::new(snapshots)),
+            pnl_history: Arc::new(RwLock::new(history_store.load_pnl())),
+            trade_history: Arc::new(RwLock::new(trades)),
+            news_symbols: Arc::new(RwLock::new(vec![
+                "AAPL".to_string(),
+                "GOOGL".to_string(),
+                "BTC/USD".to_string(),
+                "ETH/USD".to_string(),
+            ])),
+            trading_mode: Arc::new(RwLock::new(TradingMode::Hybrid)),
+            order_backoff: Arc::new(OrderBackoff::new(
+                config.order_backoff.failure_threshold,
+                config.order_backoff.base_secs,
+                config.order_backoff.max_secs,
+            )),
+            slippage: Arc::new(SlippageGuard::new(config.slippage.max_slippage_pct)),
+            strategy,
+            group_strategies: Arc::new(DashMap::new()),
+            atr: Arc::new(DashMap::new()),
+            last_session: Arc::new(RwLock::new(None)),
+            profit_tiers_taken: Arc::new(DashMap::new()),
+            high_water: Arc::new(DashMap::new()),
+            exits_deferred: Arc::new(DashMap::new()),
+            equity_floor_breached: Arc::new(RwLock::new(false)),
+            notes: NoteStore::load(&config.persistence.notes_path),
+            profiles: ProfileStore::new(&config.persistence.profiles_dir),
+            config_history: ConfigHistory::load(&config.persistence.config_history_path),
+            halts: HaltTracker::default(),
+            correlations: CorrelationTracker::default(),
+            recorder: recorder.clone(),
+            last_signals: Arc::new(DashMap::new()),
+            approval: ApprovalGate::default(),
+            history_store,
+            screener_cache: ScreenerCache::default(),
+            rotation: Arc::new(RwLock::new(Rotation::default())),
+            config: Arc::new(RwLock::new(config)),
+            live_prices: LivePriceCache::new(),
+            buy_streaks: Arc::new(DashMap::new()),
+            cycle_entries: Arc::new(DashMap::new()),
+            rate_limiter,
+            stock_cycle_lock: Arc::new(Mutex::new(())),
+            crypto_cycle_lock: Arc::new(Mutex::new(())),
+            grace_started: Arc::new(RwLock::new(Utc::now())),
+            metrics,
+        };
+        
+        Self { state, has_credentials }
+    }
+    
+    // Replaces the signal strategy chosen from config, for embedders
+    // plugging in their own. Call before `run`.
+    pub fn with_strategy(mut self, strategy: Arc<dyn Strategy>) -> Self {
+        info!("🧠 Signal strategy: {}", strategy.name());
+        self.state.strategy = strategy;
+        self
+    }
+    
+    // Registers a strategy for a STRATEGY_GROUPS group, taking precedence
+    // over the built-in its STRATEGY_GROUP_<NAME>_STRATEGY names
+    pub fn with_group_strategy(self, group: &str, strategy: Arc<dyn Strategy>) -> Self {
+        info!("🧠 Strategy group '{}': {}", group, strategy.name());
+        self.state.group_strategies.insert(group.to_lowercase(), strategy);
+        self
+    }
+    
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+    
+    // Spawns all background tasks and returns immediately
+    pub async fn run(&self) {
+        let state = &self.state;
+        let has_credentials = self.has_credentials;
+        let config = state.config.read().await.clone();
+        
+        // Log startup status
+        state.logger.success("System", "✅ Stock Trading AUTO-ENABLED");
+        state.logger.success("System", "✅ Crypto Trading AUTO-ENABLED");
+        
+        if state.recorder.is_enabled() {
+            state.recorder.start();
+            state.logger.info("System", "🎙️ Event recording ENABLED");
+        }
+        
+        // Start news aggregator
+        let news_clone = state.news.clone();
+        tokio::spawn(async move {
+            news_clone.start().await;
+        });
+        
+        // Start trading engine
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            if has_credentials {
+                trading_loop(state_clone).await;
+            } else {
+                demo_loop(state_clone).await;
+            }
+        });
+        
+        // Start crypto trading engine
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            if has_credentials {
+                crypto_trading_loop(state_clone).await;
+            }
+        });
+        
+        // Live market data streams + fast exit watcher (between analysis cycles)
+        if has_credentials && config.market_stream.enabled {
+            let api_key = config.credentials.api_key.clone();
+            let api_secret = config.credentials.api_secret.clone();
+            
+            let stock_stream = MarketStream::stocks(
+                &config.market_stream.feed, api_key.clone(), api_secret.clone(), state.live_prices.clone()
+            );
+            let mode = state.trading_mode.clone();
+            tokio::spawn(async move {
+                stock_stream.run(mode, TradingMode::get_stocks).await;
+            });
+            
+            let crypto_stream = MarketStream::crypto(api_key, api_secret, state.live_prices.clone());
+            let mode = state.trading_mode.clone();
+            tokio::spawn(async move {
+                crypto_stream.run(mode, TradingMode::get_crypto).await;
+            });
+            
+            let state_clone = state.clone();
+            tokio::spawn(async move {
+                exit_watch_loop(state_clone).await;
+            });
+            state.logger.success("System", "📡 Live market data stream ENABLED");
+        }
+        
+        // End-of-day recap at market close
+        if config.session.summary_enabled {
+            let state_clone = state.clone();
+            tokio::spawn(async move {
+                session_summary_loop(state_clone).await;
+            });
+        }
+        
+        if has_credentials && config.rotation.enabled {
+            let state_clone = state.clone();
+            tokio::spawn(async move {
+                rotation_loop(state_clone).await;
+            });
+        }
+        
+        match config.metrics.push.as_str() {
+            "" => {}
+            "statsd" if config.metrics.enabled => {
+                let state_clone = state.clone();
+                tokio::spawn(async move {
+                    statsd_loop(state_clone).await;
+                });
+            }
+            "statsd" => warn!("📊 METRICS_PUSH=statsd ignored, METRICS_ENABLED is off"),
+            other => warn!("📊 Unknown METRICS_PUSH '{}' (supported: statsd), not pushing metrics", other),
+        }
+        
+        if !config.mode_schedule.windows.is_empty() {
+            let state_clone = state.clone();
+            tokio::spawn(async move {
+                mode_schedule_loop(state_clone).await;
+            });
+        }
+        
+        // Portfolio tracking loop
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            portfolio_tracking_loop(state_clone, !has_credentials).await;
+        });
+    }
+}
+
+// Pushes metrics to STATSD_ADDR on its own task; a slow or missing agent
+// only affects this loop
+async fn statsd_loop(state: AppState) {
+    let (addr, every) = {
+        let config = state.config.read().await;
+        (config.metrics.statsd_addr.clone(), config.metrics.push_interval_secs.max(1))
+    };
+    let exporter = match StatsdExporter::connect(&addr).await {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            warn!("📊 StatsD export disabled, could not set up {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("📊 Pushing metrics to StatsD at {} every {}s", addr, every);
+    
+    let mut tick = interval(Duration::from_secs(every));
+    loop {
+        tick.tick().await;
+        let lines = StatsdExporter::lines(&state.metrics, &state.rate_limiter);
+        if let Err(e) = exporter.send(&lines).await {
+            tracing::debug!("📊 StatsD push to {} failed: {}", addr, e);
+        }
+    }
+}
+
+// The scheduled window active at `now` and its mode. Windows are checked in
+// sorted order so overlaps resolve the same way every time.
+pub fn scheduled_mode(now: DateTime<Utc>, windows: &HashMap<String, String>, timezone: &str) -> Option<(String, TradingMode)> {
+    let mut sorted: Vec<(&String, &String)> = windows.iter().collect();
+    sorted.sort();
+    sorted.into_iter()
+        .filter(|(window, _)| in_time_window(now, window, timezone))
+        .find_map(|(window, mode)| Some((window.clone(), TradingMode::parse(mode)?)))
+}
+
+// Applies TRADING_MODE_SCHEDULE. The mode is only set when a new window
+// starts, so a manual change sticks until the next boundary.
+async fn mode_schedule_loop(state: AppState) {
+    let mut tick = interval(Duration::from_secs(30));
+    let mut active_window: Option<String> = None;
+    
+    loop {
+        tick.tick().await;
+        let (windows, timezone) = {
+            let config = state.config.read().await;
+            (config.mode_schedule.windows.clone(), config.mode_schedule.timezone.clone())
+        };
+        let scheduled = scheduled_mode(Utc::now(), &windows, &timezone);
+        let window = scheduled.as_ref().map(|(w, _)| w.clone());
+        if window == active_window {
+            continue;
+        }
+        active_window = window;
+        
+        if let Some((window, mode)) = scheduled {
+            let mut current = state.trading_mode.write().await;
+            if *current != mode {
+                info!("🗓️  Schedule window {} - switching trading mode {:?} -> {:?}", window, *current, mode);
+                state.logger.info("Config", &format!("🗓️ Scheduled switch to {:?} ({})", mode, window));
+                *current = mode;
+                restart_grace_period(&state).await;
+            }
+        }
+    }
+}
+
+// The trading mode's stocks with performance rotation swaps applied
+pub async fn active_stocks(state: &AppState) -> Vec<String> {
+    let mode = state.trading_mode.read().await.clone();
+    state.rotation.read().await.apply(&mode.get_stocks())
+}
+
+async fn rotation_loop(state: AppState) {
+    let interval_mins = state.config.read().await.rotation.interval_mins.max(1);
+    let mut tick = interval(Duration::from_secs(interval_mins * 60));
+    tick.tick().await;  // First run after one interval, once there's fresh history
+    
+    loop {
+        tick.tick().await;
+        rotate_universe(&state).await;
+    }
+}
+
+// Swaps chronically losing active stocks for the watchlist stocks with the
+// strongest current signal. Symbols with an open position are left alone -
+// only universe symbols get signal exits.
+async fn rotate_universe(state: &AppState) {
+    let (config, screener_candidates, max_candidates) = {
+        let config = state.config.read().await;
+        (config.rotation.clone(), config.screener.candidates.clone(), config.screener.max_candidates)
+    };
+    let active = active_stocks(state).await;
+    let held: Vec<String> = match state.alpaca.get_positions().await {
+        Ok(positions) => positions.into_iter().map(|p| p.symbol).collect(),
+        Err(e) => {
+            warn!("⚠️  Rotation skipped, positions unavailable: {}", e);
+            return;
+        }
+    };
+    let stats = metrics::group_stats(
+        state.trade_history.read().await.iter()
+            .filter(|t| t.action == "SELL")
+            .map(|t| (symbols::normalize(&t.symbol), t.pnl)),
+    );
+    let losers: Vec<String> = rotation::underperformers(&stats, &active, config.min_trades, config.min_win_rate_pct / 100.0)
+        .into_iter()
+        .filter(|s| !held.iter().any(|h| symbols::same_symbol(h, s)))
+        .collect();
+    state.rotation.write().await.last_run = Some(Utc::now().to_rfc3339());
+    if losers.is_empty() {
+        debug!("🔁 Rotation: no underperforming stocks");
+        return;
+    }
+    
+    let watchlist = if config.watchlist.is_empty() { screener_candidates } else { config.watchlist };
+    let mut candidates = Vec::new();
+    for symbol in watchlist.iter().filter(|c| !active.iter().any(|a| symbols::same_symbol(a, c))).take(max_candidates) {
+        if let Some(signal) = candidate_signal(state, symbol).await {
+            candidates.push((symbol.clone(), signal));
+        }
+    }
+    
+    let swaps = rotation::plan_swaps(&losers, &candidates, config.max_swaps);
+    if swaps.is_empty() {
+        info!("🔁 Rotation: {} underperforming ({}), no bullish watchlist candidate", losers.len(), losers.join(", "));
+        return;
+    }
+    let mut rotation = state.rotation.write().await;
+    for (out, replacement) in swaps {
+        let s = &stats[&symbols::normalize(&out)];
+        info!("🔁 Rotating {} out ({} trades, ${:.2}, {:.0}% wins) for {}", out, s.trades, s.realized_pnl, s.win_rate * 100.0, replacement);
+        state.logger.success("Rotation", &format!("{} -> {} ({} trades, ${:.2} realized, {:.0}% wins)",
+                                                  out, replacement, s.trades, s.realized_pnl, s.win_rate * 100.0));
+        rotation.record(&out, &replacement);
+    }
+}
+
+// Fresh signal for a stock outside the universe, for ranking rotation candidates
+async fn candidate_signal(state: &AppState, symbol: &str) -> Option<f64> {
+    let bars = match state.alpaca.get_bars(symbol, "5Min", 50).await {
+        Ok(bars) if bars.len() >= 20 => bars,
+        Ok(_) => return None,
+        Err(e) => {
+            debug!("Rotation candidate {} skipped: {}", symbol, e);
+            return None;
+        }
+    };
+    let (indicators, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    let price = bars.last()?.c;
+    let ctx = StrategyContext { symbol, is_crypto: false, current_price: price, indicators: &indicators, rng_seed };
+    Some(strategy_for(state, symbol).await.signal(&bars, state.news.get_sentiment(symbol), &ctx))
+}
+
+// With `demo` (no credentials) nothing is asked of Alpaca: value comes from
+// the engine's own trades via demo_account
+async fn portfolio_tracking_loop(state: AppState, demo: bool) {
+    // OPTIMAL: 15 seconds - smooth chart updates without overwhelming UI
+    // 2 API calls/cycle = 8 calls/min (4% of limit)
+    let mut tick = interval(Duration::from_secs(15));
+    
+    loop {
+        tick.tick().await;
+        
+        let (total_value, cash, buying_power) = if demo {
+            demo_account(&state).await
+        } else {
+            // Get REAL account data from Alpaca
+            let account = match state.alpaca.get_account().await {
+                Ok(acc) => acc,
+                Err(_) => {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    continue;
+                }
+            };
+            
+            // Use Alpaca's ACTUAL portfolio_value (includes everything)
+            (
+                account.portfolio_value.parse().unwrap_or(100000.0),
+                account.cash.parse().unwrap_or(100000.0),
+                account.buying_power.parse().unwrap_or(100000.0),
+            )
+        };
+        
+        // Log every 4th cycle (once per minute) to track values
+        static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+        let tick_count = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if tick_count.is_multiple_of(4) {
+            info!("💰 Portfolio: ${:.2} | Cash: ${:.2} | Buying Power: ${:.2}", 
+                  total_value, cash, buying_power);
+        }
+        
+        // CRITICAL BUG FIX: Alpaca paper trading sometimes returns corrupted values
+        // If portfolio value drops below $10k with no positions, something is wrong!
+        if !demo && total_value < 10000.0 && cash < 10000.0 {
+            error!("🚨 CORRUPTED DATA DETECTED! Portfolio: ${:.2}, Cash: ${:.2}", total_value, cash);
+            error!("🚨 This is likely an Alpaca API bug. Skipping this update.");
+            tokio::time::sleep(Duration::from_secs(15)).await;
+            continue;
+        }
+        
+        // Calculate positions value from total
+        let positions_value = total_value - cash;
+        
+        let snapshot = PortfolioSnapshot {
+            timestamp: Utc::now().to_rfc3339(),
+            total_value,
+            cash,
+            positions_value,
+        };
+        
+        let max_snapshots = state.config.read().await.portfolio.history_max_snapshots;
+        {
+            let mut history = state.portfolio_history.write().await;
+            history.push(snapshot);
+            
+            // Keep only the most recent snapshots (100 by default)
+            if history.len() > max_snapshots {
+                let excess = history.len() - max_snapshots;
+                history.drain(..excess);
+            }
+        }
+        
+        check_equity_floor(&state, total_value).await;
+        check_drawdown_alerts(&state, total_value).await;
+        if !demo && tick_count.is_multiple_of(4) {
+            reconcile_external_positions(&state).await;
+            record_pnl_attribution(&state, max_snapshots).await;
+        }
+        save_history(&state).await;
+    }
+}
+
+// (total value, cash, buying power) for demo mode, from simulated trades.
+// Open positions are marked at the last streamed or analyzed price.
+async fn demo_account(state: &AppState) -> (f64, f64, f64) {
+    let starting_cash = state.config.read().await.portfolio.demo_starting_cash;
+    let history = state.trade_history.read().await;
+    let (cash, positions_value) = portfolio::simulated_value(&history, starting_cash, |symbol| {
+        state.live_prices.get(symbol).map(|p| p.price)
+            .or_else(|| state.last_signals.get(symbol).map(|s| s.price))
+    });
+    (cash + positions_value, cash, cash.max(0.0))
+}
+
+// Totals alone can't say which symbols drive the open P&L, so each
+// position's unrealized P&L is recorded once a minute
+async fn record_pnl_attribution(state: &AppState, max_snapshots: usize) {
+    if !state.config.read().await.portfolio.pnl_attribution {
+        return;
+    }
+    let positions = match state.alpaca.get_positions().await {
+        Ok(positions) => positions,
+        Err(e) => {
+            debug!("P&L attribution skipped, positions unavailable: {}", e);
+            return;
+        }
+    };
+    let unrealized = positions.iter()
+        .map(|p| (symbols::normalize(&p.symbol), p.unrealized_pl.parse().unwrap_or(0.0)))
+        .collect();
+    
+    let mut history = state.pnl_history.write().await;
+    history.push(PnlSnapshot { timestamp: Utc::now().to_rfc3339(), unrealized });
+    if history.len() > max_snapshots {
+        let excess = history.len() - max_snapshots;
+        history.drain(..excess);
+    }
+}
+
+// Drawdown from today's high (New York date) against DRAWDOWN_ALERT_LEVELS
+async fn check_drawdown_alerts(state: &AppState, equity: f64) {
+    let config = state.config.read().await.drawdown_alerts.clone();
+    if config.levels.is_empty() {
+        return;
+    }
+    let now = Utc::now();
+    let today = now.with_timezone(&chrono_tz::America::New_York).date_naive();
+    let session_values: Vec<f64> = state.portfolio_history.read().await.iter()
+        .filter(|s| DateTime::parse_from_rfc3339(&s.timestamp)
+            .is_ok_and(|t| t.with_timezone(&chrono_tz::America::New_York).date_naive() == today))
+        .map(|s| s.total_value)
+        .collect();
+    let session_high = session_values.iter().copied().fold(equity, f64::max);
+    let drawdown = portfolio::drawdown_pct(&session_values, equity);
+    
+    let crossed = state.drawdown_alerts.write().await.newly_crossed(today, drawdown, &config.levels);
+    let deepest = config.levels.iter().copied().fold(f64::MIN, f64::max);
+    for level in crossed {
+        let severity = if level >= deepest { "critical" } else { "warning" };
+        let message = format!(
+            "📉 Drawdown {:.2}% from session high ${:.2} (equity ${:.2}) crossed the {}% {} level",
+            drawdown, session_high, equity, level, severity
+        );
+        if severity == "critical" {
+            error!("🚨 {}", message);
+        } else {
+            warn!("⚠️  {}", message);
+        }
+        state.logger.warning("Drawdown", &message);
+        
+        if !config.webhook_url.is_empty() {
+            let alert = serde_json::json!({
+                "severity": severity,
+                "level_pct": level,
+                "drawdown_pct": drawdown,
+                "session_high": session_high,
+                "equity": equity,
+                "at": now.to_rfc3339(),
+            });
+            if let Err(e) = reqwest::Client::new().post(&config.webhook_url).json(&alert).send().await {
+                warn!("⚠️  Drawdown alert webhook failed: {}", e);
+            }
+        }
+    }
+}
+
+// Shares bought by hand on Alpaca leave the engine's history short, so its
+// cost basis and lots are wrong. The missing quantity is recorded as an
+// External buy priced to make the basis match Alpaca's average entry.
+async fn reconcile_external_positions(state: &AppState) {
+    let config = state.config.read().await.reconcile.clone();
+    if !config.external_positions {
+        return;
+    }
+    let Ok(positions) = state.alpaca.get_positions().await else {
+        return;
+    };
+    
+    let now = Utc::now();
+    let instruments = state.config.read().await.instruments.clone();
+    let mut history = state.trade_history.write().await;
+    let tracked = external::tracked_positions(&history);
+    for pos in &positions {
+        if external::recently_traded(&history, &pos.symbol, now, chrono::Duration::minutes(2)) {
+            continue;
+        }
+        let is_crypto = symbols::is_crypto_symbol(&pos.symbol);
+        let qty: f64 = pos.qty.parse().unwrap_or(0.0);
+        let avg_entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+        let tolerance = instruments.increment_for(&pos.symbol, is_crypto) / 2.0;
+        let held = tracked.get(&symbols::normalize(&pos.symbol)).copied().unwrap_or_default();
+        let Some((missing, price)) = external::external_add(held, qty, avg_entry, tolerance) else {
+            continue;
+        };
+        
+        warn!("🧾 {} - Alpaca holds {} but the engine tracked {}, recording {} external units at ${:.4}",
+              pos.symbol, qty, held.qty, missing, price);
+        state.logger.warning("Reconcile", &format!(
+            "{} has {} units added outside the engine, cost basis reconciled to ${:.4}", pos.symbol, missing, avg_entry
+        ));
+        history.push(TradeRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: now.to_rfc3339(),
+            symbol: pos.symbol.clone(),
+            action: "BUY".to_string(),
+            quantity: missing,
+            price,
+            pnl: 0.0,
+            fill_price: None,
+            slippage_flagged: false,
+            slippage_pct: None,
+            reason: Some(TradeReason::External),
+            asset_class: symbols::asset_class(&pos.symbol).to_string(),
+        });
+    }
+}
+
+async fn save_history(state: &AppState) {
+    if !state.history_store.is_enabled() {
+        return;
+    }
+    let trades = state.trade_history.read().await.clone();
+    let snapshots = state.portfolio_history.read().await.clone();
+    let pnl = state.pnl_history.read().await.clone();
+    let result = state.history_store.save_trades(&trades)
+        .and_then(|_| state.history_store.save_snapshots(&snapshots))
+        .and_then(|_| state.history_store.save_pnl(&pnl));
+    if let Err(e) = result {
+        warn!("⚠️  Failed to save trade/portfolio history: {:#}", e);
+    }
+}
+
+async fn demo_loop(state: AppState) {
+    let mut tick = interval(Duration::from_secs(30));
+    
+    loop {
+        tick.tick().await;
+        
+        let trading_enabled = *state.trading_enabled.read().await;
+        if !trading_enabled || *state.maintenance_mode.read().await {
+            continue;
+        }
+        
+        state.logger.info("Demo", "Simulating market analysis...");
+    }
+}
+
+async fn trading_loop(state: AppState) {
+    // OPTIMAL: 30 seconds - fast enough to trade, slow enough to stay safe
+    // With 20 symbols = 40 API calls/cycle = 80 calls/min (40% of limit)
+    let mut tick = interval(Duration::from_secs(30));
+    
+    loop {
+        tick.tick().await;
+        
+        let trading_enabled = *state.trading_enabled.read().await;
+        if !trading_enabled || *state.maintenance_mode.read().await {
+            continue;
+        }
+        
+        if !is_market_open(Utc::now()) {
+            // Market is closed - skip this cycle
+            info!("📈 Market CLOSED - Skipping stock analysis (opens 9:30 AM ET Mon-Fri)");
+            continue;
+        }
+        
+        run_stock_cycle(&state).await;
+    }
+}
+
+// Absolute equity floor (MIN_ACCOUNT_EQUITY). Below it new entries stop and,
+// if FLATTEN_BELOW_MIN_EQUITY is set, every position is closed once.
+async fn check_equity_floor(state: &AppState, equity: f64) {
+    let (min_equity, flatten) = {
+        let config = state.config.read().await;
+        (config.equity_floor.min_equity, config.equity_floor.flatten)
+    };
+    if min_equity <= 0.0 {
+        return;
+    }
+    
+    let below = equity < min_equity;
+    let was_below = std::mem::replace(&mut *state.equity_floor_breached.write().await, below);
+    
+    if below && !was_below {
+        error!("🛑 Account equity ${:.2} fell below the ${:.2} floor - new entries disabled", equity, min_equity);
+        state.logger.warning("Equity Floor", &format!(
+            "🛑 Equity ${:.2} below ${:.2} minimum - no new buys{}",
+            equity, min_equity, if flatten { ", flattening all positions" } else { "" }
+        ));
+        if flatten {
+            flatten_all_positions(state).await;
+        }
+    } else if !below && was_below {
+        info!("✅ Account equity ${:.2} back above the ${:.2} floor - entries re-enabled", equity, min_equity);
+        state.logger.success("Equity Floor", &format!("Equity ${:.2} recovered above ${:.2}, entries re-enabled", equity, min_equity));
+    }
+}
+
+// True (with a log line) while the cached equity is under the floor, for gating new buys
+async fn equity_floor_blocks_entry(state: &AppState, symbol: &str) -> bool {
+    if !*state.equity_floor_breached.read().await {
+        return false;
+    }
+    let equity = state.portfolio_history.read().await.last().map(|s| s.total_value).unwrap_or(0.0);
+    info!("🛑 {} - Buy suppressed, equity ${:.2} is below MIN_ACCOUNT_EQUITY", symbol, equity);
+    true
+}
+
+// Clamps a buy to MAX_UNITS_PER_SYMBOL given what's already held
+async fn apply_unit_cap(state: &AppState, symbol: &str, qty: f64, positions: &[alpaca::Position], increment: f64) -> f64 {
+    let Some(max_units) = state.config.read().await.instruments.max_units_for(symbol) else {
+        return qty;
+    };
+    let held: f64 = positions.iter()
+        .find(|p| symbols::same_symbol(&p.symbol, symbol))
+        .and_then(|p| p.qty.parse().ok())
+        .unwrap_or(0.0);
+    let allowed = sizing::clamp_to_max_units(qty, held, max_units, increment);
+    if allowed < qty {
+        info!("📏 {} - Order clamped from {} to {} (holding {}, max {} units)", symbol, qty, allowed, held, max_units);
+        state.logger.info("Risk", &format!("📏 {} order clamped to {} units (cap {}, holding {})", symbol, allowed, max_units, held));
+    }
+    allowed
+}
+
+// Shrinks a buy to MAX_ADV_PARTICIPATION_PCT of estimated daily volume.
+// Stocks only: crypto bar volumes are truncated to whole units in the shared
+// Bar shape, which would understate ADV for BTC-sized assets
+async fn apply_adv_cap(state: &AppState, symbol: &str, qty: f64, bars: &[alpaca::Bar], increment: f64) -> f64 {
+    let max_pct = state.config.read().await.instruments.max_adv_pct;
+    if max_pct <= 0.0 {
+        return qty;
+    }
+    let volumes: Vec<f64> = bars.iter().map(|b| b.v as f64).collect();
+    let adv = sizing::estimate_daily_volume(&volumes, sizing::STOCK_BARS_PER_DAY);
+    let allowed = sizing::clamp_to_participation(qty, adv, max_pct, increment);
+    if allowed < qty {
+        info!("📏 {} - Order reduced from {} to {} ({}% of ~{:.0} ADV)", symbol, qty, allowed, max_pct, adv);
+        state.logger.info("Risk", &format!("📏 {} order reduced to {} shares by the {}% ADV cap", symbol, allowed, max_pct));
+    }
+    allowed
+}
+
+// Blocks an entry whose notional, plus everything held in symbols correlated
+// with it, would exceed MAX_CORRELATED_EXPOSURE_PCT of portfolio value.
+// Symbols without enough recent bars are treated as uncorrelated.
+async fn correlation_blocks_entry(state: &AppState, symbol: &str, notional: f64) -> bool {
+    let config = state.config.read().await.correlation.clone();
+    if !config.enabled {
+        return false;
+    }
+    
+    let (positions, account) = match (state.alpaca.get_positions().await, state.alpaca.get_account().await) {
+        (Ok(p), Ok(a)) => (p, a),
+        _ => {
+            warn!("⚠️  {} - Could not load positions/account for the correlation check", symbol);
+            return false;
+        }
+    };
+    let equity: f64 = account.portfolio_value.parse().unwrap_or(0.0);
+    if equity <= 0.0 {
+        return false;
+    }
+    
+    let refresh = Duration::from_secs(config.refresh_secs);
+    let mut exposure = notional;
+    let mut correlated = Vec::new();
+    for pos in &positions {
+        let Some(corr) = state.correlations.correlation(symbol, &pos.symbol, refresh) else {
+            continue;
+        };
+        if corr >= config.threshold {
+            let qty: f64 = pos.qty.parse().unwrap_or(0.0);
+            let price: f64 = pos.current_price.parse().unwrap_or(0.0);
+            exposure += (qty * price).abs();
+            correlated.push(format!("{} ({:.2})", pos.symbol, corr));
+        }
+    }
+    
+    let exposure_pct = exposure / equity * 100.0;
+    if exposure_pct <= config.max_exposure_pct {
+        return false;
+    }
+    info!("🔗 {} - Buy blocked, correlated exposure {:.1}% > {:.1}% with [{}]",
+          symbol, exposure_pct, config.max_exposure_pct, correlated.join(", "));
+    state.logger.warning("Risk", &format!(
+        "🔗 {} entry blocked: correlated exposure would be {:.1}% of equity (limit {:.1}%)",
+        symbol, exposure_pct, config.max_exposure_pct
+    ));
+    true
+}
+
+// Blocks an entry that would take gross position notional past MAX_LEVERAGE
+// times equity. `positions` covers every holding, stock and crypto alike.
+async fn leverage_blocks_entry(state: &AppState, symbol: &str, notional: f64, positions: &[alpaca::Position], account: &alpaca::Account) -> bool {
+    let max_leverage = state.config.read().await.entry_limits.max_leverage;
+    if max_leverage <= 0.0 {
+        return false;
+    }
+    let equity: f64 = account.portfolio_value.parse().unwrap_or(0.0);
+    let held: f64 = positions.iter()
+        .map(|p| {
+            let qty: f64 = p.qty.parse().unwrap_or(0.0);
+            let price: f64 = p.current_price.parse().unwrap_or(0.0);
+            (qty * price).abs()
+        })
+        .sum();
+    let Some(leverage) = sizing::leverage_after(held, notional, equity) else {
+        return false;
+    };
+    if leverage <= max_leverage {
+        return false;
+    }
+    info!("🏦 {} - Buy blocked, leverage would be {:.2}x > {:.2}x (${:.2} held + ${:.2} on ${:.2} equity)",
+          symbol, leverage, max_leverage, held, notional, equity);
+    state.logger.warning("Risk", &format!(
+        "🏦 {} entry blocked: leverage would be {:.2}x (limit {:.2}x)", symbol, leverage, max_leverage
+    ));
+    true
+}
+
+// Flags or clears a halt from how stale the latest bar is. Only meaningful
+// while the market is open - bars stop overnight for everyone.
+async fn update_halt_status(state: &AppState, symbol: &str, bars: &[alpaca::Bar]) {
+    let config = state.config.read().await.halts.clone();
+    let now = Utc::now();
+    if !config.enabled || !is_market_open(now) {
+        return;
+    }
+    
+    let Some(minutes) = halt::minutes_since_last_bar(bars, now) else {
+        return;
+    };
+    if minutes >= config.stale_bar_minutes {
+        if state.halts.flag(symbol, minutes) {
+            warn!("⛔ {} - No trades for {} min, treating as halted", symbol, minutes);
+            state.logger.warning("Stocks", &format!("⛔ {} looks halted (no trades for {} min), entries paused", symbol, minutes));
+        }
+    } else if state.halts.clear(symbol) {
+        info!("✅ {} - Trading resumed, halt cleared", symbol);
+        state.logger.info("Stocks", &format!("✅ {} trading resumed", symbol));
+    }
+}
+
+// A big open-vs-prior-close jump distorts momentum and the crossover for the
+// next few bars; with GAP_SKIP_ENTRIES, stock buys wait it out
+async fn gap_blocks_entry(state: &AppState, symbol: &str, bars: &[alpaca::Bar], price: f64, signal: f64) -> bool {
+    let config = state.config.read().await.gaps.clone();
+    let Some(gap) = gap::blocks_entry(bars, config.threshold_pct, config.max_bars, price) else {
+        return false;
+    };
+    info!("🕳️  {} - Gap {:+.2}% ({} bars ago, ${:.2} -> ${:.2}), not yet filled", 
+          symbol, gap.pct, gap.bars_ago, gap.prior_close, gap.open);
+    if !config.skip_entries {
+        return false;
+    }
+    info!("🕳️  {} - Buy ({:.3}) skipped until the gap fills", symbol, signal);
+    state.logger.info("Gaps", &format!("{} buy skipped, {:+.2}% gap not filled yet", symbol, gap.pct));
+    true
+}
+
+async fn flatten_all_positions(state: &AppState) {
+    let positions = match state.alpaca.get_positions().await {
+        Ok(p) => p,
+        Err(e) => {
+            error!("❌ Could not fetch positions to flatten: {}", e);
+            return;
+        }
+    };
+    
+    for pos in &positions {
+        let is_crypto = symbols::is_crypto_symbol(&pos.symbol);
+        let result = if is_crypto {
+            state.crypto.close_crypto_position(&pos.symbol).await
+        } else {
+            state.alpaca.close_position(&pos.symbol).await
+        };
+        
+        match result {
+            Ok(close_order) => {
+                let pnl: f64 = pos.unrealized_pl.parse().unwrap_or(0.0);
+                let trade = TradeRecord {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    timestamp: Utc::now().to_rfc3339(),
+                    symbol: pos.symbol.clone(),
+                    action: "SELL".to_string(),
+                    quantity: pos.qty.parse().unwrap_or(0.0),
+                    price: pos.current_price.parse().unwrap_or(0.0),
+                    pnl,
+                    fill_price: None,
+                    slippage_flagged: false,
+                    slippage_pct: None,
+                    reason: Some(TradeReason::Flatten),
+                    asset_class: symbols::asset_class(&pos.symbol).to_string(),
+                };
+                let entry = pos.avg_entry_price.parse().unwrap_or(0.0);
+                tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), close_order.id, entry));
+                state.trade_history.write().await.push(trade);
+                info!("🛑 Flattened {} - P&L: ${:.2}", pos.symbol, pnl);
+                state.logger.trade(LogLevel::Warning, &format!("🛑 Closed by equity floor | P&L: ${:.2}", pnl), &pos.symbol);
+            },
+            Err(e) => error!("❌ Failed to flatten {}: {}", pos.symbol, e),
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+// Emits exactly one summary each time the stock session goes from open to closed
+async fn session_summary_loop(state: AppState) {
+    let mut tick = interval(Duration::from_secs(30));
+    let mut was_open = is_market_open(Utc::now());
+    
+    loop {
+        tick.tick().await;
+        let now = Utc::now();
+        if session_just_closed(&mut was_open, now) {
+            emit_session_summary(&state, now).await;
+        }
+    }
+}
+
+// True on the first check after the session closes. `was_open` carries the
+// previous check's answer.
+fn session_just_closed(was_open: &mut bool, now: DateTime<Utc>) -> bool {
+    let open = is_market_open(now);
+    let closed = *was_open && !open;
+    *was_open = open;
+    closed
+}
+
+pub async fn emit_session_summary(state: &AppState, now: DateTime<Utc>) -> SessionSummary {
+    let ending_equity = match state.alpaca.get_account().await {
+        Ok(account) => account.portfolio_value.parse().unwrap_or(0.0),
+        Err(_) => state.portfolio_history.read().await.last().map(|s| s.total_value).unwrap_or(0.0),
+    };
+    let summary = session::summarize(
+        &state.trade_history.read().await,
+        session::session_open(now),
+        now,
+        ending_equity,
+    );
+    
+    info!("🔔 ========== SESSION SUMMARY ==========");
+    info!("🔔 {} trades ({} buys, {} sells) | Realized P&L: ${:.2} | Win rate: {:.0}% | Equity: ${:.2}",
+          summary.trades, summary.buys, summary.sells, summary.realized_pnl, summary.win_rate * 100.0, ending_equity);
+    if let (Some(best), Some(worst)) = (&summary.best_trade, &summary.worst_trade) {
+        info!("🔔 Best: {} ${:.2} | Worst: {} ${:.2}", best.symbol, best.pnl, worst.symbol, worst.pnl);
+    }
+    state.logger.success("Session", &format!(
+        "🔔 Market closed: {} trades, P&L ${:.2}, win rate {:.0}%, equity ${:.2}",
+        summary.trades, summary.realized_pnl, summary.win_rate * 100.0, ending_equity
+    ));
+    
+    let webhook = state.config.read().await.session.webhook_url.clone();
+    if !webhook.is_empty() {
+        if let Err(e) = reqwest::Client::new().post(&webhook).json(&summary).send().await {
+            warn!("⚠️  Session summary webhook failed: {}", e);
+        }
+    }
+    
+    // Per-session state: entry confirmation streaks don't carry overnight
+    state.buy_streaks.clear();
+    *state.last_session.write().await = Some(summary.clone());
+    summary
+}
+
+// CHECK IF MARKET IS OPEN (9:30 AM - 4:00 PM ET, Monday-Friday)
+pub fn is_market_open(now: DateTime<Utc>) -> bool {
+    let now = now.with_timezone(&chrono_tz::America::New_York);
+    let weekday = now.weekday().number_from_monday(); // 1=Mon, 7=Sun
+    let is_weekday = weekday <= 5; // Mon-Fri
+    let market_open = chrono::NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+    let market_close = chrono::NaiveTime::from_hms_opt(16, 0, 0).unwrap();
+    let current_time = now.time();
+    
+    is_weekday && current_time >= market_open && current_time < market_close
+}
+
+// Entry window like "08:00-22:00" in the given timezone, used for crypto hours
+// and per-symbol windows. Windows may wrap midnight ("20:00-02:00").
+// Empty or unparseable = always open.
+pub fn in_time_window(now: DateTime<Utc>, window: &str, timezone: &str) -> bool {
+    let Some((start, end)) = window.split_once('-') else {
+        return true;
+    };
+    let (Ok(start), Ok(end)) = (
+        chrono::NaiveTime::parse_from_str(start.trim(), "%H:%M"),
+        chrono::NaiveTime::parse_from_str(end.trim(), "%H:%M"),
+    ) else {
+        return true;
+    };
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let current_time = now.with_timezone(&tz).time();
+    
+    if start <= end {
+        current_time >= start && current_time < end
+    } else {
+        current_time >= start || current_time < end
+    }
+}
+
+// Per-symbol ENTRY_WINDOWS; symbols without one are never blocked here
+async fn outside_entry_window(state: &AppState, symbol: &str, signal: f64) -> bool {
+    let (window, timezone) = {
+        let config = state.config.read().await;
+        let Some(window) = config.instruments.entry_window_for(symbol) else {
+            return false;
+        };
+        (window.to_string(), config.instruments.entry_window_timezone.clone())
+    };
+    if in_time_window(Utc::now(), &window, &timezone) {
+        return false;
+    }
+    info!("🕰️  {} - Buy ({:.3}) suppressed outside its entry window {} {}", symbol, signal, window, timezone);
+    state.logger.info("Window", &format!("{} buy suppressed outside entry window {}", symbol, window));
+    true
+}
+
+// Re-evaluates the stock or crypto regime from the returns recorded by the
+// previous cycles
+async fn update_volatility_regime(state: &AppState, is_crypto: bool) {
+    let config = state.config.read().await.volatility_regime.clone();
+    if !config.enabled {
+        return;
+    }
+    let Some(vol) = state.correlations.average_volatility(|s| symbols::is_crypto_symbol(s) == is_crypto) else {
+        return;
+    };
+    let threshold = if is_crypto { config.crypto_threshold_pct } else { config.stock_threshold_pct };
+    let high = vol > threshold;
+    if state.volatility_regime.set(is_crypto, high) {
+        let class = if is_crypto { "Crypto" } else { "Stock" };
+        if high {
+            warn!("🌪️  {} volatility regime HIGH ({:.3}% > {}%) - sizing x{}, buy threshold +{}",
+                  class, vol, threshold, config.size_multiplier, config.buy_threshold_bump);
+            state.logger.warning("Regime", &format!("🌪️ {} volatility HIGH ({:.3}%), reducing exposure", class, vol));
+        } else {
+            info!("🌤️  {} volatility regime back to normal ({:.3}% <= {}%)", class, vol, threshold);
+            state.logger.info("Regime", &format!("🌤️ {} volatility normal ({:.3}%)", class, vol));
+        }
+    }
+}
+
+// MAX_NEW_ENTRIES_PER_CYCLE: once this cycle has opened that many positions,
+// further buys wait for the next one (exits are never limited)
+async fn entry_limit_reached(state: &AppState, symbol: &str, signal: f64) -> bool {
+    let max = state.config.read().await.entry_limits.max_per_cycle;
+    if max == 0 {
+        return false;
+    }
+    let placed = state.cycle_entries.get(symbols::asset_class(symbol)).map_or(0, |n| *n);
+    if placed < max {
+        return false;
+    }
+    info!("🚦 {} - Buy ({:.3}) deferred, {} new entries already placed this cycle", symbol, signal, placed);
+    state.logger.info("Entries", &format!("{} buy deferred to next cycle ({} entries/cycle limit)", symbol, max));
+    true
+}
+
+// Penny stocks and sub-cent coins give silly quantities and noisy signals
+async fn below_min_price(state: &AppState, symbol: &str, price: f64, is_crypto: bool) -> bool {
+    let floor = {
+        let limits = &state.config.read().await.entry_limits;
+        if is_crypto { limits.crypto_min_price } else { limits.stock_min_price }
+    };
+    if floor <= 0.0 || price >= floor {
+        return false;
+    }
+    info!("🪙 {} - Price ${} below the ${} minimum, skipping entry", symbol, price, floor);
+    state.logger.info("Entries", &format!("{} skipped, price ${} below ${} minimum", symbol, price, floor));
+    true
+}
+
+// Fresh bars and cold sentiment make the first signals after startup or a
+// mode switch unreliable, so ENTRY_GRACE_PERIOD_SECS holds off entries
+pub async fn restart_grace_period(state: &AppState) {
+    *state.grace_started.write().await = Utc::now();
+}
+
+async fn in_grace_period(state: &AppState, symbol: &str, signal: f64) -> bool {
+    let grace_secs = state.config.read().await.entry_limits.grace_period_secs;
+    let elapsed = (Utc::now() - *state.grace_started.read().await).num_seconds();
+    if elapsed >= grace_secs {
+        return false;
+    }
+    info!("⏳ {} - Buy ({:.3}) deferred, {}s of the {}s grace period left", symbol, signal, grace_secs - elapsed, grace_secs);
+    state.logger.info("Entries", &format!("{} buy deferred, grace period ({}s left)", symbol, grace_secs - elapsed));
+    true
+}
+
+// MIN_HOLD_MINUTES: profit-taking and signal exits wait until the holding is
+// old enough, unless it's already down past the catastrophic stop
+async fn hold_defers_exit(state: &AppState, symbol: &str, pos: &alpaca::Position, current_price: f64, exit: &str) -> bool {
+    let config = state.config.read().await.holding.clone();
+    if config.min_hold_minutes <= 0 {
+        return false;
+    }
+    let Some(opened) = holding::opened_at(&state.trade_history.read().await, symbol) else {
+        return false;
+    };
+    let Some(left) = holding::hold_remaining(opened, chrono::Duration::minutes(config.min_hold_minutes), Utc::now()) else {
+        return false;
+    };
+    
+    let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+    let change_pct = if entry > 0.0 { (current_price - entry) / entry * 100.0 } else { 0.0 };
+    if config.stop_override_loss_pct > 0.0 && change_pct <= -config.stop_override_loss_pct {
+        warn!("🛑 {} - Down {:.1}% (stop {:.1}%), {} allowed inside the minimum hold", symbol, change_pct, config.stop_override_loss_pct, exit);
+        state.logger.warning("Exits", &format!("{} {} inside the minimum hold, down {:.1}%", symbol, exit, change_pct));
+        return false;
+    }
+    
+    // Exits get re-checked every cycle, so each holding is logged once per exit kind
+    let key = format!("{}:{}", symbols::normalize(symbol), exit);
+    if state.exits_deferred.insert(key, opened) == Some(opened) {
+        debug!("⏳ {} - {} deferred, {}m of the minimum hold left", symbol, exit, left.num_minutes() + 1);
+    } else {
+        info!("⏳ {} - {} deferred, {}m of the {}m minimum hold left", symbol, exit, left.num_minutes() + 1, config.min_hold_minutes);
+        state.logger.info("Exits", &format!("{} {} deferred, minimum hold ({}m left)", symbol, exit, left.num_minutes() + 1));
+    }
+    true
+}
+
+// Pattern day trader rule: a sub-$25k margin account making a 4th day trade
+// in five business days gets restricted, so once the allowance is used up a
+// stock bought today is held until tomorrow instead of sold
+async fn pdt_blocks_exit(state: &AppState, symbol: &str, exit: &str) -> bool {
+    let config = state.config.read().await.pdt.clone();
+    if !config.enabled || symbols::is_crypto_symbol(symbol) {
+        return false;
+    }
+    let today = pdt::today(Utc::now());
+    let local_count = {
+        let history = state.trade_history.read().await;
+        if !pdt::bought_on(&history, symbol, today) {
+            return false;
+        }
+        pdt::day_trades(&history, pdt::window_start(today))
+    };
+    
+    // Alpaca's count also sees trades made outside the engine
+    let (equity, broker_count) = match state.alpaca.get_account().await {
+        Ok(account) => (account.portfolio_value.parse().unwrap_or(0.0), account.daytrade_count),
+        Err(e) => {
+            warn!("⚠️  {} - Could not check equity for the PDT guard: {}", symbol, e);
+            (state.portfolio_history.read().await.last().map(|s| s.total_value).unwrap_or(0.0), 0)
+        }
+    };
+    let day_trades = local_count.max(broker_count as usize);
+    if equity >= config.equity_threshold || day_trades < config.max_day_trades as usize {
+        return false;
+    }
+    
+    // Logged once per symbol and day, the exit is retried every cycle
+    let day_start = today.and_time(chrono::NaiveTime::MIN).and_utc();
+    let key = format!("{}:pdt:{}", symbols::normalize(symbol), exit);
+    if state.exits_deferred.insert(key, day_start) == Some(day_start) {
+        debug!("🚷 {} - {} still blocked by the PDT guard", symbol, exit);
+    } else {
+        warn!("🚷 {} - {} blocked, {}/{} day trades used with equity ${:.2} under ${:.0}; holding until tomorrow",
+              symbol, exit, day_trades, config.max_day_trades, equity, config.equity_threshold);
+        state.logger.warning("PDT", &format!("{} {} blocked, would be day trade #{} (equity ${:.2})", symbol, exit, day_trades + 1, equity));
+    }
+    true
+}
+
+// A spread that's wide next to the symbol's typical move (ATR) makes the
+// round trip expensive. No ATR yet or no quote lets the entry through.
+async fn spread_blocks_entry(state: &AppState, symbol: &str, signal: f64, is_crypto: bool) -> bool {
+    let max_ratio = state.config.read().await.entry_limits.max_spread_atr_ratio;
+    if max_ratio <= 0.0 {
+        return false;
+    }
+    let Some(atr) = state.atr.get(&symbols::normalize(symbol)).map(|a| *a).filter(|a| *a > 0.0) else {
+        return false;
+    };
+    let quote = if is_crypto {
+        state.crypto.get_crypto_quote_detail(symbol).await
+    } else {
+        state.alpaca.get_quote_detail(symbol).await
+    };
+    let quote = match quote {
+        Ok(quote) => quote,
+        Err(e) => {
+            warn!("⚠️  {} - No quote for the spread check, allowing entry: {}", symbol, e);
+            return false;
+        }
+    };
+    
+    let ratio = quote.spread / atr;
+    if ratio <= max_ratio {
+        debug!("{} - Spread ${:.4} is {:.2}x ATR ${:.4}", symbol, quote.spread, ratio, atr);
+        return false;
+    }
+    info!("↔️  {} - Buy ({:.3}) skipped, spread ${:.4} is {:.2}x ATR ${:.4} (max {:.2}x)",
+          symbol, signal, quote.spread, ratio, atr, max_ratio);
+    state.logger.info("Entries", &format!("{} skipped, spread {:.2}x ATR (max {:.2}x)", symbol, ratio, max_ratio));
+    true
+}
+
+fn record_cycle_entry(state: &AppState, symbol: &str) {
+    *state.cycle_entries.entry(symbols::asset_class(symbol)).or_insert(0) += 1;
+}
+
+// RISK_PER_TRADE_USD sizing: the notional that loses that many dollars at
+// the stop, capped at `cap`. The stop is STOP_LOSS_PCT below entry when
+// that's set, since it's the one stop_out_if_hit enforces; otherwise
+// RISK_STOP_ATR_MULTIPLIER x ATR below. None (percentage sizing) when it's
+// off or an ATR stop is needed and the symbol has no ATR yet.
+async fn dollar_risk_size(state: &AppState, symbol: &str, price: f64, cap: f64) -> Option<f64> {
+    let (config, stop_loss_pct) = {
+        let config = state.config.read().await;
+        (config.risk_sizing.clone(), config.stops.stop_loss_pct)
+    };
+    if config.risk_per_trade_usd <= 0.0 {
+        return None;
+    }
+    let (stop, basis) = if stop_loss_pct > 0.0 {
+        (price * (1.0 - stop_loss_pct / 100.0), format!("{}% stop-loss", stop_loss_pct))
+    } else {
+        let atr = *state.atr.get(&symbols::normalize(symbol))?;
+        (price - atr * config.stop_atr_multiplier, format!("{}x ATR {:.4}", config.stop_atr_multiplier, atr))
+    };
+    let qty = sizing::risk_qty(config.risk_per_trade_usd, price, stop);
+    if qty <= 0.0 {
+        return None;
+    }
+    let notional = (qty * price).min(cap);
+    info!("⚖️  {} - Risk sizing: ${:.2} at risk to stop ${:.2} ({}) -> ${:.2} notional{}",
+          symbol, config.risk_per_trade_usd, stop, basis, notional,
+          if notional < qty * price { " (capped)" } else { "" });
+    Some(notional)
+}
+
+// The strategy scoring `symbol`: its group's if it has one, else the default
+pub async fn strategy_for(state: &AppState, symbol: &str) -> Arc<dyn Strategy> {
+    let config = state.config.read().await;
+    let Some(group) = config.strategy_group(symbol) else {
+        return state.strategy.clone();
+    };
+    if let Some(strategy) = state.group_strategies.get(&group.name.to_lowercase()) {
+        return strategy.clone();
+    }
+    strategy::builtin(&group.strategy).unwrap_or_else(|| {
+        warn!("⚠️  Unknown strategy '{}' for group '{}', using {}", group.strategy, group.name, state.strategy.name());
+        state.strategy.clone()
+    })
+}
+
+async fn regime_thresholds(state: &AppState, symbol: &str, is_crypto: bool) -> Thresholds {
+    let config = state.config.read().await;
+    let mut thresholds = config.thresholds_for(symbol, is_crypto);
+    if config.volatility_regime.enabled && state.volatility_regime.is_high(is_crypto) {
+        thresholds.buy += config.volatility_regime.buy_threshold_bump;
+    }
+    thresholds
+}
+
+async fn regime_size_multiplier(state: &AppState, is_crypto: bool) -> f64 {
+    let config = state.config.read().await;
+    if config.volatility_regime.enabled && state.volatility_regime.is_high(is_crypto) {
+        config.volatility_regime.size_multiplier.clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+// Entry size for `symbol` at `price` and which limit set it, for GET /sizer.
+// Mirrors the sizing in process_stock / process_crypto (keep them in step)
+// without logging, clamping notices or placing anything.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntrySize {
+    pub qty: f64,
+    pub notional: f64,
+    pub buying_power: f64,
+    pub size_pct: f64,
+    pub max_notional: f64,
+    pub risk_notional: Option<f64>,  // Set when RISK_PER_TRADE_USD sizing applies
+    pub regime_multiplier: f64,
+    pub increment: f64,
+    pub held: f64,
+    pub max_units: Option<f64>,
+    pub adv: Option<f64>,  // Stocks only, when MAX_ADV_PCT is on
+    pub max_adv_pct: f64,
+    pub binding: &'static str,  // buying_power_pct, max_notional, buying_power, risk, volatility_regime, unit_cap or adv
+}
+
+pub async fn preview_entry_size(
+    state: &AppState,
+    symbol: &str,
+    price: f64,
+    is_crypto: bool,
+    buying_power: f64,
+    positions: &[alpaca::Position],
+    bars: &[alpaca::Bar],
+) -> EntrySize {
+    let max_notional = if is_crypto { 2000.0 } else { 5000.0 };
+    let (size_pct, increment, max_units, max_adv_pct) = {
+        let config = state.config.read().await;
+        (
+            config.entry_size_pct(symbol, is_crypto),
+            config.instruments.increment_for(symbol, is_crypto),
+            config.instruments.max_units_for(symbol),
+            if is_crypto { 0.0 } else { config.instruments.max_adv_pct },
+        )
+    };
+    
+    let pct_notional = buying_power * size_pct / 100.0;
+    let mut binding = if pct_notional <= max_notional { "buying_power_pct" } else { "max_notional" };
+    let mut position_size = pct_notional.min(max_notional);
+    
+    let risk_cap = buying_power.min(max_notional);
+    let risk_notional = dollar_risk_size(state, symbol, price, risk_cap).await;
+    if let Some(notional) = risk_notional {
+        position_size = notional;
+        binding = if notional < risk_cap {
+            "risk"
+        } else if buying_power < max_notional {
+            "buying_power"
+        } else {
+            "max_notional"
+        };
+    }
+    
+    let regime_multiplier = regime_size_multiplier(state, is_crypto).await;
+    if regime_multiplier < 1.0 {
+        position_size *= regime_multiplier;
+        binding = "volatility_regime";
+    }
+    
+    let mut qty = if price > 0.0 { sizing::round_down_to_increment(position_size / price, increment) } else { 0.0 };
+    
+    let held: f64 = positions.iter()
+        .find(|p| symbols::same_symbol(&p.symbol, symbol))
+        .and_then(|p| p.qty.parse().ok())
+        .unwrap_or(0.0);
+    if let Some(max_units) = max_units {
+        let allowed = sizing::clamp_to_max_units(qty, held, max_units, increment);
+        if allowed < qty {
+            qty = allowed;
+            binding = "unit_cap";
+        }
+    }
+    
+    let adv = (max_adv_pct > 0.0).then(|| {
+        let volumes: Vec<f64> = bars.iter().map(|b| b.v as f64).collect();
+        sizing::estimate_daily_volume(&volumes, sizing::STOCK_BARS_PER_DAY)
+    });
+    if let Some(adv) = adv {
+        let allowed = sizing::clamp_to_participation(qty, adv, max_adv_pct, increment);
+        if allowed < qty {
+            qty = allowed;
+            binding = "adv";
+        }
+    }
+    
+    EntrySize {
+        qty,
+        notional: qty * price,
+        buying_power,
+        size_pct,
+        max_notional,
+        risk_notional,
+        regime_multiplier,
+        increment,
+        held,
+        max_units,
+        adv,
+        max_adv_pct,
+        binding,
+    }
+}
+
+// With CONFIDENCE_SIZING_ENABLED, entries shrink toward CONFIDENCE_SIZE_FLOOR
+// as confidence falls. Strategies without an explanation size normally.
+async fn confidence_size_multiplier(state: &AppState, symbol: &str, explanation: Option<&SignalExplanation>) -> f64 {
+    let (enabled, floor) = {
+        let config = state.config.read().await;
+        (config.risk_sizing.confidence_scaling, config.risk_sizing.confidence_floor.clamp(0.0, 1.0))
+    };
+    let Some(explanation) = explanation.filter(|_| enabled) else {
+        return 1.0;
+    };
+    let multiplier = floor + (1.0 - floor) * explanation.confidence.clamp(0.0, 1.0);
+    info!("🎯 {} - Confidence {:.2}, sizing at {:.0}%", symbol, explanation.confidence, multiplier * 100.0);
+    multiplier
+}
+
+// One full pass over the active stock universe. Waits for a manual cycle
+// (POST /cycle/run) already in progress rather than overlapping it.
+pub async fn run_stock_cycle(state: &AppState) -> CycleSummary {
+    let _running = state.stock_cycle_lock.lock().await;
+    stock_cycle(state).await
+}
+
+// See run_stock_cycle
+pub async fn run_crypto_cycle(state: &AppState) -> CycleSummary {
+    let _running = state.crypto_cycle_lock.lock().await;
+    crypto_cycle(state).await
+}
+
+// Runs one cycle now, out of band. None if a cycle for that asset class is
+// already running, scheduled or manual.
+pub async fn try_run_cycle(state: &AppState, is_crypto: bool) -> Option<CycleSummary> {
+    if is_crypto {
+        let _running = state.crypto_cycle_lock.try_lock().ok()?;
+        Some(crypto_cycle(state).await)
+    } else {
+        let _running = state.stock_cycle_lock.try_lock().ok()?;
+        Some(stock_cycle(state).await)
+    }
+}
+
+async fn stock_cycle(state: &AppState) -> CycleSummary {
+    // Get symbols based on current trading mode
+    let mode = state.trading_mode.read().await.clone();
+    let symbols = active_stocks(state).await;
+    update_volatility_regime(state, false).await;
+    state.cycle_entries.remove("stock");
+    
+    info!("📈 Trading Mode: {:?} | Analyzing {} symbols", mode, symbols.len());
+    info!("📈 ========== STOCK TRADING CYCLE START ==========");
+    state.logger.info("Stocks", "🔄 Starting market analysis cycle");
+    
+    let mut summary = CycleSummary::default();
+    let cycle_start = Instant::now();
+    
+    // One batched quote request per cycle; symbols missing from it are fetched individually
+    let quotes = if state.config.read().await.data.batch_requests {
+        state.alpaca.get_latest_quotes(&symbols.iter().map(String::as_str).collect::<Vec<_>>()).await.unwrap_or_else(|e| {
+            warn!("⚠️  Batched quote request failed, fetching per symbol: {}", e);
+            HashMap::new()
+        })
+    } else {
+        HashMap::new()
+    };
+    
+    for symbol in &symbols {
+        let symbol_start = Instant::now();
+        match process_stock(state, symbol, quotes.get(symbol).copied()).await {
+            Ok(result) => summary.record(&result),
+            Err(e) => {
+                summary.failed += 1;
+                error!("❌ Error processing stock {}: {}", symbol, e);
+            }
+        }
+        state.metrics.observe_since(SYMBOL_PROCESSING_SECONDS, &[("asset_class", "stock")], symbol_start);
+        
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+    
+    state.metrics.observe_since(CYCLE_DURATION_SECONDS, &[("asset_class", "stock")], cycle_start);
+    
+    info!("📊 Cycle Summary: {} analyzed | {} BUY signals | {} SELL signals | {} neutral | {} failed", 
+          summary.analyzed, summary.buy_signals, summary.sell_signals, summary.neutral, summary.failed);
+    
+    state.logger.info("Stocks", &format!(
+        "Cycle complete: {} stocks analyzed, {} buy signals, {} sell signals",
+        summary.analyzed, summary.buy_signals, summary.sell_signals
+    ));
+    
+    info!("📈 ========== STOCK TRADING CYCLE END ==========\n");
+    summary
+}
+
+// `quote` is a price already fetched for this cycle; None fetches it here
+pub async fn process_stock(state: &AppState, symbol: &str, quote: Option<f64>) -> Result<String> {
+    info!("🔍 Analyzing {}", symbol);
+    
+    // Get current live price
+    let latest = match quote {
+        Some(price) => Ok(price),
+        None => state.alpaca.get_latest_quote(symbol).await,
+    };
+    let current_price = match latest {
+        Ok(price) => {
+            info!("💵 {} LIVE PRICE: ${:.2}", symbol, price);
+            price
+        },
+        Err(e) if report_entitlement(state, &e) => return Ok("entitlement_limited".to_string()),
+        Err(e) => {
+            warn!("⚠️  {} - Could not fetch live price: {}", symbol, e);
+            state.logger.warning("Data", &format!("{} - Price fetch failed", symbol));
+            return Err(e);
+        }
+    };
+    
+    // Get historical bars
+    let bars = match state.alpaca.get_bars(symbol, "5Min", 50).await {
+        Ok(bars) if bars.len() >= 20 => {
+            info!("📊 {} - Got {} bars for analysis", symbol, bars.len());
+            bars
+        }
+        Ok(bars) => {
+            info!("⚠️  {} - Only {} bars (need 20+), skipping", symbol, bars.len());
+            return Ok("insufficient_data".to_string());
+        }
+        Err(e) if report_entitlement(state, &e) => return Ok("entitlement_limited".to_string()),
+        Err(e) => {
+            warn!("❌ {} - Failed to fetch bars: {}", symbol, e);
+            return Err(e);
+        }
+    };
+    
+    let sentiment = state.news.get_sentiment(symbol);
+    let (indicators, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    update_atr(state, symbol, &bars).await;
+    state.correlations.record(symbol, &bars.iter().map(|b| b.c).collect::<Vec<_>>());
+    state.recorder.record_bars(symbol, &bars);
+    state.recorder.record(ReplayEvent::Quote { symbol: symbol.to_string(), price: current_price });
+    update_halt_status(state, symbol, &bars).await;
+    let ctx = StrategyContext { symbol, is_crypto: false, current_price, indicators: &indicators, rng_seed };
+    let (signal, explanation) = strategy_for(state, symbol).await.evaluate(&bars, sentiment, &ctx);
+    
+    info!("📈 {} ANALYSIS: Signal={:.3}, Sentiment={:.3}", symbol, signal, sentiment);
+    cache_signal(state, symbol, current_price, signal, sentiment, explanation.as_ref(), &bars);
+    
+    log_analysis(state, symbol, current_price, signal, sentiment, explanation.as_ref(), false).await;
+    
+    let positions = match state.alpaca.get_positions().await {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Failed to fetch positions: {}", e);
+            return Err(e);
+        }
+    };
+    
+    let has_position = positions.iter().any(|p| p.symbol == symbol);
+    
+    // CONSERVATIVE THRESHOLDS - Smarter, fewer trades
+    // BUY when signal > 0.15 (strong bullish) for the configured number of cycles
+    // SELL when signal < -0.15 (strong bearish) OR profit > 15%
+    let thresholds = regime_thresholds(state, symbol, false).await;
+    let buy_streak = update_buy_streak(state, symbol, signal, thresholds.buy);
+    let action = decide_action(signal, has_position, buy_streak, &thresholds);
+    state.recorder.record(ReplayEvent::Decision { symbol: symbol.to_string(), signal, action });
+    
+    // PROFIT TAKING: Auto-sell once the position reaches its profit target
+    if has_position {
+        if let Some(pos) = positions.iter().find(|p| p.symbol == symbol) {
+            let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+            let (target, basis) = profit_target_pct(state, symbol, entry, false).await;
+            info!("🎯 {} profit target: {:.2}% above entry ({})", symbol, target, basis);
+            if stop_out_if_hit(state, symbol, pos, current_price, false).await {
+                return Ok("stopped_out".to_string());
+            }
+            if take_profit_if_due(state, symbol, pos, current_price, false).await {
+                return Ok("profit_taking".to_string());
+            }
+        }
+    }
+    
+    if action == TradeAction::Buy {
+        // Backoff only holds back new entries, exits still get checked every cycle
+        if let Some(remaining) = state.order_backoff.remaining_secs(symbol) {
+            info!("⏸️  {} - Buy signal skipped, backing off after repeated order failures ({}s left)", symbol, remaining);
+            return Ok("backoff".to_string());
+        }
+        if equity_floor_blocks_entry(state, symbol).await {
+            return Ok("equity_floor".to_string());
+        }
+        if state.halts.is_halted(symbol) {
+            info!("⛔ {} - Buy signal ({:.3}) skipped, symbol appears halted", symbol, signal);
+            return Ok("halted".to_string());
+        }
+        if outside_entry_window(state, symbol, signal).await {
+            return Ok("outside_window".to_string());
+        }
+        if below_min_price(state, symbol, current_price, false).await {
+            return Ok("below_min_price".to_string());
+        }
+        if gap_blocks_entry(state, symbol, &bars, current_price, signal).await {
+            return Ok("gap".to_string());
+        }
+        if in_grace_period(state, symbol, signal).await {
+            return Ok("grace_period".to_string());
+        }
+        if entry_limit_reached(state, symbol, signal).await {
+            return Ok("entry_limit".to_string());
+        }
+        if spread_blocks_entry(state, symbol, signal, false).await {
+            return Ok("wide_spread".to_string());
+        }
+        
+        info!("🟢 {} STRONG BUY SIGNAL ({:.3}) - EXECUTING TRADE", symbol, signal);
+        state.logger.signal(&format!("🟢 BUY signal ({:.3})", signal), symbol);
+        
+        let account = match state.alpaca.get_account().await {
+            Ok(acc) => acc,
+            Err(e) => {
+                error!("Failed to get account: {}", e);
+                return Err(e);
+            }
+        };
+        
+        let buying_power: f64 = account.buying_power.parse().unwrap_or(0.0);
+        info!("💰 Available buying power: ${:.2}", buying_power);
+        
+        let size_pct = state.config.read().await.entry_size_pct(symbol, false);
+        let position_size = (buying_power * size_pct / 100.0).min(5000.0); // 5% of buying power (or the group's), max $5k
+        let position_size = dollar_risk_size(state, symbol, current_price, buying_power.min(5000.0)).await
+            .unwrap_or(position_size);
+        let position_size = position_size * regime_size_multiplier(state, false).await;
+        let position_size = position_size * confidence_size_multiplier(state, symbol, explanation.as_ref()).await;
+        let lot_size = state.config.read().await.instruments.increment_for(symbol, false);
+        let qty = sizing::round_down_to_increment(position_size / current_price, lot_size);
+        let qty = apply_unit_cap(state, symbol, qty, &positions, lot_size).await;
+        let qty = apply_adv_cap(state, symbol, qty, &bars, lot_size).await;
+        if qty > 0.0 && correlation_blocks_entry(state, symbol, qty * current_price).await {
+            return Ok("correlation_limit".to_string());
+        }
+        if qty > 0.0 && leverage_blocks_entry(state, symbol, qty * current_price, &positions, &account).await {
+            return Ok("max_leverage".to_string());
+        }
+        
+        info!("📦 Calculated order: {} shares of {} at ${:.2} (${:.2} total, lot size {})", 
+              qty, symbol, current_price, qty * current_price, lot_size);
+        
+        if qty > 0.0 && !trade_approved(state, symbol, "buy", qty, current_price, signal).await {
+            return Ok("not_approved".to_string());
+        }
+        
+        if qty > 0.0 {
+            // Symbols that filled badly before only get limit orders
+            let limit_price = state.slippage.limit_price(symbol, "buy", current_price);
+            let order = OrderRequest {
+                symbol: symbol.to_string(),
+                qty: sizing::format_qty(qty, lot_size),
+                side: "buy".to_string(),
+                order_type: if limit_price.is_some() { "limit" } else { "market" }.to_string(),
+                time_in_force: "day".to_string(),
+                limit_price: limit_price.map(slippage::format_limit_price),
+                reference_price: Some(current_price),
+            };
+            
+            info!("📤 Submitting BUY order for {} shares of {}...", qty, symbol);
+            
+            match state.alpaca.place_order(order).await {
+                Ok(order_response) => {
+                    state.order_backoff.record_success(symbol);
+                    state.buy_streaks.remove(symbol);
+                    record_cycle_entry(state, symbol);
+                    info!("✅ ORDER PLACED! {} - {} shares at ${:.2}", symbol, qty, current_price);
+                    state.logger.trade(
+                        LogLevel::Success,
+                        &format!("✅ BUY {} shares at ${:.2} (Order ID: {})", qty, current_price, &order_response.id[..8]),
+                        symbol
+                    );
+                    
+                    let trade = TradeRecord {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        timestamp: Utc::now().to_rfc3339(),
+                        symbol: symbol.to_string(),
+                        action: "BUY".to_string(),
+                        quantity: qty,
+                        price: current_price,
+                        pnl: 0.0,
+                        fill_price: None,
+                        slippage_flagged: false,
+                        slippage_pct: None,
+                        reason: Some(TradeReason::Signal),
+                        asset_class: symbols::asset_class(symbol).to_string(),
+                    };
+                    tokio::spawn(confirm_fill(state.clone(), trade.id.clone(), order_response.id.clone(), "buy"));
+                    state.trade_history.write().await.push(trade);
+                    
+                    return Ok("buy".to_string());
+                },
+                Err(e) => {
+                    error!("❌ ORDER FAILED for {}: {}", symbol, e);
+                    state.logger.trade(LogLevel::Error, &format!("Failed: {}", e), symbol);
+                    record_order_failure(state, symbol, &e);
+                }
+            }
+        } else {
+            warn!("⚠️  {} - Quantity rounds to 0 (lot size {}), skipping trade", symbol, lot_size);
+        }
+    } else if action == TradeAction::Sell && state.notes.has_tag(symbol, NO_AUTO_SELL) {
+        info!("📝 {} - Sell signal ({:.3}) ignored, position is tagged {}", symbol, signal, NO_AUTO_SELL);
+    } else if action == TradeAction::Sell {
+        info!("🔴 {} STRONG SELL SIGNAL ({:.3}) - EXECUTING TRADE", symbol, signal);
+        state.logger.signal(&format!("🔴 SELL signal ({:.3})", signal), symbol);
+        
+        if let Some(pos) = positions.iter().find(|p| p.symbol == symbol) {
+            if hold_defers_exit(state, symbol, pos, current_price, "sell signal").await {
+                return Ok("min_hold".to_string());
+            }
+            if pdt_blocks_exit(state, symbol, "sell signal").await {
+                return Ok("pdt".to_string());
+            }
+            let pnl: f64 = pos.unrealized_pl.parse().unwrap_or(0.0);
+            if !trade_approved(state, symbol, "sell", pos.qty.parse().unwrap_or(0.0), current_price, signal).await {
+                return Ok("not_approved".to_string());
+            }
+            info!("📤 Submitting SELL order to close {} position (P&L: ${:.2})...", symbol, pnl);
+            
+            match state.alpaca.close_position(symbol).await {
+                Ok(close_order) => {
+                    state.order_backoff.record_success(symbol);
+                    info!("✅ POSITION CLOSED! {} - P&L: ${:.2}", symbol, pnl);
+                    state.logger.trade(
+                        LogLevel::Success,
+                        &format!("✅ SELL at ${:.2} | P&L: ${:.2}", current_price, pnl),
+                        symbol
+                    );
+                    
+                    let trade = TradeRecord {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        timestamp: Utc::now().to_rfc3339(),
+                        symbol: symbol.to_string(),
+                        action: "SELL".to_string(),
+                        quantity: pos.qty.parse().unwrap_or(0.0),
+                        price: current_price,
+                        pnl,
+                        fill_price: None,
+                        slippage_flagged: false,
+                        slippage_pct: None,
+                        reason: Some(TradeReason::Signal),
+                        asset_class: symbols::asset_class(symbol).to_string(),
+                    };
+                    let entry = pos.avg_entry_price.parse().unwrap_or(0.0);
+                    tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), close_order.id, entry));
+                    state.trade_history.write().await.push(trade);
+                    
+                    return Ok("sell".to_string());
+                },
+                Err(e) => {
+                    error!("❌ CLOSE FAILED for {}: {}", symbol, e);
+                    state.logger.trade(LogLevel::Error, &format!("Failed: {}", e), symbol);
+                    record_order_failure(state, symbol, &e);
+                }
+            }
+        }
+    } else {
+        if has_position {
+            info!("⚪ {} - Signal {:.3} not strong enough to SELL (threshold: {})", symbol, signal, thresholds.sell);
+        } else if buy_streak > 0 {
+            info!("⏳ {} - Buy signal {:.3} held {}/{} cycles, awaiting confirmation", 
+                  symbol, signal, buy_streak, thresholds.confirmation_cycles);
+        } else {
+            info!("⚪ {} - Signal {:.3} not strong enough to BUY (threshold: {})", symbol, signal, thresholds.buy);
+        }
+        return Ok("neutral".to_string());
+    }
+    
+    Ok("neutral".to_string())
+}
+
+async fn crypto_trading_loop(state: AppState) {
+    // OPTIMAL: 60 seconds - crypto moves fast, need responsive updates
+    // With 5 cryptos = 10 API calls/cycle = 10 calls/min (5% of limit)
+    let mut tick = interval(Duration::from_secs(60));
+    
+    loop {
+        tick.tick().await;
+        let crypto_enabled = *state.crypto_trading_enabled.read().await;
+        if !crypto_enabled || *state.maintenance_mode.read().await { continue; }
+        
+        run_crypto_cycle(&state).await;
+    }
+}
+
+// One full pass over the active crypto universe
+async fn crypto_cycle(state: &AppState) -> CycleSummary {
+    // Get crypto symbols based on current trading mode
+    let mode = state.trading_mode.read().await.clone();
+    let crypto_symbols = mode.get_crypto();
+    update_volatility_regime(state, true).await;
+    state.cycle_entries.remove("crypto");
+    
+    info!("₿ Trading Mode: {:?} | Analyzing {} crypto", mode, crypto_symbols.len());
+    info!("₿ ========== CRYPTO TRADING CYCLE START ==========");
+    state.logger.info("Crypto", "🔄 Starting crypto market analysis");
+    
+    let mut summary = CycleSummary::default();
+    let cycle_start = Instant::now();
+    
+    // All crypto bars in one request; if it fails each symbol fetches its own
+    let mut bars = if state.config.read().await.data.batch_requests {
+        match state.crypto.get_crypto_bars_multi(&crypto_symbols, "5Min", 50).await {
+            Ok(bars) => Some(bars),
+            Err(e) => {
+                warn!("⚠️  Batched crypto bars request failed, fetching per symbol: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    
+    for symbol in &crypto_symbols {
+        let symbol_start = Instant::now();
+        let prefetched = bars.as_mut().map(|b| b.remove(*symbol).unwrap_or_default());
+        match process_crypto(state, symbol, prefetched).await {
+            Ok(result) => summary.record(&result),
+            Err(e) => {
+                summary.failed += 1;
+                error!("❌ Error processing crypto {}: {}", symbol, e);
+            }
+        }
+        state.metrics.observe_since(SYMBOL_PROCESSING_SECONDS, &[("asset_class", "crypto")], symbol_start);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    
+    state.metrics.observe_since(CYCLE_DURATION_SECONDS, &[("asset_class", "crypto")], cycle_start);
+    
+    info!("₿ Crypto Summary: {} analyzed | {} BUY | {} SELL | {} failed", 
+          summary.analyzed, summary.buy_signals, summary.sell_signals, summary.failed);
+    state.logger.info("Crypto", &format!(
+        "Cycle complete: {} cryptos analyzed, {} buy, {} sell",
+        summary.analyzed, summary.buy_signals, summary.sell_signals
+    ));
+    info!("₿ ========== CRYPTO TRADING CYCLE END ==========\n");
+    summary
+}
+
+// `bars` are this cycle's prefetched bars; None fetches them here
+pub async fn process_crypto(state: &AppState, symbol: &str, bars: Option<Vec<CryptoBar>>) -> Result<String> {
+    info!("₿ Analyzing {}", symbol);
+    
+    let current_price = match state.crypto.get_latest_crypto_price(symbol).await {
+        Ok(price) => {
+            info!("💰 {} LIVE PRICE: ${:.2}", symbol, price);
+            price
+        },
+        Err(e) => {
+            warn!("⚠️  {} - Could not fetch crypto price: {}", symbol, e);
+            return Err(e);
+        }
+    };
+    
+    let bars = match bars {
+        Some(bars) => Ok(bars),
+        None => state.crypto.get_crypto_bars(symbol, "5Min", 50).await,
+    };
+    let bars = match bars {
+        Ok(bars) if bars.len() >= 20 => {
+            info!("📊 {} - Got {} crypto bars", symbol, bars.len());
+            let converted_bars: Vec<alpaca::Bar> = bars.iter().map(|b| alpaca::Bar {
+                t: b.t.clone(), o: b.o, h: b.h, l: b.l, c: b.c, v: b.v as i64,
+            }).collect();
+            converted_bars
+        }
+        Ok(bars) => {
+            info!("⚠️  {} - Only {} bars, skipping", symbol, bars.len());
+            return Ok("insufficient_data".to_string());
+        }
+        Err(e) => { warn!("❌ {} - Failed to fetch bars: {}", symbol, e); return Err(e); }
+    };
+    
+    let sentiment = state.news.get_sentiment(symbol);
+    let (indicators, rng_seed) = {
+        let config = state.config.read().await;
+        (config.indicators.clone(), config.strategy.rng_seed)
+    };
+    update_atr(state, symbol, &bars).await;
+    state.correlations.record(symbol, &bars.iter().map(|b| b.c).collect::<Vec<_>>());
+    state.recorder.record_bars(symbol, &bars);
+    state.recorder.record(ReplayEvent::Quote { symbol: symbol.to_string(), price: current_price });
+    let ctx = StrategyContext { symbol, is_crypto: true, current_price, indicators: &indicators, rng_seed };
+    let (signal, explanation) = strategy_for(state, symbol).await.evaluate(&bars, sentiment, &ctx);
+    info!("₿ {} ANALYSIS: Signal={:.3}, Sentiment={:.3}", symbol, signal, sentiment);
+    cache_signal(state, symbol, current_price, signal, sentiment, explanation.as_ref(), &bars);
+    log_analysis(state, symbol, current_price, signal, sentiment, explanation.as_ref(), true).await;
+    
+    let positions = match state.crypto.get_positions().await {
+        Ok(p) => p,
+        Err(e) => {
+            warn!("Failed to fetch crypto positions: {}", e);
+            return Err(e);
+        }
+    };
+    let has_position = positions.iter().any(|p| symbols::same_symbol(&p.symbol, symbol));
+    
+    let thresholds = regime_thresholds(state, symbol, true).await;
+    let buy_streak = update_buy_streak(state, symbol, signal, thresholds.buy);
+    let action = decide_action(signal, has_position, buy_streak, &thresholds);
+    state.recorder.record(ReplayEvent::Decision { symbol: symbol.to_string(), signal, action });
+    if action == TradeAction::Hold && buy_streak > 0 && !has_position {
+        info!("⏳ {} - Buy signal {:.3} held {}/{} cycles, awaiting confirmation", 
+              symbol, signal, buy_streak, thresholds.confirmation_cycles);
+    }
+    
+    // PROFIT TAKING for crypto: Auto-sell once the position reaches its profit target
+    if has_position {
+        if let Some(pos) = positions.iter().find(|p| symbols::same_symbol(&p.symbol, symbol)) {
+            let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+            let (target, basis) = profit_target_pct(state, symbol, entry, true).await;
+            info!("🎯 {} profit target: {:.2}% above entry ({})", symbol, target, basis);
+            if stop_out_if_hit(state, symbol, pos, current_price, true).await {
+                return Ok("stopped_out".to_string());
+            }
+            if take_profit_if_due(state, symbol, pos, current_price, true).await {
+                return Ok("profit_taking".to_string());
+            }
+        }
+    }
+    
+    if action == TradeAction::Buy {
+        if let Some(remaining) = state.order_backoff.remaining_secs(symbol) {
+            info!("⏸️  {} - Buy signal skipped, backing off after repeated order failures ({}s left)", symbol, remaining);
+            return Ok("backoff".to_string());
+        }
+        // Entries only inside the optional crypto window; exits are never gated
+        let hours = state.config.read().await.crypto_hours.clone();
+        if !in_time_window(Utc::now(), &hours.window, &hours.timezone) {
+            info!("🌙 {} - Crypto buy ({:.3}) suppressed outside trading hours {} {}", 
+                  symbol, signal, hours.window, hours.timezone);
+            return Ok("outside_hours".to_string());
+        }
+        if outside_entry_window(state, symbol, signal).await {
+            return Ok("outside_window".to_string());
+        }
+        if equity_floor_blocks_entry(state, symbol).await {
+            return Ok("equity_floor".to_string());
+        }
+        if below_min_price(state, symbol, current_price, true).await {
+            return Ok("below_min_price".to_string());
+        }
+        if in_grace_period(state, symbol, signal).await {
+            return Ok("grace_period".to_string());
+        }
+        if entry_limit_reached(state, symbol, signal).await {
+            return Ok("entry_limit".to_string());
+        }
+        if spread_blocks_entry(state, symbol, signal, true).await {
+            return Ok("wide_spread".to_string());
+        }
+        
+        info!("🟢 {} STRONG CRYPTO BUY SIGNAL ({:.3})", symbol, signal);
+        let account = state.alpaca.get_account().await?;
+        let buying_power: f64 = account.buying_power.parse().unwrap_or(0.0);
+        let size_pct = state.config.read().await.entry_size_pct(symbol, true);
+        let position_size = (buying_power * size_pct / 100.0).min(2000.0);
+        let position_size = dollar_risk_size(state, symbol, current_price, buying_power.min(2000.0)).await
+            .unwrap_or(position_size);
+        let position_size = position_size * regime_size_multiplier(state, true).await;
+        let position_size = position_size * confidence_size_multiplier(state, symbol, explanation.as_ref()).await;
+        let step = state.config.read().await.instruments.increment_for(symbol, true);
+        let qty = sizing::round_down_to_increment(position_size / current_price, step);
+        let qty = apply_unit_cap(state, symbol, qty, &positions, step).await;
+        if qty > 0.0 && correlation_blocks_entry(state, symbol, qty * current_price).await {
+            return Ok("correlation_limit".to_string());
+        }
+        // Leverage is gross across the whole book, and `positions` is only the crypto side of it
+        if qty > 0.0 && leverage_blocks_entry(state, symbol, qty * current_price, &state.alpaca.get_positions().await?, &account).await {
+            return Ok("max_leverage".to_string());
+        }
+        
+        if qty > 0.0 && !trade_approved(state, symbol, "buy", qty, current_price, signal).await {
+            return Ok("not_approved".to_string());
+        }
+        
+        if qty > 0.0 {
+            let limit_price = state.slippage.limit_price(symbol, "buy", current_price);
+            let order = CryptoOrderRequest {
+                symbol: symbol.to_string(), qty: sizing::format_qty(qty, step),
+                side: "buy".to_string(),
+                order_type: if limit_price.is_some() { "limit" } else { "market" }.to_string(),
+                time_in_force: "gtc".to_string(),
+                limit_price: limit_price.map(slippage::format_limit_price),
+                reference_price: Some(current_price),
+            };
+            
+            match state.crypto.place_crypto_order(order).await {
+                Ok(order_response) => {
+                    state.order_backoff.record_success(symbol);
+                    state.buy_streaks.remove(symbol);
+                    record_cycle_entry(state, symbol);
+                    state.profit_tiers_taken.remove(&symbols::normalize(symbol));
+                    info!("✅ CRYPTO ORDER PLACED! {}", symbol);
+                    state.logger.trade(LogLevel::Success, &format!("✅ BUY {:.6} at ${:.2}", qty, current_price), symbol);
+                    let trade = TradeRecord {
+                        id: uuid::Uuid::new_v4().to_string(), timestamp: Utc::now().to_rfc3339(),
+                        symbol: symbol.to_string(), action: "BUY".to_string(),
+                        quantity: qty, price: current_price, pnl: 0.0,
+                        fill_price: None, slippage_flagged: false, slippage_pct: None, reason: Some(TradeReason::Signal),
+                        asset_class: symbols::asset_class(symbol).to_string(),
+                    };
+                    if let Some(order_id) = order_response["id"].as_str() {
+                        tokio::spawn(confirm_fill(state.clone(), trade.id.clone(), order_id.to_string(), "buy"));
+                    }
+                    state.trade_history.write().await.push(trade);
+                    return Ok("buy".to_string());
+                },
+                Err(e) => {
+                    error!("❌ CRYPTO ORDER FAILED: {}", e);
+                    record_order_failure(state, symbol, &e);
+                }
+            }
+        } else {
+            warn!("⚠️  {} - Quantity rounds to 0 (step size {}), skipping trade", symbol, step);
+        }
+    } else if action == TradeAction::Sell && state.notes.has_tag(symbol, NO_AUTO_SELL) {
+        info!("📝 {} - Sell signal ({:.3}) ignored, position is tagged {}", symbol, signal, NO_AUTO_SELL);
+    } else if action == TradeAction::Sell {
+        if let Some(pos) = positions.iter().find(|p| symbols::same_symbol(&p.symbol, symbol)) {
+            if hold_defers_exit(state, symbol, pos, current_price, "sell signal").await {
+                return Ok("min_hold".to_string());
+            }
+            let pnl: f64 = pos.unrealized_pl.parse().unwrap_or(0.0);
+            if !trade_approved(state, symbol, "sell", pos.qty.parse().unwrap_or(0.0), current_price, signal).await {
+                return Ok("not_approved".to_string());
+            }
+            match state.crypto.close_crypto_position(&pos.symbol).await {
+                Ok(close_order) => {
+                    state.order_backoff.record_success(symbol);
+                    state.profit_tiers_taken.remove(&symbols::normalize(symbol));
+                    info!("✅ CRYPTO POSITION CLOSED! {} P&L: ${:.2}", symbol, pnl);
+                    state.logger.trade(LogLevel::Success, &format!("✅ SELL at ${:.2} | P&L: ${:.2}", current_price, pnl), symbol);
+                    let trade = TradeRecord {
+                        id: uuid::Uuid::new_v4().to_string(), timestamp: Utc::now().to_rfc3339(),
+                        symbol: symbol.to_string(), action: "SELL".to_string(),
+                        quantity: pos.qty.parse().unwrap_or(0.0), price: current_price, pnl,
+                        fill_price: None, slippage_flagged: false, slippage_pct: None, reason: Some(TradeReason::Signal),
+                        asset_class: symbols::asset_class(symbol).to_string(),
+                    };
+                    let entry = pos.avg_entry_price.parse().unwrap_or(0.0);
+                    tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), close_order.id, entry));
+                    state.trade_history.write().await.push(trade);
+                    return Ok("sell".to_string());
+                },
+                Err(e) => {
+                    error!("❌ CLOSE FAILED: {}", e);
+                    record_order_failure(state, symbol, &e);
+                }
+            }
+        }
+    }
+    Ok("neutral".to_string())
+}
+
+fn cache_signal(
+    state: &AppState,
+    symbol: &str,
+    price: f64,
+    signal: f64,
+    sentiment: f64,
+    explanation: Option<&SignalExplanation>,
+    bars: &[alpaca::Bar],
+) {
+    state.last_signals.insert(symbols::normalize(symbol), CachedSignal {
+        computed_at: Utc::now(),
+        price,
+        signal,
+        sentiment,
+        explanation: explanation.cloned(),
+        bars: bars.to_vec(),
+    });
+}
+
+// Price + analysis entries for the activity log. With SUPPRESS_NEUTRAL_ANALYSIS_LOGS
+// on, signals inside the buy/sell dead-band are left out (tracing still has them).
+async fn log_analysis(
+    state: &AppState,
+    symbol: &str,
+    current_price: f64,
+    signal: f64,
+    sentiment: f64,
+    explanation: Option<&SignalExplanation>,
+    is_crypto: bool,
+) {
+    let (suppress, with_details, thresholds) = {
+        let config = state.config.read().await;
+        (config.logging.suppress_neutral_analysis, config.logging.signal_details, config.thresholds_for(symbol, is_crypto))
+    };
+    if suppress && signal <= thresholds.buy && signal >= thresholds.sell {
+        return;
+    }
+    
+    let category = if is_crypto { "Crypto Price" } else { "Price" };
+    state.logger.info(category, &format!("{}: ${:.2}", symbol, current_price));
+    let details = explanation
+        .filter(|_| with_details)
+        .and_then(|e| serde_json::to_value(e).ok());
+    state.logger.analysis(
+        &format!("${:.2} | Signal: {:.3} | Sentiment: {:.3}", current_price, signal, sentiment),
+        symbol,
+        details,
+    );
+}
+
+// Optional approval webhook for signal-driven trades; always true when unset
+async fn trade_approved(state: &AppState, symbol: &str, side: &str, qty: f64, price: f64, signal: f64) -> bool {
+    let config = state.config.read().await.approval.clone();
+    if config.webhook_url.is_empty() {
+        return true;
+    }
+    let request = ApprovalRequest { symbol: symbol.to_string(), side: side.to_string(), qty, price, signal };
+    info!("🙋 {} - Asking for approval to {} {} @ ${:.2}", symbol, side, qty, price);
+    let approved = state.approval.approve(&config, &request).await;
+    if !approved {
+        info!("🚫 {} - {} not approved, skipping", symbol, side.to_uppercase());
+        state.logger.warning("Approval", &format!("{} {} not approved, skipped", side.to_uppercase(), symbol));
+    }
+    approved
+}
+
+// Tracks how many consecutive cycles the buy condition has held for a symbol
+fn update_buy_streak(state: &AppState, symbol: &str, signal: f64, buy_threshold: f64) -> u32 {
+    if signal > buy_threshold {
+        let mut streak = state.buy_streaks.entry(symbol.to_string()).or_insert(0);
+        *streak += 1;
+        *streak
+    } else {
+        state.buy_streaks.remove(symbol);
+        0
+    }
+}
+
+// Data the plan doesn't cover gets its own warning instead of looking like
+// "insufficient data". Only the first rejection goes to the activity log.
+fn report_entitlement(state: &AppState, e: &anyhow::Error) -> bool {
+    let Some(issue) = e.downcast_ref::<EntitlementError>() else {
+        return false;
+    };
+    warn!("🔒 {} - {} (set MARKET_DATA_FEED=iex or upgrade the Alpaca data plan)", issue.symbol, issue);
+    if issue.occurrences == 1 {
+        state.logger.warning("Data Plan", &format!(
+            "🔒 {} {} not available on the '{}' feed: {} - switch MARKET_DATA_FEED to iex or upgrade the data plan",
+            issue.symbol, issue.endpoint, issue.feed, issue.message
+        ));
+    }
+    true
+}
+
+fn record_order_failure(state: &AppState, symbol: &str, e: &anyhow::Error) {
+    if let Some(secs) = state.order_backoff.record_failure(symbol, &e.to_string()) {
+        warn!("⏸️  {} - Repeated order failures, backing off for {}s", symbol, secs);
+        state.logger.warning("Backoff", &format!("{} paused for {}s after repeated order failures", symbol, secs));
+    }
+}
+
+// Polls a freshly placed order until it fills, then compares the fill with
+// the quote the trade was sized on. Runs in the background so the cycle
+// isn't held up waiting for the exchange. The trade's quantity becomes what
+// actually filled, so cost basis (tracked positions, tax lots) weights each
+// buy by its filled size rather than the size requested.
+async fn confirm_fill(state: AppState, trade_id: String, order_id: String, side: &'static str) {
+    let Some((fill_price, filled_qty)) = wait_for_fill(&state, &order_id).await else {
+        return;
+    };
+    
+    let mut history = state.trade_history.write().await;
+    let Some(trade) = history.iter_mut().find(|t| t.id == trade_id) else {
+        return;
+    };
+    if filled_qty > 0.0 && filled_qty < trade.quantity {
+        info!("🧩 {} - Partial fill: {} of {} at ${:.4}", trade.symbol, filled_qty, trade.quantity, fill_price);
+        state.logger.info("Fills", &format!(
+            "🧩 {} {} partially filled: {} of {} at ${:.4}", side.to_uppercase(), trade.symbol, filled_qty, trade.quantity, fill_price
+        ));
+    }
+    if filled_qty > 0.0 {
+        trade.quantity = filled_qty;
+    }
+    trade.fill_price = Some(fill_price);
+    trade.slippage_pct = Some(SlippageGuard::slippage_pct(side, trade.price, fill_price));
+    
+    if let Some(pct) = state.slippage.check_fill(&trade.symbol, side, trade.price, fill_price) {
+        trade.slippage_flagged = true;
+        warn!("🚨 {} filled at ${:.4} vs quote ${:.4} ({:.2}% slippage) - switching to limit orders",
+              trade.symbol, fill_price, trade.price, pct);
+        state.logger.warning("Slippage", &format!(
+            "{} filled {:.2}% worse than quote (${:.4} vs ${:.4}), limit orders only from now on",
+            trade.symbol, pct, fill_price, trade.price
+        ));
+    }
+}
+
+// Polls an order until it's filled and returns (avg fill price, filled qty).
+// An order canceled or expired after a partial fill returns what did fill.
+// None if nothing filled or it doesn't finish within FILL_CONFIRM_TIMEOUT_SECS.
+async fn wait_for_fill(state: &AppState, order_id: &str) -> Option<(f64, f64)> {
+    let timeout_secs = state.config.read().await.slippage.fill_confirm_timeout_secs;
+    let started = Instant::now();
+    
+    let order = loop {
+        match state.alpaca.get_order(order_id).await {
+            Ok(order) if order.status == "filled" => break order,
+            Ok(order) if matches!(order.status.as_str(), "canceled" | "expired" | "rejected") => {
+                let filled = order.filled_qty.as_deref().and_then(|q| q.parse::<f64>().ok()).unwrap_or(0.0);
+                if filled > 0.0 {
+                    info!("🧩 Order {} for {} ended as {} after filling {}", order_id, order.symbol, order.status, filled);
+                    break order;
+                }
+                warn!("⚠️  Order {} for {} ended as {}", order_id, order.symbol, order.status);
+                return None;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️  Could not check order {}: {}", order_id, e),
+        }
+        if started.elapsed().as_secs() >= timeout_secs {
+            info!("⏱️  Order {} not filled within {}s, giving up on fill confirmation", order_id, timeout_secs);
+            return None;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    };
+    
+    let fill_price = order.filled_avg_price.as_deref().and_then(|p| p.parse::<f64>().ok())?;
+    let filled_qty = order.filled_qty.as_deref().and_then(|q| q.parse::<f64>().ok()).unwrap_or(0.0);
+    Some((fill_price, filled_qty))
+}
+
+// Replaces the estimated P&L on a closing trade (unrealized_pl at close time)
+// with the realized figure from the actual fill, and flags big differences.
+pub async fn reconcile_close_pnl(state: AppState, trade_id: String, order_id: String, entry_price: f64) {
+    let alert_usd = {
+        let config = state.config.read().await;
+        if !config.reconcile.enabled {
+            return;
+        }
+        config.reconcile.discrepancy_alert_usd
+    };
+    
+    let Some((fill_price, filled_qty)) = wait_for_fill(&state, &order_id).await else {
+        return;
+    };
+    
+    let mut history = state.trade_history.write().await;
+    let Some(trade) = history.iter_mut().find(|t| t.id == trade_id) else {
+        return;
+    };
+    let qty = if filled_qty > 0.0 { filled_qty } else { trade.quantity };
+    let realized = (fill_price - entry_price) * qty;
+    let discrepancy = realized - trade.pnl;
+    
+    if discrepancy.abs() > alert_usd {
+        warn!("🧾 {} realized P&L ${:.2} differs from estimate ${:.2} by ${:.2}",
+              trade.symbol, realized, trade.pnl, discrepancy);
+        state.logger.warning("Reconcile", &format!(
+            "{} P&L corrected ${:.2} -> ${:.2} (fill ${:.4})",
+            trade.symbol, trade.pnl, realized, fill_price
+        ));
+    }
+    trade.pnl = realized;
+    trade.fill_price = Some(fill_price);
+    trade.slippage_pct = Some(SlippageGuard::slippage_pct("sell", trade.price, fill_price));
+    trade.quantity = qty;
+}
+
+async fn update_atr(state: &AppState, symbol: &str, bars: &[alpaca::Bar]) {
+    let period = state.config.read().await.profit_target.atr_period;
+    if let Some(atr) = TechnicalAnalysis::calculate_atr(bars, period) {
+        state.atr.insert(symbols::normalize(symbol), atr);
+    }
+}
+
+// Profit target as a percent above entry, plus a description for logging.
+// Fixed (15% stocks / 20% crypto by default) unless the current trading mode
+// is in ATR_PROFIT_TARGET_MODES and an ATR is known for the symbol.
+pub async fn profit_target_pct(state: &AppState, symbol: &str, entry: f64, is_crypto: bool) -> (f64, String) {
+    let targets = state.config.read().await.profit_target.clone();
+    let fixed = if is_crypto { targets.crypto_pct } else { targets.stock_pct };
+    
+    let mode = format!("{:?}", *state.trading_mode.read().await);
+    let atr_mode = targets.atr_modes.iter().any(|m| m.eq_ignore_ascii_case(&mode));
+    
+    if atr_mode && entry > 0.0 {
+        if let Some(atr) = state.atr.get(&symbols::normalize(symbol)).map(|a| *a) {
+            let pct = targets.atr_multiplier * atr / entry * 100.0;
+            return (pct, format!("{}×ATR ${:.2}", targets.atr_multiplier, atr));
+        }
+    }
+    (fixed, "fixed".to_string())
+}
+
+// Shared profit-taking check against profit_target_pct.
+// Used by both the analysis cycles and the live-price exit watcher.
+// Returns true if the position was closed.
+pub async fn take_profit_if_due(
+    state: &AppState,
+    symbol: &str,
+    pos: &alpaca::Position,
+    current_price: f64,
+    is_crypto: bool,
+) -> bool {
+    if state.notes.has_tag(symbol, NO_AUTO_SELL) {
+        debug!("📝 {} - Tagged {}, skipping automatic profit taking", symbol, NO_AUTO_SELL);
+        return false;
+    }
+    
+    let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+    let profit_percent = if entry > 0.0 {
+        ((current_price - entry) / entry) * 100.0
+    } else {
+        0.0
+    };
+    let (target, basis) = profit_target_pct(state, symbol, entry, is_crypto).await;
+    debug!("🎯 {} profit target {:.2}% ({}), currently {:.2}%", symbol, target, basis, profit_percent);
+    
+    if profit_percent < target {
+        return is_crypto && take_crypto_profit_tier(state, symbol, pos, current_price, profit_percent).await;
+    }
+    if hold_defers_exit(state, symbol, pos, current_price, "profit take").await
+        || pdt_blocks_exit(state, symbol, "profit take").await {
+        return false;
+    }
+    
+    let pnl: f64 = pos.unrealized_pl.parse().unwrap_or(0.0);
+    
+    if is_crypto {
+        info!("💰 {} CRYPTO PROFIT TAKING! {}% gain (target {:.2}%, {})", symbol, profit_percent.round(), target, basis);
+        match state.crypto.close_crypto_position(&pos.symbol).await {
+            Ok(close_order) => {
+                let qty = pos.qty.parse().unwrap_or(0.0);
+                record_exit(state, symbol, pos, qty, current_price, pnl, close_order.id, TradeReason::ProfitTake).await;
+                state.order_backoff.record_success(symbol);
+                state.profit_tiers_taken.remove(&symbols::normalize(symbol));
+                info!("✅ CRYPTO PROFIT BOOKED! {} - ${:.2}", symbol, pnl);
+                state.logger.trade(
+                    LogLevel::Success,
+                    &format!("💰 CRYPTO PROFIT ${:.2} (+{}%)", pnl, profit_percent.round()),
+                    symbol
+                );
+                true
+            },
+            Err(e) => {
+                error!("❌ Crypto profit taking failed: {}", e);
+                record_order_failure(state, symbol, &e);
+                false
+            }
+        }
+    } else {
+        info!("💰 {} PROFIT TAKING! {}% gain (target {:.2}%, {}) - SELLING", symbol, profit_percent.round(), target, basis);
+        match state.alpaca.close_position(symbol).await {
+            Ok(close_order) => {
+                let qty = pos.qty.parse().unwrap_or(0.0);
+                record_exit(state, symbol, pos, qty, current_price, pnl, close_order.id, TradeReason::ProfitTake).await;
+                state.order_backoff.record_success(symbol);
+                info!("✅ PROFIT BOOKED! {} - ${:.2} (+{}%)", symbol, pnl, profit_percent.round());
+                state.logger.trade(
+                    LogLevel::Success,
+                    &format!("💰 PROFIT TAKING ${:.2} (+{}%)", pnl, profit_percent.round()),
+                    symbol
+                );
+                true
+            },
+            Err(e) => {
+                error!("❌ Profit taking failed: {}", e);
+                record_order_failure(state, symbol, &e);
+                false
+            }
+        }
+    }
+}
+
+// STOP_LOSS_PCT / TRAILING_STOP_PCT: closes the position once the price falls
+// through its stop. The trailing stop arms after TRAILING_STOP_ACTIVATION_PCT
+// of gain and then follows the high-water mark; until then the fixed stop
+// applies. Stops are protective, so the minimum hold doesn't delay them (the
+// PDT guard still does). Returns true if it sold.
+pub async fn stop_out_if_hit(
+    state: &AppState,
+    symbol: &str,
+    pos: &alpaca::Position,
+    current_price: f64,
+    is_crypto: bool,
+) -> bool {
+    let config = state.config.read().await.stops.clone();
+    if config.stop_loss_pct <= 0.0 && config.trailing_pct <= 0.0 {
+        return false;
+    }
+    if state.notes.has_tag(symbol, NO_AUTO_SELL) {
+        return false;
+    }
+    let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+    if entry <= 0.0 || current_price <= 0.0 {
+        return false;
+    }
+    let rules = StopRules {
+        stop_loss_pct: config.stop_loss_pct,
+        trail_pct: config.trailing_pct,
+        activation_pct: config.trailing_activation_pct,
+    };
+    
+    // A different entry price means a new position: start its high over
+    let key = symbols::normalize(symbol);
+    let (previous_high, high) = {
+        let mut mark = state.high_water.entry(key.clone()).or_insert((entry, entry));
+        if mark.0 != entry {
+            *mark = (entry, entry);
+        }
+        let previous = mark.1;
+        mark.1 = mark.1.max(current_price);
+        (previous, mark.1)
+    };
+    if !rules.trailing_armed(entry, previous_high) && rules.trailing_armed(entry, high) {
+        info!("🪜 {} - Trailing stop armed at ${:.4} ({:.1}% above entry), trailing {:.1}%",
+              symbol, high, (high - entry) / entry * 100.0, config.trailing_pct);
+        state.logger.info("Exits", &format!("🪜 {} trailing stop armed, {:.1}% below the high", symbol, config.trailing_pct));
+    }
+    
+    let Some(hit) = rules.check(entry, high, current_price) else {
+        return false;
+    };
+    if pdt_blocks_exit(state, symbol, "stop").await {
+        return false;
+    }
+    let description = match hit {
+        StopHit::StopLoss { stop } => format!("stop-loss ${:.4} ({:.1}% below entry)", stop, config.stop_loss_pct),
+        StopHit::Trailing { stop, high } => format!("trailing stop ${:.4} ({:.1}% below the ${:.4} high)", stop, config.trailing_pct, high),
+    };
+    warn!("🛑 {} - ${:.4} hit the {} - SELLING", symbol, current_price, description);
+    
+    let closed = if is_crypto {
+        state.crypto.close_crypto_position(&pos.symbol).await
+    } else {
+        state.alpaca.close_position(symbol).await
+    };
+    match closed {
+        Ok(close_order) => {
+            let qty = pos.qty.parse().unwrap_or(0.0);
+            let pnl: f64 = pos.unrealized_pl.parse().unwrap_or(0.0);
+            record_exit(state, symbol, pos, qty, current_price, pnl, close_order.id, TradeReason::Stop).await;
+            state.order_backoff.record_success(symbol);
+            state.high_water.remove(&key);
+            if is_crypto {
+                state.profit_tiers_taken.remove(&key);
+            }
+            state.logger.trade(
+                LogLevel::Warning,
+                &format!("🛑 STOPPED OUT at ${:.2} ({}), P&L ${:.2}", current_price, description, pnl),
+                symbol
+            );
+            true
+        }
+        Err(e) => {
+            error!("❌ Stop exit failed for {}: {}", symbol, e);
+            record_order_failure(state, symbol, &e);
+            false
+        }
+    }
+}
+
+// Tiered crypto profit taking: at each CRYPTO_PROFIT_TIERS level (percent gain)
+// sell that fraction of the current holding, once per position. The full
+// profit target still closes whatever is left. Returns true if a tier sold.
+async fn take_crypto_profit_tier(
+    state: &AppState,
+    symbol: &str,
+    pos: &alpaca::Position,
+    current_price: f64,
+    profit_percent: f64,
+) -> bool {
+    let (tiers, min_notional, step) = {
+        let config = state.config.read().await;
+        (
+            config.crypto_exit.tiers.clone(),
+            config.crypto_exit.min_notional,
+            config.instruments.increment_for(symbol, true),
+        )
+    };
+    let key = symbols::normalize(symbol);
+    let taken = state.profit_tiers_taken.get(&key).map(|t| *t).unwrap_or(0);
+    let Some(&(level, fraction)) = tiers.get(taken) else {
+        return false;
+    };
+    if profit_percent < level || hold_defers_exit(state, symbol, pos, current_price, "tier exit").await {
+        return false;
+    }
+    
+    let held: f64 = pos.qty.parse().unwrap_or(0.0);
+    let qty = sizing::partial_qty(held, fraction, step);
+    if qty * current_price < min_notional {
+        info!("⚠️  {} - Tier {} exit (${:.2}) is below the ${:.2} minimum, skipping tier", 
+              symbol, taken + 1, qty * current_price, min_notional);
+        state.profit_tiers_taken.insert(key, taken + 1);
+        return false;
+    }
+    
+    info!("💰 {} CRYPTO TIER {} EXIT at +{:.1}% - selling {} of {}", symbol, taken + 1, profit_percent, qty, held);
+    let order = CryptoOrderRequest {
+        symbol: symbol.to_string(), qty: sizing::format_qty(qty, step),
+        side: "sell".to_string(), order_type: "market".to_string(),
+        time_in_force: "gtc".to_string(),
+        limit_price: None,
+        reference_price: Some(current_price),
+    };
+    
+    match state.crypto.place_crypto_order(order).await {
+        Ok(order_response) => {
+            state.order_backoff.record_success(symbol);
+            state.profit_tiers_taken.insert(key, taken + 1);
+            let entry: f64 = pos.avg_entry_price.parse().unwrap_or(0.0);
+            let pnl = (current_price - entry) * qty;
+            let order_id = order_response["id"].as_str().unwrap_or_default().to_string();
+            record_exit(state, symbol, pos, qty, current_price, pnl, order_id, TradeReason::ProfitTake).await;
+            state.logger.trade(
+                LogLevel::Success,
+                &format!("💰 TIER {} PARTIAL EXIT {} @ ${:.2} | P&L: ${:.2} (+{:.1}%)", taken + 1, qty, current_price, pnl, profit_percent),
+                symbol
+            );
+            true
+        },
+        Err(e) => {
+            error!("❌ Crypto partial exit failed for {}: {}", symbol, e);
+            record_order_failure(state, symbol, &e);
+            false
+        }
+    }
+}
+
+// Profit-taking closes go into trade history like any other sell, so their
+// P&L gets reconciled too
+#[allow(clippy::too_many_arguments)]
+async fn record_exit(
+    state: &AppState,
+    symbol: &str,
+    pos: &alpaca::Position,
+    quantity: f64,
+    current_price: f64,
+    pnl: f64,
+    order_id: String,
+    reason: TradeReason,
+) {
+    let trade = TradeRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Utc::now().to_rfc3339(),
+        symbol: symbol.to_string(),
+        action: "SELL".to_string(),
+        quantity,
+        price: current_price,
+        pnl,
+        fill_price: None,
+        slippage_flagged: false,
+        slippage_pct: None,
+        reason: Some(reason),
+        asset_class: symbols::asset_class(symbol).to_string(),
+    };
+    let entry = pos.avg_entry_price.parse().unwrap_or(0.0);
+    tokio::spawn(reconcile_close_pnl(state.clone(), trade.id.clone(), order_id, entry));
+    state.trade_history.write().await.push(trade);
+}
+
+// Fast exit watcher: re-checks held positions against streamed prices every
+// few seconds so exits don't wait for the next full analysis cycle
+async fn exit_watch_loop(state: AppState) {
+    let (watch_secs, max_age) = {
+        let config = state.config.read().await;
+        (config.market_stream.watch_interval_secs, config.market_stream.max_price_age_secs)
+    };
+    let mut tick = interval(Duration::from_secs(watch_secs.max(1)));
+    
+    loop {
+        tick.tick().await;
+        
+        let stocks_enabled = *state.trading_enabled.read().await;
+        let crypto_enabled = *state.crypto_trading_enabled.read().await;
+        if (!stocks_enabled && !crypto_enabled) || *state.maintenance_mode.read().await {
+            continue;
+        }
+        
+        let positions = match state.alpaca.get_positions().await {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+        
+        for pos in &positions {
+            let is_crypto = symbols::is_crypto_symbol(&pos.symbol);
+            if (is_crypto && !crypto_enabled) || (!is_crypto && !stocks_enabled) {
+                continue;
+            }
+            
+            // Only act on fresh streamed prices - stale ones wait for the cycle
+            if let Some(live_price) = state.live_prices.get_fresh(&pos.symbol, max_age) {
+                if !stop_out_if_hit(&state, &pos.symbol, pos, live_price, is_crypto).await {
+                    take_profit_if_due(&state, &pos.symbol, pos, live_price, is_crypto).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::extract::{Path, Query};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    // No test here should reach Alpaca; nothing listens on the discard port
+    const NO_ALPACA: &str = "http://127.0.0.1:9";
+
+    const PRICE: f64 = 100.0;
+
+    // Stand-in for Alpaca: every symbol trades at PRICE, `positions` are held
+    // and every order fills at PRICE
+    async fn mock_alpaca(positions: serde_json::Value) -> String {
+        let order = |side: &str| json!({
+            "id": "test-order", "symbol": "", "qty": "1", "side": side, "order_type": "market",
+            "status": "filled", "filled_avg_price": PRICE.to_string(), "filled_qty": "1",
+        });
+        let (buy, sell) = (order("buy"), order("sell"));
+        let app = Router::new()
+            .route("/v2/account", get(|| async {
+                Json(json!({ "buying_power": "50000", "cash": "50000", "portfolio_value": "100000" }))
+            }))
+            .route("/v2/positions", get(move || async move { Json(positions) }))
+            .route("/v2/positions/:symbol", axum::routing::delete(move || async move { Json(sell) }))
+            .route("/v2/orders", axum::routing::post({
+                let buy = buy.clone();
+                move || async move { Json(buy) }
+            }))
+            .route("/v2/orders/:id", get(move || async move { Json(buy) }))
+            .route("/v2/stocks/:symbol/bars", get(|| async {
+                let now = Utc::now();
+                let bars: Vec<serde_json::Value> = (0..50)
+                    .map(|i| json!({
+                        "t": (now - chrono::Duration::minutes(5 * (49 - i))).to_rfc3339(),
+                        "o": PRICE, "h": PRICE + 0.5, "l": PRICE - 0.5, "c": PRICE, "v": 10_000,
+                    }))
+                    .collect();
+                Json(json!({ "bars": bars }))
+            }))
+            .route("/v1beta3/crypto/us/latest/quotes", get(|Query(query): Query<HashMap<String, String>>| async move {
+                let quotes: serde_json::Map<String, serde_json::Value> = query["symbols"].split(',')
+                    .map(|s| (s.to_string(), json!({ "ap": PRICE, "bp": PRICE - 0.01 })))
+                    .collect();
+                Json(json!({ "quotes": quotes }))
+            }));
+        test_support::serve(app).await
+    }
+
+    fn crypto_bars() -> Vec<CryptoBar> {
+        let now = Utc::now();
+        (0..50)
+            .map(|i| CryptoBar {
+                t: (now - chrono::Duration::minutes(5 * (49 - i))).to_rfc3339(),
+                o: PRICE, h: PRICE + 0.5, l: PRICE - 0.5, c: PRICE, v: 10.0, vw: PRICE,
+            })
+            .collect()
+    }
+
+    fn crypto_position(symbol: &str, qty: f64) -> serde_json::Value {
+        json!({
+            "symbol": symbol, "qty": qty.to_string(), "avg_entry_price": PRICE.to_string(),
+            "current_price": PRICE.to_string(), "unrealized_pl": "0", "asset_class": "crypto",
+        })
+    }
+
+    // Scores every symbol the same
+    struct Fixed(f64);
+
+    impl Strategy for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn signal(&self, _bars: &[alpaca::Bar], _sentiment: f64, _ctx: &StrategyContext) -> f64 {
+            self.0
+        }
+    }
+
+    // An engine on `host` that acts on the first signal and scores everything `signal`
+    async fn engine_with_signal(host: &str, signal: f64) -> Engine {
+        let engine = test_support::engine(host).with_strategy(Arc::new(Fixed(signal)));
+        engine.state().config.write().await.entry_limits.grace_period_secs = 0;
+        engine
+    }
+
+    fn trade(action: &str, symbol: &str, quantity: f64, price: f64, pnl: f64) -> TradeRecord {
+        TradeRecord { pnl, ..test_support::trade(symbol, action, quantity, price, Utc::now()) }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn manual_cycle_returns_its_summary_and_never_overlaps() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&host, 0.0).await;
+        let state = engine.state();
+        let universe = active_stocks(state).await.len();
+
+        let summary = try_run_cycle(state, false).await.unwrap();
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.analyzed as usize, universe);
+        assert_eq!(summary.neutral, summary.analyzed);
+
+        // A cycle already in flight turns the manual trigger away
+        let _running = state.stock_cycle_lock.lock().await;
+        assert!(try_run_cycle(state, false).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn reconcile_replaces_estimate_with_broker_fill() {
+        let app = Router::new().route("/v2/orders/:id", get(|Path(id): Path<String>| async move {
+            Json(json!({
+                "id": id, "symbol": "AAPL", "qty": "10", "side": "sell", "order_type": "market",
+                "status": "filled", "filled_avg_price": "105.00", "filled_qty": "10",
+            }))
+        }));
+        let engine = test_support::engine(&test_support::serve(app).await);
+        let state = engine.state();
+        // Closed at a $104 quote for an estimated $40, but the broker filled at $105
+        let close = trade("SELL", "AAPL", 10.0, 104.0, 40.0);
+        let id = close.id.clone();
+        state.trade_history.write().await.push(close);
+
+        reconcile_close_pnl(state.clone(), id, "close-order".to_string(), 100.0).await;
+
+        let history = state.trade_history.read().await;
+        assert_eq!(history[0].pnl, 50.0);
+        assert_eq!(history[0].fill_price, Some(105.0));
+    }
+
+    #[tokio::test]
+    async fn atr_profit_target_scales_with_volatility() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.profit_target.atr_modes = vec!["volatile".to_string()];
+        *state.trading_mode.write().await = TradingMode::Volatile;
+        state.atr.insert("GME".to_string(), 2.0);
+        state.atr.insert("KO".to_string(), 0.25);
+
+        let (high, basis) = profit_target_pct(state, "GME", 20.0, false).await;
+        assert!((high - 30.0).abs() < 1e-9, "{} ({})", high, basis);
+        let (low, _) = profit_target_pct(state, "KO", 20.0, false).await;
+        assert!((low - 3.75).abs() < 1e-9);
+
+        // No ATR yet, or a mode without ATR targets: the fixed target
+        assert_eq!(profit_target_pct(state, "AAPL", 20.0, false).await.1, "fixed");
+        *state.trading_mode.write().await = TradingMode::Conservative;
+        assert_eq!(profit_target_pct(state, "GME", 20.0, false).await, (15.0, "fixed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn one_session_summary_at_the_close() {
+        // Wednesday 2024-06-12, checked every 30s from 15:55 to 16:10 New York (EDT)
+        let start = Utc.with_ymd_and_hms(2024, 6, 12, 19, 55, 0).unwrap();
+        let mut was_open = is_market_open(start);
+        let closes: Vec<DateTime<Utc>> = (1..=30)
+            .map(|i| start + chrono::Duration::seconds(30 * i))
+            .filter(|now| session_just_closed(&mut was_open, *now))
+            .collect();
+        assert_eq!(closes, vec![Utc.with_ymd_and_hms(2024, 6, 12, 20, 0, 0).unwrap()]);
+
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.buy_streaks.insert("AAPL".to_string(), 1);
+        let mut sell = trade("SELL", "AAPL", 10.0, 190.0, 25.0);
+        sell.timestamp = (closes[0] - chrono::Duration::hours(1)).to_rfc3339();
+        state.trade_history.write().await.push(sell);
+
+        let summary = emit_session_summary(state, closes[0]).await;
+        assert_eq!((summary.trades, summary.sells, summary.realized_pnl), (1, 1, 25.0));
+        assert!(state.last_session.read().await.is_some());
+        assert!(state.buy_streaks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn crypto_buys_wait_for_trading_hours_but_sells_do_not() {
+        // A one-hour window starting six hours from now
+        let opens = Utc::now() + chrono::Duration::hours(6);
+        let window = format!("{}-{}", opens.format("%H:%M"), (opens + chrono::Duration::hours(1)).format("%H:%M"));
+
+        let flat = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&flat, 0.9).await;
+        engine.state().config.write().await.crypto_hours.window = window.clone();
+        engine.state().config.write().await.crypto_hours.timezone = "UTC".to_string();
+        let result = process_crypto(engine.state(), "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert_eq!(result, "outside_hours");
+        assert!(engine.state().trade_history.read().await.is_empty());
+
+        let holding = mock_alpaca(json!([crypto_position("BTCUSD", 0.5)])).await;
+        let engine = engine_with_signal(&holding, -0.9).await;
+        engine.state().config.write().await.crypto_hours.window = window;
+        engine.state().config.write().await.crypto_hours.timezone = "UTC".to_string();
+        let result = process_crypto(engine.state(), "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert_eq!(result, "sell");
+        assert_eq!(engine.state().trade_history.read().await[0].action, "SELL");
+    }
+
+    #[tokio::test]
+    async fn crypto_leverage_counts_stock_holdings() {
+        // $99.5k of stock on $100k equity; a $1k crypto entry would take it past 1x
+        let aapl = json!({
+            "symbol": "AAPL", "qty": "995", "avg_entry_price": PRICE.to_string(),
+            "current_price": PRICE.to_string(), "unrealized_pl": "0", "asset_class": "us_equity",
+        });
+        let host = mock_alpaca(json!([aapl])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+        let state = engine.state();
+        state.config.write().await.entry_limits.max_leverage = 1.0;
+
+        let result = process_crypto(state, "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert_eq!(result, "max_leverage");
+        assert!(state.trade_history.read().await.is_empty());
+
+        state.config.write().await.entry_limits.max_leverage = 1.5;
+        let result = process_crypto(state, "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert_eq!(result, "buy");
+        assert_eq!(state.trade_history.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn risk_sizing_uses_the_enforced_stop_loss() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.risk_sizing.risk_per_trade_usd = 100.0;
+        state.atr.insert("AAPL".to_string(), 1.0);
+
+        // No fixed stop: 2 x ATR below entry
+        let notional = dollar_risk_size(state, "AAPL", PRICE, f64::MAX).await.unwrap();
+        assert!((notional / PRICE * 2.0 - 100.0).abs() < 1e-9);
+
+        // With STOP_LOSS_PCT the loss at the stop stop_out_if_hit actually uses is the configured risk
+        state.config.write().await.stops.stop_loss_pct = 5.0;
+        let rules = StopRules { stop_loss_pct: 5.0, trail_pct: 0.0, activation_pct: 0.0 };
+        let Some(StopHit::StopLoss { stop }) = rules.check(PRICE, PRICE, 90.0) else {
+            panic!("expected the stop-loss");
+        };
+        let qty = dollar_risk_size(state, "AAPL", PRICE, f64::MAX).await.unwrap() / PRICE;
+        assert!((qty * (PRICE - stop) - 100.0).abs() < 1e-9);
+        // The stop is known without an ATR
+        assert!(dollar_risk_size(state, "MSFT", PRICE, f64::MAX).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn neutral_analysis_is_kept_out_of_the_activity_log() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.logging.suppress_neutral_analysis = true;
+        let analysis_entries = || state.logger.get_logs().iter().filter(|l| l.category == "Analysis").count();
+
+        log_analysis(state, "AAPL", 190.0, 0.05, 0.0, None, false).await;
+        assert_eq!(analysis_entries(), 0);
+
+        log_analysis(state, "AAPL", 190.0, 0.6, 0.0, None, false).await;
+        log_analysis(state, "AAPL", 190.0, -0.6, 0.0, None, false).await;
+        assert_eq!(analysis_entries(), 2);
+
+        state.config.write().await.logging.suppress_neutral_analysis = false;
+        log_analysis(state, "AAPL", 190.0, 0.05, 0.0, None, false).await;
+        assert_eq!(analysis_entries(), 3);
+    }
+
+    #[tokio::test]
+    async fn profit_tier_sells_half_the_crypto_holding() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.crypto_exit.tiers = vec![(10.0, 0.5)];
+            config.crypto_exit.min_notional = 0.5;
+            config.instruments.crypto_steps.insert("BTC/USD".to_string(), 0.0001);
+        }
+        let pos = alpaca::Position {
+            symbol: "BTCUSD".to_string(),
+            qty: "0.0123".to_string(),
+            avg_entry_price: "90".to_string(),
+            current_price: PRICE.to_string(),
+            unrealized_pl: "0.123".to_string(),
+        };
+
+        assert!(take_crypto_profit_tier(state, "BTC/USD", &pos, PRICE, 11.1).await);
+        let history = state.trade_history.read().await;
+        assert_eq!(history[0].action, "SELL");
+        assert_eq!(sizing::format_qty(history[0].quantity, 0.0001), "0.0061");
+        // Each tier fires once per position
+        drop(history);
+        assert!(!take_crypto_profit_tier(state, "BTC/USD", &pos, PRICE, 11.1).await);
+    }
+
+    #[tokio::test]
+    async fn equity_floor_blocks_entries_until_equity_recovers() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.equity_floor.min_equity = 25_000.0;
+            config.equity_floor.flatten = false;
+        }
+        assert!(!equity_floor_blocks_entry(state, "AAPL").await);
+
+        check_equity_floor(state, 24_000.0).await;
+        assert!(equity_floor_blocks_entry(state, "AAPL").await);
+
+        check_equity_floor(state, 26_000.0).await;
+        assert!(!equity_floor_blocks_entry(state, "AAPL").await);
+    }
+
+    #[tokio::test]
+    async fn no_auto_sell_tag_exempts_a_position_from_automatic_exits() {
+        let host = mock_alpaca(json!([crypto_position("BTCUSD", 0.5)])).await;
+        let engine = engine_with_signal(&host, -0.9).await;
+        let state = engine.state();
+        state.config.write().await.stops.stop_loss_pct = 5.0;
+        state.notes.set("BTC/USD", "holding through the halving".to_string(), vec![NO_AUTO_SELL.to_string()]);
+
+        process_crypto(state, "BTC/USD", Some(crypto_bars())).await.unwrap();
+        let pos = alpaca::Position::from(serde_json::from_value::<crate::crypto::CryptoPosition>(crypto_position("BTCUSD", 0.5)).unwrap());
+        assert!(!take_profit_if_due(state, "BTC/USD", &pos, PRICE * 1.5, true).await);
+        assert!(!stop_out_if_hit(state, "BTC/USD", &pos, PRICE * 0.5, true).await);
+        assert!(state.trade_history.read().await.is_empty());
+
+        state.notes.set("BTC/USD", String::new(), vec![]);
+        process_crypto(state, "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert_eq!(state.trade_history.read().await[0].action, "SELL");
+    }
+
+    #[tokio::test]
+    async fn halted_symbols_are_skipped_for_new_entries() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+        let state = engine.state();
+        // Keep the live staleness check from clearing the flag set below
+        state.config.write().await.halts.enabled = false;
+
+        assert!(state.halts.flag("AAPL", 30));
+        assert_eq!(process_stock(state, "AAPL", Some(PRICE)).await.unwrap(), "halted");
+        assert!(state.trade_history.read().await.is_empty());
+
+        assert!(state.halts.clear("AAPL"));
+        assert_ne!(process_stock(state, "AAPL", Some(PRICE)).await.unwrap(), "halted");
+        assert_eq!(state.trade_history.read().await[0].action, "BUY");
+    }
+
+    #[tokio::test]
+    async fn entry_limit_caps_buys_per_cycle() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+        let state = engine.state();
+        state.config.write().await.entry_limits.max_per_cycle = 2;
+
+        for symbol in ["AAPL", "MSFT", "NVDA", "AMZN", "META"] {
+            process_stock(state, symbol, Some(PRICE)).await.unwrap();
+        }
+        let bought: Vec<String> = state.trade_history.read().await.iter().map(|t| t.symbol.clone()).collect();
+        assert_eq!(bought, vec!["AAPL", "MSFT"]);
+        assert_eq!(*state.cycle_entries.get("stock").unwrap(), 2);
+        // Crypto counts separately
+        assert!(!entry_limit_reached(state, "BTC/USD", 0.9).await);
+    }
+
+    #[tokio::test]
+    async fn entries_below_the_minimum_price_are_skipped() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.entry_limits.stock_min_price = 5.0;
+            config.entry_limits.crypto_min_price = 0.01;
+        }
+
+        assert_eq!(process_stock(state, "SIRI", Some(4.99)).await.unwrap(), "below_min_price");
+        assert!(state.trade_history.read().await.is_empty());
+        process_stock(state, "AAPL", Some(PRICE)).await.unwrap();
+        assert_eq!(state.trade_history.read().await[0].symbol, "AAPL");
+
+        assert!(below_min_price(state, "SHIB/USD", 0.00002, true).await);
+        assert!(!below_min_price(state, "DOGE/USD", 0.15, true).await);
+        state.config.write().await.entry_limits.stock_min_price = 0.0;
+        assert!(!below_min_price(state, "SIRI", 4.99, false).await);
+    }
+
+    #[tokio::test]
+    async fn entries_wait_out_the_grace_period() {
+        let host = mock_alpaca(json!([])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+        let state = engine.state();
+        state.config.write().await.entry_limits.grace_period_secs = 60;
+        restart_grace_period(state).await;
+
+        assert_eq!(process_stock(state, "AAPL", Some(PRICE)).await.unwrap(), "grace_period");
+        assert!(state.trade_history.read().await.is_empty());
+
+        *state.grace_started.write().await = Utc::now() - chrono::Duration::seconds(61);
+        process_stock(state, "AAPL", Some(PRICE)).await.unwrap();
+        assert_eq!(state.trade_history.read().await[0].action, "BUY");
+
+        // A mode switch starts it over
+        restart_grace_period(state).await;
+        assert!(in_grace_period(state, "MSFT", 0.9).await);
+    }
+
+    #[tokio::test]
+    async fn dollar_risk_sizing_loses_the_configured_amount_at_the_stop() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.risk_sizing.risk_per_trade_usd = 100.0;
+            config.risk_sizing.stop_atr_multiplier = 1.5;
+        }
+        assert_eq!(dollar_risk_size(state, "AAPL", PRICE, 10_000.0).await, None);
+
+        // ATR of 2 puts the stop at 97
+        state.atr.insert("AAPL".to_string(), 2.0);
+        let notional = dollar_risk_size(state, "AAPL", PRICE, 10_000.0).await.unwrap();
+        let qty = notional / PRICE;
+        assert!((qty * (PRICE - 97.0) - 100.0).abs() < 1e-9);
+        assert_eq!(sizing::risk_qty(100.0, PRICE, 97.0), qty);
+
+        // Capped by buying power / the notional limit
+        assert_eq!(dollar_risk_size(state, "AAPL", PRICE, 1_000.0).await, Some(1_000.0));
+    }
+
+    #[tokio::test]
+    async fn profit_take_waits_out_the_minimum_hold() {
+        let host = mock_alpaca(json!([crypto_position("BTCUSD", 0.5)])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state();
+        state.config.write().await.holding.min_hold_minutes = 60;
+        let bought = |minutes_ago: i64| TradeRecord {
+            timestamp: (Utc::now() - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+            ..trade("BUY", "BTC/USD", 0.5, PRICE, 0.0)
+        };
+        let pos = alpaca::Position::from(serde_json::from_value::<crate::crypto::CryptoPosition>(crypto_position("BTCUSD", 0.5)).unwrap());
+
+        state.trade_history.write().await.push(bought(30));
+        assert!(!take_profit_if_due(state, "BTC/USD", &pos, PRICE * 1.5, true).await);
+        assert_eq!(state.trade_history.read().await.len(), 1);
+
+        *state.trade_history.write().await = vec![bought(61)];
+        assert!(take_profit_if_due(state, "BTC/USD", &pos, PRICE * 1.5, true).await);
+        assert_eq!(state.trade_history.read().await[1].reason, Some(TradeReason::ProfitTake));
+    }
+
+    #[tokio::test]
+    async fn strategy_groups_decide_independently() {
+        let host = mock_alpaca(json!([{
+            "symbol": "MSFT", "qty": "10", "avg_entry_price": PRICE.to_string(),
+            "current_price": PRICE.to_string(), "unrealized_pl": "0", "asset_class": "us_equity",
+        }])).await;
+        let engine = engine_with_signal(&host, 0.0).await
+            .with_group_strategy("trend", Arc::new(Fixed(0.6)))
+            .with_group_strategy("fade", Arc::new(Fixed(-0.6)));
+        let state = engine.state();
+        state.config.write().await.strategy_groups = vec![
+            crate::config::StrategyGroupConfig {
+                name: "trend".to_string(), strategy: "technical".to_string(), symbols: vec!["AAPL".to_string(), "NVDA".to_string()],
+                buy_threshold: None, sell_threshold: None, position_size_pct: Some(1.0),
+            },
+            crate::config::StrategyGroupConfig {
+                name: "fade".to_string(), strategy: "technical".to_string(), symbols: vec!["MSFT".to_string()],
+                buy_threshold: Some(0.9), sell_threshold: Some(-0.5), position_size_pct: None,
+            },
+        ];
+
+        let indicators = state.config.read().await.indicators.clone();
+        let ctx = StrategyContext { symbol: "AAPL", is_crypto: false, current_price: PRICE, indicators: &indicators, rng_seed: None };
+        assert_eq!(strategy_for(state, "AAPL").await.signal(&[], 0.0, &ctx), 0.6);
+        assert_eq!(strategy_for(state, "MSFT").await.signal(&[], 0.0, &ctx), -0.6);
+        assert_eq!(strategy_for(state, "AMZN").await.signal(&[], 0.0, &ctx), 0.0);
+        assert_eq!(state.config.read().await.thresholds_for("MSFT", false).buy, 0.9);
+
+        for symbol in ["AAPL", "MSFT", "AMZN"] {
+            process_stock(state, symbol, Some(PRICE)).await.unwrap();
+        }
+        let trades: Vec<(String, String)> = state.trade_history.read().await.iter()
+            .map(|t| (t.symbol.clone(), t.action.clone()))
+            .collect();
+        assert_eq!(trades, vec![("AAPL".to_string(), "BUY".to_string()), ("MSFT".to_string(), "SELL".to_string())]);
+        // Each group sizes its own entries
+        assert_eq!(preview_entry_size(state, "NVDA", PRICE, false, 50_000.0, &[], &[]).await.size_pct, 1.0);
+        assert_eq!(preview_entry_size(state, "AMZN", PRICE, false, 50_000.0, &[], &[]).await.size_pct, 5.0);
+    }
+
+    #[tokio::test]
+    async fn fourth_day_trade_is_held_under_the_pdt_threshold() {
+        // No account to ask, so equity comes from the last snapshot
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        let equity = |total_value: f64| PortfolioSnapshot {
+            timestamp: Utc::now().to_rfc3339(), total_value, cash: total_value, positions_value: 0.0,
+        };
+        state.portfolio_history.write().await.push(equity(20_000.0));
+        {
+            let mut config = state.config.write().await;
+            config.pdt.enabled = true;
+            config.pdt.equity_threshold = 25_000.0;
+            config.pdt.max_day_trades = 3;
+        }
+        let round_trips: Vec<TradeRecord> = ["AAPL", "MSFT", "NVDA"].iter()
+            .flat_map(|symbol| [trade("BUY", symbol, 1.0, PRICE, 0.0), trade("SELL", symbol, 1.0, PRICE, 0.0)])
+            .collect();
+        state.trade_history.write().await.extend(round_trips[..4].iter().cloned());
+        state.trade_history.write().await.push(trade("BUY", "TSLA", 1.0, PRICE, 0.0));
+
+        // Two day trades so far: a third is allowed
+        assert!(!pdt_blocks_exit(state, "TSLA", "sell signal").await);
+
+        state.trade_history.write().await.extend(round_trips[4..].iter().cloned());
+        assert!(pdt_blocks_exit(state, "TSLA", "sell signal").await);
+        // Only positions opened today are held back
+        assert!(!pdt_blocks_exit(state, "AMZN", "sell signal").await);
+
+        state.portfolio_history.write().await.push(equity(30_000.0));
+        assert!(!pdt_blocks_exit(state, "TSLA", "sell signal").await);
+    }
+
+    #[tokio::test]
+    async fn spread_wide_against_atr_blocks_entry() {
+        // Every quote is a cent wide
+        let host = mock_alpaca(json!([])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state();
+        state.config.write().await.entry_limits.max_spread_atr_ratio = 0.5;
+        state.atr.insert("BTCUSD".to_string(), 0.10);
+        state.atr.insert("ETHUSD".to_string(), 0.01);
+
+        assert!(!spread_blocks_entry(state, "BTC/USD", 0.9, true).await);
+        assert!(spread_blocks_entry(state, "ETH/USD", 0.9, true).await);
+        // No ATR yet lets it through
+        assert!(!spread_blocks_entry(state, "SOL/USD", 0.9, true).await);
+    }
+
+    #[tokio::test]
+    async fn held_crypto_position_is_recognized_across_symbol_forms() {
+        // Alpaca reports the position as BTCUSD, the analysis runs on BTC/USD
+        let host = mock_alpaca(json!([crypto_position("BTCUSD", 0.5)])).await;
+        let engine = engine_with_signal(&host, 0.9).await;
+
+        process_crypto(engine.state(), "BTC/USD", Some(crypto_bars())).await.unwrap();
+        assert!(engine.state().trade_history.read().await.is_empty(), "bought into an existing position");
+    }
+
+    #[tokio::test]
+    async fn crypto_processing_reads_positions_through_the_crypto_client() {
+        let host = mock_alpaca(json!([crypto_position("BTCUSD", 0.5)])).await;
+        let mut config = test_support::config(&host);
+        config.metrics.enabled = true;
+        let engine = Engine::new(config).with_strategy(Arc::new(Fixed(0.0)));
+
+        process_crypto(engine.state(), "BTC/USD", Some(crypto_bars())).await.unwrap();
+        let metrics = engine.state().metrics.render();
+        assert!(metrics.contains("ladybug_alpaca_request_seconds_count{endpoint=\"crypto_positions\"} 1"), "{}", metrics);
+        assert!(!metrics.contains("endpoint=\"positions\""));
+    }
+
+    #[tokio::test]
+    async fn analysis_log_carries_the_signal_components() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        let explanation = SignalExplanation { rsi: Some(75.0), rsi_score: -0.3, crossover_score: 0.2, total: -0.1, ..Default::default() };
+        let details = |state: &AppState| state.logger.get_logs().into_iter().find(|l| l.category == "Analysis").and_then(|l| l.details);
+
+        state.config.write().await.logging.signal_details = false;
+        log_analysis(state, "AAPL", 190.0, -0.1, 0.0, Some(&explanation), false).await;
+        assert_eq!(details(state), None);
+
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.logging.signal_details = true;
+        log_analysis(state, "AAPL", 190.0, -0.1, 0.0, Some(&explanation), false).await;
+        let details = details(state).unwrap();
+        assert_eq!(details["rsi"], 75.0);
+        assert_eq!(details["rsi_score"], -0.3);
+        assert_eq!(details["crossover_score"], 0.2);
+    }
+
+    #[tokio::test]
+    async fn correlated_holdings_count_against_a_new_entry() {
+        let held = json!({
+            "symbol": "MSFT", "qty": "10", "avg_entry_price": "100", "current_price": "100",
+            "unrealized_pl": "0", "asset_class": "us_equity",
+        });
+        let host = mock_alpaca(json!([held])).await;
+        let engine = test_support::engine(&host);
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.correlation.enabled = true;
+            config.correlation.threshold = 0.8;
+            config.correlation.max_exposure_pct = 1.5;
+        }
+        // AAPL moves with MSFT, KO against it
+        let closes = |sign: f64| -> Vec<f64> {
+            (0..30).map(|i| 100.0 + sign * (i % 5) as f64).collect()
+        };
+        state.correlations.record("MSFT", &closes(1.0));
+        state.correlations.record("AAPL", &closes(1.0));
+        state.correlations.record("KO", &closes(-1.0));
+
+        // $1,000 more on top of the $1,000 MSFT holding is 2% of the $100k account
+        assert!(correlation_blocks_entry(state, "AAPL", 1_000.0).await);
+        assert!(!correlation_blocks_entry(state, "KO", 1_000.0).await);
+    }
+
+    #[tokio::test]
+    async fn unit_cap_counts_the_held_position() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.instruments.max_units.insert("BTC/USD".to_string(), 1.0);
+        let positions: Vec<alpaca::Position> = serde_json::from_value(json!([crypto_position("BTCUSD", 0.75)])).unwrap();
+
+        assert_eq!(apply_unit_cap(state, "BTC/USD", 0.5, &positions, 0.01).await, 0.25);
+        assert_eq!(apply_unit_cap(state, "BTC/USD", 0.5, &[], 0.01).await, 0.5);
+        // No cap configured for the symbol
+        assert_eq!(apply_unit_cap(state, "ETH/USD", 5.0, &positions, 0.01).await, 5.0);
+    }
+
+    #[tokio::test]
+    async fn afternoon_only_symbol_buys_only_in_the_afternoon() {
+        // Wednesday 2024-06-12 in New York (EDT, UTC-4)
+        let at = |hour: u32| Utc.with_ymd_and_hms(2024, 6, 12, hour + 4, 0, 0).unwrap();
+        assert!(!in_time_window(at(10), "13:00-16:00", "America/New_York"));
+        assert!(in_time_window(at(13), "13:00-16:00", "America/New_York"));
+        assert!(!in_time_window(at(16), "13:00-16:00", "America/New_York"));
+        // Wrapping midnight, and no usable window
+        assert!(in_time_window(at(19) + chrono::Duration::hours(4), "20:00-02:00", "America/New_York"));
+        assert!(in_time_window(at(10), "", "America/New_York"));
+
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        let now = Utc::now();
+        let window = |from: i64, to: i64| format!(
+            "{}-{}", (now + chrono::Duration::hours(from)).format("%H:%M"), (now + chrono::Duration::hours(to)).format("%H:%M")
+        );
+        {
+            let mut config = state.config.write().await;
+            config.instruments.entry_window_timezone = "UTC".to_string();
+            config.instruments.entry_windows.insert("TSLA".to_string(), window(2, 4));
+            config.instruments.entry_windows.insert("NVDA".to_string(), window(-1, 1));
+        }
+        assert!(outside_entry_window(state, "TSLA", 0.5).await);
+        assert!(!outside_entry_window(state, "NVDA", 0.5).await);
+        assert!(!outside_entry_window(state, "AAPL", 0.5).await);
+    }
+
+    #[test]
+    fn schedule_switches_mode_at_window_boundaries() {
+        let windows: HashMap<String, String> = [
+            ("09:30-11:00", "volatile"),
+            ("11:00-16:00", "conservative"),
+            ("20:00-04:00", "hybrid"),
+        ].into_iter().map(|(w, m)| (w.to_string(), m.to_string())).collect();
+        // Wednesday 2024-06-12 in New York (EDT, UTC-4)
+        let at = |hour: u32, minute: u32| Utc.with_ymd_and_hms(2024, 6, 12, hour + 4, minute, 0).unwrap();
+        let mode = |now| scheduled_mode(now, &windows, "America/New_York").map(|(_, mode)| mode);
+
+        assert_eq!(mode(at(9, 29)), None);
+        assert_eq!(mode(at(9, 30)), Some(TradingMode::Volatile));
+        assert_eq!(mode(at(10, 59)), Some(TradingMode::Volatile));
+        // End is exclusive, so 11:00 already belongs to the next window
+        assert_eq!(mode(at(11, 0)), Some(TradingMode::Conservative));
+        assert_eq!(mode(at(16, 0)), None);
+        assert_eq!(mode(at(19, 59) + chrono::Duration::minutes(1)), Some(TradingMode::Hybrid));
+    }
+
+    #[tokio::test]
+    async fn low_volume_names_get_smaller_orders() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        state.config.write().await.instruments.max_adv_pct = 1.0;
+        let bars_with_volume = |v: i64| -> Vec<alpaca::Bar> {
+            (0..20).map(|_| alpaca::Bar { t: String::new(), o: 5.0, h: 5.0, l: 5.0, c: 5.0, v }).collect()
+        };
+
+        // 100 shares a bar is ~7,800 a day, so 1% is 78 shares
+        assert_eq!(apply_adv_cap(state, "THIN", 500.0, &bars_with_volume(100), 1.0).await, 78.0);
+        assert_eq!(apply_adv_cap(state, "DEEP", 500.0, &bars_with_volume(1_000_000), 1.0).await, 500.0);
+    }
+
+    #[tokio::test]
+    async fn high_volatility_regime_shrinks_entries() {
+        let engine = test_support::engine(NO_ALPACA);
+        let state = engine.state();
+        {
+            let mut config = state.config.write().await;
+            config.volatility_regime.enabled = true;
+            config.volatility_regime.size_multiplier = 0.5;
+            config.instruments.max_adv_pct = 0.0;
+        }
+        let size = || preview_entry_size(state, "AAPL", PRICE, false, 50_000.0, &[], &[]);
+
+        // Closes moving 0.1% a bar, well under the 0.4% threshold
+        state.correlations.record("AAPL", &(0..20).map(|i| PRICE * 1.001f64.powi(i)).collect::<Vec<_>>());
+        update_volatility_regime(state, false).await;
+        let calm = size().await;
+
+        // Closes swinging 2% either way
+        state.correlations.record("AAPL", &(0..20).map(|i| if i % 2 == 0 { PRICE } else { PRICE * 1.02 }).collect::<Vec<_>>());
+        update_volatility_regime(state, false).await;
+        let stormy = size().await;
+
+        assert!(state.volatility_regime.is_high(false));
+        assert_eq!(stormy.binding, "volatility_regime");
+        // 5% of 50k at $100 is 25 shares; half of that rounds down to 12
+        assert_eq!((calm.qty, stormy.qty), (25.0, 12.0));
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::backoff::SymbolBackoff;
+use crate::config::Config;
+use crate::engine::{AppState, PnlSnapshot, PortfolioSnapshot, TradeRecord, TradingMode};
+use crate::notes::PositionNote;
+use crate::portfolio::DrawdownAlerts;
+use crate::rotation::Rotation;
+use crate::session::SessionSummary;
+
+// Bumped whenever EngineState's shape changes; imports must match exactly
+pub const STATE_VERSION: u32 = 2;
+
+// Everything mutable in a running engine as one JSON document, for
+// GET /state/export and POST /state/import. Secrets (Alpaca credentials, API
+// tokens) are never exported, and an import keeps the running ones.
+#[derive(Serialize, Deserialize)]
+pub struct EngineState {
+    pub version: u32,
+    pub exported_at: String,
+    pub trading_enabled: bool,
+    pub crypto_trading_enabled: bool,
+    pub maintenance_mode: bool,
+    pub equity_floor_breached: bool,
+    pub trading_mode: TradingMode,
+    pub config: Config,
+    pub trade_history: Vec<TradeRecord>,
+    pub portfolio_history: Vec<PortfolioSnapshot>,
+    pub pnl_history: Vec<PnlSnapshot>,
+    pub buy_streaks: BTreeMap<String, u32>,
+    pub profit_tiers_taken: BTreeMap<String, usize>,
+    pub atr: BTreeMap<String, f64>,
+    pub high_water: BTreeMap<String, (f64, f64)>,  // Per position: (entry, highest price seen)
+    pub rotation: Rotation,
+    pub notes: BTreeMap<String, PositionNote>,
+    pub last_session: Option<SessionSummary>,
+    pub news_symbols: Vec<String>,
+    pub drawdown_alerts: DrawdownAlerts,
+    pub order_backoff: Vec<SymbolBackoff>,
+}
+
+fn valid_timestamp(timestamp: &str) -> bool {
+    DateTime::parse_from_rfc3339(timestamp).is_ok()
+}
+
+impl EngineState {
+    pub async fn capture(state: &AppState) -> Self {
+        Self {
+            version: STATE_VERSION,
+            exported_at: Utc::now().to_rfc3339(),
+            trading_enabled: *state.trading_enabled.read().await,
+            crypto_trading_enabled: *state.crypto_trading_enabled.read().await,
+            maintenance_mode: *state.maintenance_mode.read().await,
+            equity_floor_breached: *state.equity_floor_breached.read().await,
+            trading_mode: state.trading_mode.read().await.clone(),
+            config: state.config.read().await.clone(),
+            trade_history: state.trade_history.read().await.clone(),
+            portfolio_history: state.portfolio_history.read().await.clone(),
+            pnl_history: state.pnl_history.read().await.clone(),
+            buy_streaks: state.buy_streaks.iter().map(|e| (e.key().clone(), *e.value())).collect(),
+            profit_tiers_taken: state.profit_tiers_taken.iter().map(|e| (e.key().clone(), *e.value())).collect(),
+            atr: state.atr.iter().map(|e| (e.key().clone(), *e.value())).collect(),
+            high_water: state.high_water.iter().map(|e| (e.key().clone(), *e.value())).collect(),
+            rotation: state.rotation.read().await.clone(),
+            notes: state.notes.snapshot(),
+            last_session: state.last_session.read().await.clone(),
+            news_symbols: state.news_symbols.read().await.clone(),
+            drawdown_alerts: state.drawdown_alerts.read().await.clone(),
+            order_backoff: state.order_backoff.snapshot(),
+        }
+    }
+
+    // Everything that would leave the engine inconsistent, checked before
+    // anything is applied
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != STATE_VERSION {
+            return Err(format!("unsupported state version {} (expected {})", self.version, STATE_VERSION));
+        }
+        let thresholds = &self.config.thresholds;
+        if thresholds.stock_buy <= thresholds.stock_sell || thresholds.crypto_buy <= thresholds.crypto_sell {
+            return Err("buy thresholds must be above sell thresholds".to_string());
+        }
+        if self.config.portfolio.history_max_snapshots == 0 {
+            return Err("portfolio.history_max_snapshots must be positive".to_string());
+        }
+        for trade in &self.trade_history {
+            if trade.action != "BUY" && trade.action != "SELL" {
+                return Err(format!("trade {} has unknown action '{}'", trade.id, trade.action));
+            }
+            if !trade.quantity.is_finite() || trade.quantity < 0.0 || !trade.price.is_finite() || !trade.pnl.is_finite() {
+                return Err(format!("trade {} has an invalid quantity, price or P&L", trade.id));
+            }
+            if !valid_timestamp(&trade.timestamp) {
+                return Err(format!("trade {} has an invalid timestamp", trade.id));
+            }
+        }
+        for snapshot in &self.portfolio_history {
+            if !valid_timestamp(&snapshot.timestamp) || !snapshot.total_value.is_finite() || !snapshot.cash.is_finite() {
+                return Err(format!("invalid portfolio snapshot at '{}'", snapshot.timestamp));
+            }
+        }
+        if let Some(pnl) = self.pnl_history.iter().find(|s| !valid_timestamp(&s.timestamp)) {
+            return Err(format!("invalid P&L snapshot at '{}'", pnl.timestamp));
+        }
+        if let Some((symbol, _)) = self.atr.iter().find(|(_, atr)| !atr.is_finite() || **atr < 0.0) {
+            return Err(format!("invalid ATR for {}", symbol));
+        }
+        let invalid_mark = |(entry, high): &(f64, f64)| !entry.is_finite() || !high.is_finite() || *entry <= 0.0 || high < entry;
+        if let Some((symbol, _)) = self.high_water.iter().find(|(_, mark)| invalid_mark(mark)) {
+            return Err(format!("invalid high-water mark for {}", symbol));
+        }
+        Ok(())
+    }
+
+    // Replaces the running state. Every lock is taken before anything
+    // changes, so no cycle sees a half-imported engine. Call validate first.
+    pub async fn apply(self, state: &AppState) {
+        let mut config = state.config.write().await;
+        let mut trading_mode = state.trading_mode.write().await;
+        let mut trading_enabled = state.trading_enabled.write().await;
+        let mut crypto_trading_enabled = state.crypto_trading_enabled.write().await;
+        let mut maintenance_mode = state.maintenance_mode.write().await;
+        let mut equity_floor_breached = state.equity_floor_breached.write().await;
+        let mut trade_history = state.trade_history.write().await;
+        let mut portfolio_history = state.portfolio_history.write().await;
+        let mut pnl_history = state.pnl_history.write().await;
+        let mut rotation = state.rotation.write().await;
+        let mut last_session = state.last_session.write().await;
+        let mut news_symbols = state.news_symbols.write().await;
+        let mut drawdown_alerts = state.drawdown_alerts.write().await;
+
+        let mut imported = self.config;
+        imported.credentials = config.credentials.clone();
+        imported.api.read_token = config.api.read_token.clone();
+        imported.api.admin_token = config.api.admin_token.clone();
+        *config = imported;
+        *trading_mode = self.trading_mode;
+        *trading_enabled = self.trading_enabled;
+        *crypto_trading_enabled = self.crypto_trading_enabled;
+        *maintenance_mode = self.maintenance_mode;
+        *equity_floor_breached = self.equity_floor_breached;
+        *trade_history = self.trade_history;
+        *portfolio_history = self.portfolio_history;
+        *pnl_history = self.pnl_history;
+        *rotation = self.rotation;
+        *last_session = self.last_session;
+        *news_symbols = self.news_symbols;
+        *drawdown_alerts = self.drawdown_alerts;
+
+        state.buy_streaks.clear();
+        for (symbol, streak) in self.buy_streaks {
+            state.buy_streaks.insert(symbol, streak);
+        }
+        state.profit_tiers_taken.clear();
+        for (symbol, taken) in self.profit_tiers_taken {
+            state.profit_tiers_taken.insert(symbol, taken);
+        }
+        state.atr.clear();
+        for (symbol, atr) in self.atr {
+            state.atr.insert(symbol, atr);
+        }
+        state.high_water.clear();
+        for (symbol, mark) in self.high_water {
+            state.high_water.insert(symbol, mark);
+        }
+        state.notes.replace(self.notes);
+        state.order_backoff.restore(self.order_backoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    const NO_ALPACA: &str = "http://127.0.0.1:9";
+
+    // The export as JSON, minus the one field that changes on every export
+    async fn export(state: &AppState) -> serde_json::Value {
+        let mut json = serde_json::to_value(EngineState::capture(state).await).unwrap();
+        json.as_object_mut().unwrap().remove("exported_at");
+        json
+    }
+
+    #[tokio::test]
+    async fn export_import_export_is_lossless() {
+        let source = test_support::engine(NO_ALPACA);
+        let state = source.state();
+        *state.maintenance_mode.write().await = true;
+        state.buy_streaks.insert("AAPL".to_string(), 2);
+        state.atr.insert("AAPL".to_string(), 1.5);
+        state.high_water.insert("AAPL".to_string(), (100.0, 112.5));
+        for _ in 0..3 {
+            state.order_backoff.record_failure("AAPL", "rejected");
+        }
+        *state.news_symbols.write().await = vec!["NVDA".to_string()];
+        let today = Utc::now().date_naive();
+        state.drawdown_alerts.write().await.newly_crossed(today, 6.0, &[5.0, 10.0]);
+
+        let json = serde_json::to_string(&EngineState::capture(state).await).unwrap();
+        let imported: EngineState = serde_json::from_str(&json).unwrap();
+        imported.validate().unwrap();
+        let target = test_support::engine(NO_ALPACA);
+        imported.apply(target.state()).await;
+
+        assert_eq!(export(target.state()).await, export(state).await);
+        assert!(target.state().order_backoff.remaining_secs("AAPL").is_some());
+        assert_eq!(target.state().high_water.get("AAPL").map(|m| *m), Some((100.0, 112.5)));
+        let refired = target.state().drawdown_alerts.write().await.newly_crossed(today, 6.0, &[5.0, 10.0]);
+        assert!(refired.is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+// Alpaca answers requests outside the data plan (recent SIP data, some
+// symbols/feeds on the free tier) with 403/422 and one of these messages
+const ENTITLEMENT_MARKERS: &[&str] = &[
+    "subscription does not permit",
+    "insufficient subscription",
+    "not entitled",
+    "no permission",
+];
+
+// Returned (via anyhow) by market data calls that the plan doesn't cover,
+// so callers can tell it apart from "no bars yet"
+#[derive(Debug, Clone, Serialize)]
+pub struct EntitlementError {
+    pub symbol: String,
+    pub endpoint: String,
+    pub feed: String,
+    pub message: String,
+    pub first_seen: String,
+    pub occurrences: u32,
+}
+
+impl fmt::Display for EntitlementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "data plan does not cover {} {} on the '{}' feed: {}",
+               self.symbol, self.endpoint, self.feed, self.message)
+    }
+}
+
+impl std::error::Error for EntitlementError {}
+
+pub fn is_entitlement_error(status: u16, body: &str) -> bool {
+    let body = body.to_lowercase();
+    status == 403 || ENTITLEMENT_MARKERS.iter().any(|m| body.contains(m))
+}
+
+// Pulls the human-readable message out of an Alpaca error body
+pub fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(|m| m.to_string()))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+// Latest entitlement rejection per symbol + endpoint, for /diagnostics
+#[derive(Clone, Default)]
+pub struct EntitlementTracker {
+    issues: Arc<DashMap<(String, String), EntitlementError>>,
+}
+
+impl EntitlementTracker {
+    pub fn record(&self, symbol: &str, endpoint: &str, feed: &str, body: &str) -> EntitlementError {
+        let mut entry = self.issues
+            .entry((symbol.to_string(), endpoint.to_string()))
+            .or_insert_with(|| EntitlementError {
+                symbol: symbol.to_string(),
+                endpoint: endpoint.to_string(),
+                feed: feed.to_string(),
+                message: String::new(),
+                first_seen: Utc::now().to_rfc3339(),
+                occurrences: 0,
+            });
+        entry.feed = feed.to_string();
+        entry.message = error_message(body);
+        entry.occurrences += 1;
+        entry.clone()
+    }
+
+    pub fn clear(&self, symbol: &str, endpoint: &str) {
+        self.issues.remove(&(symbol.to_string(), endpoint.to_string()));
+    }
+
+    pub fn snapshot(&self) -> Vec<EntitlementError> {
+        let mut issues: Vec<EntitlementError> = self.issues.iter().map(|i| i.value().clone()).collect();
+        issues.sort_by(|a, b| (&a.symbol, &a.endpoint).cmp(&(&b.symbol, &b.endpoint)));
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_plan_rejections() {
+        assert!(is_entitlement_error(403, "forbidden"));
+        assert!(is_entitlement_error(422, r#"{"message":"subscription does not permit querying recent SIP data"}"#));
+        assert!(!is_entitlement_error(422, r#"{"message":"invalid timeframe"}"#));
+        assert!(!is_entitlement_error(500, "internal error"));
+    }
+
+    #[test]
+    fn records_message_and_counts_repeats() {
+        let tracker = EntitlementTracker::default();
+        let body = r#"{"code":42210000,"message":"subscription does not permit querying recent SIP data"}"#;
+        tracker.record("AAPL", "bars", "sip", body);
+        let issue = tracker.record("AAPL", "bars", "sip", body);
+
+        assert_eq!(issue.occurrences, 2);
+        assert_eq!(issue.message, "subscription does not permit querying recent SIP data");
+        assert_eq!(tracker.snapshot().len(), 1);
+
+        tracker.clear("AAPL", "bars");
+        assert!(tracker.snapshot().is_empty());
+    }
+}
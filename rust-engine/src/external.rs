@@ -0,0 +1,93 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::engine::TradeRecord;
+use crate::symbols;
+
+// What the engine's own trade history says it holds in a symbol
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackedPosition {
+    pub qty: f64,
+    pub cost: f64,  // Average-cost basis of `qty`
+}
+
+// Net holdings per normalized symbol from the trade history, average cost.
+// Buys blend by quantity, which confirm_fill sets to the filled amount, so
+// partially filled scale-ins weight by what was actually bought.
+pub fn tracked_positions(trades: &[TradeRecord]) -> HashMap<String, TrackedPosition> {
+    let mut tracked: HashMap<String, TrackedPosition> = HashMap::new();
+    for trade in trades {
+        let position = tracked.entry(symbols::normalize(&trade.symbol)).or_default();
+        let price = trade.fill_price.unwrap_or(trade.price);
+        if trade.action == "BUY" {
+            position.qty += trade.quantity;
+            position.cost += trade.quantity * price;
+        } else if position.qty > 0.0 {
+            let sold = trade.quantity.min(position.qty);
+            position.cost -= position.cost / position.qty * sold;
+            position.qty -= sold;
+        }
+    }
+    tracked
+}
+
+// Shares added outside the engine: Alpaca holds more than the history
+// accounts for. Returns (qty, price) of the missing buy, priced so the
+// tracked cost basis matches Alpaca's average entry.
+pub fn external_add(
+    tracked: TrackedPosition,
+    broker_qty: f64,
+    broker_avg_entry: f64,
+    tolerance: f64,
+) -> Option<(f64, f64)> {
+    let missing = broker_qty - tracked.qty;
+    if missing <= tolerance {
+        return None;
+    }
+    let price = (broker_qty * broker_avg_entry - tracked.cost) / missing;
+    Some((missing, if price > 0.0 { price } else { broker_avg_entry }))
+}
+
+// Orders in flight make Alpaca and the history disagree for a moment, so
+// symbols traded recently are left alone
+pub fn recently_traded(trades: &[TradeRecord], symbol: &str, now: DateTime<Utc>, window: Duration) -> bool {
+    trades.iter().rev()
+        .filter(|t| symbols::same_symbol(&t.symbol, symbol))
+        .filter_map(|t| DateTime::parse_from_rfc3339(&t.timestamp).ok())
+        .any(|ts| now.signed_duration_since(ts) < window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::TradeReason;
+    use crate::test_support::trade;
+
+    #[test]
+    fn external_buy_is_reconciled_into_the_cost_basis() {
+        let mut trades = vec![trade("AAPL", "BUY", 10.0, 100.0, Utc::now())];
+        let tracked = tracked_positions(&trades)["AAPL"];
+
+        // The user bought 5 more by hand; Alpaca now averages $104 over 15
+        let (qty, price) = external_add(tracked, 15.0, 104.0, 1e-6).unwrap();
+        assert_eq!((qty, price), (5.0, 112.0));
+
+        trades.push(TradeRecord { reason: Some(TradeReason::External), ..trade("AAPL", "BUY", qty, price, Utc::now()) });
+        let reconciled = tracked_positions(&trades)["AAPL"];
+        assert_eq!(reconciled.qty, 15.0);
+        assert!((reconciled.cost / reconciled.qty - 104.0).abs() < 1e-9);
+        assert_eq!(external_add(reconciled, 15.0, 104.0, 1e-6), None);
+    }
+
+    #[test]
+    fn partial_fills_weight_the_average_cost() {
+        // Two scale-ins for 10 each: 4 filled at $100, then 6 at $110
+        let trades = vec![
+            TradeRecord { fill_price: Some(100.0), ..trade("AAPL", "BUY", 4.0, 99.0, Utc::now()) },
+            TradeRecord { fill_price: Some(110.0), ..trade("AAPL", "BUY", 6.0, 111.0, Utc::now()) },
+        ];
+        let tracked = tracked_positions(&trades)["AAPL"];
+        assert_eq!(tracked.qty, 10.0);
+        assert!((tracked.cost / tracked.qty - 106.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,118 @@
+// Artificial latency and failures for every Alpaca/webhook call, to exercise
+// timeouts, backoff and degraded paths without a flaky real API. Only built
+// with `--features fault-injection`; never enable it for live trading.
+//
+//   FAULT_DELAY_MS=750      added before each request
+//   FAULT_ERROR_RATE=0.2    share of requests answered with a synthetic 503
+//
+// With RNG_SEED set, which requests fail is the same from run to run.
+use axum::http::StatusCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::Response;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+struct FaultConfig {
+    delay: Duration,
+    error_rate: f64,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultConfig {
+    fn new(delay: Duration, error_rate: f64, seed: Option<u64>) -> Self {
+        FaultConfig {
+            delay,
+            error_rate: error_rate.clamp(0.0, 1.0),
+            rng: Mutex::new(match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            }),
+        }
+    }
+}
+
+fn config() -> &'static FaultConfig {
+    static CONFIG: OnceLock<FaultConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let env = |key: &str| std::env::var(key).ok().and_then(|v| v.trim().parse::<f64>().ok());
+        let config = FaultConfig::new(
+            Duration::from_millis(env("FAULT_DELAY_MS").unwrap_or(0.0).max(0.0) as u64),
+            env("FAULT_ERROR_RATE").unwrap_or(0.0),
+            std::env::var("RNG_SEED").ok().and_then(|v| v.trim().parse().ok()),
+        );
+        warn!("🧪 Fault injection active: +{:?} per request, {:.0}% synthetic failures",
+              config.delay, config.error_rate * 100.0);
+        config
+    })
+}
+
+// Sleeps for the configured delay, then maybe returns a failure to hand back
+// in place of the real response
+pub async fn inject(url: &str) -> Option<Response> {
+    inject_with(config(), url).await
+}
+
+async fn inject_with(config: &FaultConfig, url: &str) -> Option<Response> {
+    if !config.delay.is_zero() {
+        tokio::time::sleep(config.delay).await;
+    }
+    if config.error_rate > 0.0 && config.rng.lock().unwrap().gen_bool(config.error_rate) {
+        info!("🧪 Injected 503 for {}", url);
+        let response = axum::http::Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body("{\"message\":\"injected fault\"}")
+            .ok()?;
+        return Some(Response::from(response));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backoff::OrderBackoff;
+
+    const URL: &str = "http://127.0.0.1:9/v2/orders";
+
+    #[tokio::test(start_paused = true)]
+    async fn injected_delay_precedes_every_request() {
+        let config = FaultConfig::new(Duration::from_millis(750), 0.0, None);
+        let start = tokio::time::Instant::now();
+
+        assert!(inject_with(&config, URL).await.is_none());
+        assert_eq!(start.elapsed(), Duration::from_millis(750));
+    }
+
+    #[tokio::test]
+    async fn injected_failures_drive_the_order_backoff() {
+        let config = FaultConfig::new(Duration::ZERO, 1.0, Some(7));
+        let backoff = OrderBackoff::new(3, 60, 600);
+
+        let mut applied = Vec::new();
+        for _ in 0..4 {
+            let response = inject_with(&config, URL).await.expect("every request should fail");
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            applied.push(backoff.record_failure("AAPL", &response.text().await.unwrap()));
+        }
+        assert_eq!(applied, vec![None, None, Some(60), Some(120)]);
+        assert!(backoff.remaining_secs("AAPL").is_some());
+    }
+
+    #[tokio::test]
+    async fn seeded_faults_repeat_from_run_to_run() {
+        async fn failures(seed: u64) -> Vec<bool> {
+            let config = FaultConfig::new(Duration::ZERO, 0.5, Some(seed));
+            let mut failed = Vec::new();
+            for _ in 0..32 {
+                failed.push(inject_with(&config, URL).await.is_some());
+            }
+            failed
+        }
+
+        let first = failures(42).await;
+        assert_eq!(first, failures(42).await);
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+}
@@ -0,0 +1,146 @@
+// Fixed prices and bars in place of Alpaca market data, so the engine and
+// dashboard can be driven deterministically. Only built with
+// `--features frozen-prices`; account, positions and orders still go to the
+// (paper) API. Pair it with RNG_SEED for repeatable signals.
+//
+//   FROZEN_PRICES_FILE=fixtures/prices.json
+//
+//   {
+//     "prices": { "AAPL": 190.0, "BTC/USD": 60000.0 },
+//     "bars": { "AAPL": [{ "t": "2024-01-02T14:30:00Z", "o": 189.5, "h": 190.2, "l": 189.1, "c": 190.0, "v": 1200 }] }
+//   }
+//
+// A symbol with a price but no bars gets flat bars at that price, stamped on
+// the current 5-minute grid so halt detection sees them as fresh. Symbols
+// missing from the fixture fall through to the real API.
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::warn;
+
+use crate::alpaca::Bar;
+use crate::symbols;
+
+#[derive(Default, Deserialize)]
+struct Fixture {
+    #[serde(default)]
+    prices: HashMap<String, f64>,
+    #[serde(default)]
+    bars: HashMap<String, Vec<Bar>>,
+}
+
+impl Fixture {
+    // Parses a fixture file, keying everything by normalized symbol
+    fn parse(text: &str) -> serde_json::Result<Self> {
+        let fixture: Fixture = serde_json::from_str(text)?;
+        Ok(Fixture {
+            prices: fixture.prices.into_iter().map(|(s, p)| (symbols::normalize(&s), p)).collect(),
+            bars: fixture.bars.into_iter().map(|(s, b)| (symbols::normalize(&s), b)).collect(),
+        })
+    }
+
+    fn price(&self, symbol: &str) -> Option<f64> {
+        let key = symbols::normalize(symbol);
+        self.prices.get(&key).copied()
+            .or_else(|| self.bars.get(&key)?.last().map(|b| b.c))
+    }
+
+    fn bars(&self, symbol: &str, limit: u32, now: DateTime<Utc>) -> Option<Vec<Bar>> {
+        let key = symbols::normalize(symbol);
+        if let Some(bars) = self.bars.get(&key) {
+            return Some(bars[bars.len().saturating_sub(limit as usize)..].to_vec());
+        }
+        let price = self.prices.get(&key).copied()?;
+        Some(flat_bars(price, limit, now))
+    }
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let Ok(path) = std::env::var("FROZEN_PRICES_FILE") else {
+            warn!("🧊 frozen-prices build without FROZEN_PRICES_FILE, using live data");
+            return Fixture::default();
+        };
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Fixture::parse(&text).map_err(anyhow::Error::from));
+        match loaded {
+            Ok(fixture) => {
+                warn!("🧊 Prices frozen from {}: {} prices, {} bar series",
+                      path, fixture.prices.len(), fixture.bars.len());
+                fixture
+            }
+            Err(e) => {
+                warn!("🧊 Could not load FROZEN_PRICES_FILE {}: {} - using live data", path, e);
+                Fixture::default()
+            }
+        }
+    })
+}
+
+// The frozen price: the fixture's price, else the last fixture bar's close
+pub fn price(symbol: &str) -> Option<f64> {
+    fixture().price(symbol)
+}
+
+// The most recent `limit` fixture bars, or flat bars at the frozen price
+pub fn bars(symbol: &str, limit: u32) -> Option<Vec<Bar>> {
+    fixture().bars(symbol, limit, Utc::now())
+}
+
+fn flat_bars(price: f64, limit: u32, now: DateTime<Utc>) -> Vec<Bar> {
+    let step = Duration::minutes(5);
+    let last = now.duration_trunc(step).unwrap_or(now);
+    (0..limit as i32).rev()
+        .map(|i| Bar {
+            t: (last - step * i).to_rfc3339(),
+            o: price,
+            h: price,
+            l: price,
+            c: price,
+            v: 1000,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const FIXTURE: &str = r#"{
+        "prices": { "aapl": 190.0 },
+        "bars": { "BTC/USD": [
+            { "t": "2024-01-02T14:25:00Z", "o": 59900.0, "h": 60100.0, "l": 59800.0, "c": 59950.0, "v": 3 },
+            { "t": "2024-01-02T14:30:00Z", "o": 59950.0, "h": 60200.0, "l": 59900.0, "c": 60000.0, "v": 5 }
+        ] }
+    }"#;
+
+    #[test]
+    fn prices_come_from_the_fixture_under_any_spelling() {
+        let fixture = Fixture::parse(FIXTURE).unwrap();
+        assert_eq!(fixture.price("AAPL"), Some(190.0));
+        // No price entry: the last bar's close
+        assert_eq!(fixture.price("BTCUSD"), Some(60000.0));
+        assert_eq!(fixture.price("MSFT"), None);
+
+        let now = Utc::now();
+        let bars = fixture.bars("BTC/USD", 1, now).unwrap();
+        assert_eq!((bars.len(), bars[0].c), (1, 60000.0));
+        assert_eq!(fixture.bars("BTC/USD", 10, now).unwrap().len(), 2);
+        assert!(fixture.bars("MSFT", 10, now).is_none());
+    }
+
+    #[test]
+    fn flat_bars_end_on_the_current_five_minute_grid() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 2, 14, 33, 20).unwrap();
+        let bars = flat_bars(190.0, 3, now);
+
+        let stamps: Vec<&str> = bars.iter().map(|b| b.t.as_str()).collect();
+        assert_eq!(stamps, vec!["2024-01-02T14:20:00+00:00", "2024-01-02T14:25:00+00:00", "2024-01-02T14:30:00+00:00"]);
+        assert!(bars.iter().all(|b| b.o == 190.0 && b.h == 190.0 && b.l == 190.0 && b.c == 190.0));
+        assert_eq!(Fixture::parse(FIXTURE).unwrap().bars("AAPL", 3, now).unwrap()[2].t, bars[2].t);
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::alpaca::Bar;
+
+// An open that jumped away from the previous bar's close, usually the
+// overnight move showing up in the session's first bar. Momentum and the
+// crossover read it as a trend even though nothing traded in between.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gap {
+    pub bars_ago: usize,  // 0 = the latest bar opened with the gap
+    pub prior_close: f64,
+    pub open: f64,
+    pub pct: f64,         // Signed, positive for a gap up
+}
+
+impl Gap {
+    // The gap has filled once price trades back to the prior close
+    pub fn filled(&self, bars_since: &[Bar], current_price: f64) -> bool {
+        if self.pct > 0.0 {
+            current_price <= self.prior_close || bars_since.iter().any(|b| b.l <= self.prior_close)
+        } else {
+            current_price >= self.prior_close || bars_since.iter().any(|b| b.h >= self.prior_close)
+        }
+    }
+}
+
+// Most recent open more than `threshold_pct` away from the close before it
+pub fn latest_gap(bars: &[Bar], threshold_pct: f64) -> Option<Gap> {
+    if threshold_pct <= 0.0 {
+        return None;
+    }
+    bars.windows(2)
+        .enumerate()
+        .rev()
+        .find_map(|(i, w)| {
+            let (prev, bar) = (&w[0], &w[1]);
+            if prev.c <= 0.0 {
+                return None;
+            }
+            let pct = (bar.o - prev.c) / prev.c * 100.0;
+            (pct.abs() > threshold_pct).then(|| Gap {
+                bars_ago: bars.len() - 2 - i,
+                prior_close: prev.c,
+                open: bar.o,
+                pct,
+            })
+        })
+}
+
+// Whether a gap still holds off new entries: it hasn't filled and fewer than
+// `max_bars` bars have printed since (0 = wait for the fill only)
+pub fn blocks_entry(bars: &[Bar], threshold_pct: f64, max_bars: usize, current_price: f64) -> Option<Gap> {
+    let gap = latest_gap(bars, threshold_pct)?;
+    if max_bars > 0 && gap.bars_ago >= max_bars {
+        return None;
+    }
+    // The gap bar itself opened away from the close, so fills are checked
+    // from its range onward
+    let since = &bars[bars.len() - 1 - gap.bars_ago..];
+    if gap.filled(since, current_price) {
+        return None;
+    }
+    Some(gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(o: f64, h: f64, l: f64, c: f64) -> Bar {
+        Bar { t: String::new(), o, h, l, c, v: 1_000 }
+    }
+
+    // Closes at 100, then opens 5% higher and drifts up
+    fn gapped_up() -> Vec<Bar> {
+        let mut bars = vec![bar(100.0, 100.5, 99.5, 100.0); 5];
+        bars.extend([bar(105.0, 106.0, 104.0, 105.5), bar(105.5, 106.5, 105.0, 106.0), bar(106.0, 107.0, 105.5, 106.5)]);
+        bars
+    }
+
+    #[test]
+    fn finds_the_latest_gap_beyond_the_threshold() {
+        let gap = latest_gap(&gapped_up(), 2.0).unwrap();
+        assert_eq!(gap.bars_ago, 2);
+        assert_eq!((gap.prior_close, gap.open), (100.0, 105.0));
+        assert!((gap.pct - 5.0).abs() < 1e-9);
+
+        assert!(latest_gap(&gapped_up(), 6.0).is_none());
+        assert!(latest_gap(&gapped_up(), 0.0).is_none());
+    }
+
+    #[test]
+    fn gap_blocks_entries_until_it_fills_or_ages_out() {
+        let bars = gapped_up();
+        assert!(blocks_entry(&bars, 2.0, 0, 106.5).is_some());
+        // Back to the prior close
+        assert!(blocks_entry(&bars, 2.0, 0, 100.0).is_none());
+        // Three bars allowed, two printed since the gap
+        assert!(blocks_entry(&bars, 2.0, 3, 106.5).is_some());
+        assert!(blocks_entry(&bars, 2.0, 2, 106.5).is_none());
+
+        let mut filled = bars.clone();
+        filled.push(bar(106.0, 106.0, 99.8, 101.0));
+        assert!(blocks_entry(&filled, 2.0, 0, 101.0).is_none());
+    }
+}
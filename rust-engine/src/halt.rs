@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::alpaca::Bar;
+
+// Alpaca's trading API doesn't expose LULD pauses or volatility halts, so a
+// halt is inferred from the data: during market hours a liquid symbol prints
+// a bar every few minutes, and a halted one stops printing altogether.
+pub fn minutes_since_last_bar(bars: &[Bar], now: DateTime<Utc>) -> Option<i64> {
+    let last = DateTime::parse_from_rfc3339(&bars.last()?.t).ok()?;
+    Some(now.signed_duration_since(last).num_minutes())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Halt {
+    pub since: String,
+    pub minutes_without_trades: i64,
+}
+
+// Symbols currently treated as halted. Entries are skipped for them until
+// fresh bars show up again.
+#[derive(Clone, Default)]
+pub struct HaltTracker {
+    halted: Arc<DashMap<String, Halt>>,
+}
+
+impl HaltTracker {
+    // Returns true the first time a symbol is flagged
+    pub fn flag(&self, symbol: &str, minutes_without_trades: i64) -> bool {
+        let mut is_new = false;
+        self.halted
+            .entry(symbol.to_string())
+            .and_modify(|h| h.minutes_without_trades = minutes_without_trades)
+            .or_insert_with(|| {
+                is_new = true;
+                Halt { since: Utc::now().to_rfc3339(), minutes_without_trades }
+            });
+        is_new
+    }
+
+    // Returns true if the symbol was halted
+    pub fn clear(&self, symbol: &str) -> bool {
+        self.halted.remove(symbol).is_some()
+    }
+
+    pub fn is_halted(&self, symbol: &str) -> bool {
+        self.halted.contains_key(symbol)
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, Halt> {
+        self.halted.iter().map(|h| (h.key().clone(), h.value().clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn staleness_is_minutes_since_the_last_bar() {
+        let bar = |t: &str| Bar { t: t.to_string(), o: 1.0, h: 1.0, l: 1.0, c: 1.0, v: 100 };
+        let now = Utc.with_ymd_and_hms(2024, 6, 12, 15, 0, 0).unwrap();
+        let bars = vec![bar("2024-06-12T14:30:00Z"), bar("2024-06-12T14:45:00Z")];
+        assert_eq!(minutes_since_last_bar(&bars, now), Some(15));
+        assert_eq!(minutes_since_last_bar(&[], now), None);
+    }
+
+    #[test]
+    fn a_halt_is_flagged_once_and_cleared_on_resume() {
+        let halts = HaltTracker::default();
+        assert!(halts.flag("AAPL", 20));
+        assert!(!halts.flag("AAPL", 25));
+        assert!(halts.is_halted("AAPL"));
+        assert_eq!(halts.snapshot()["AAPL"].minutes_without_trades, 25);
+
+        assert!(halts.clear("AAPL"));
+        assert!(!halts.clear("AAPL"));
+        assert!(!halts.is_halted("AAPL"));
+    }
+}
@@ -0,0 +1,325 @@
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::engine::{PnlSnapshot, PortfolioSnapshot, TradeReason, TradeRecord};
+
+// Bumped whenever the binary layout of that file's records changes
+const TRADES_VERSION: u32 = 4;     // 2: TradeRecord.slippage_pct, 3: reason, 4: asset_class
+const SNAPSHOTS_VERSION: u32 = 1;
+const PNL_VERSION: u32 = 1;
+const BINARY_MAGIC: [u8; 4] = *b"LBH\0";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryFormat {
+    Jsonl,    // One JSON record per line, human readable
+    Bincode,  // Compact and fast to load, versioned header
+    Off,
+}
+
+impl HistoryFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "jsonl" | "json" => Some(HistoryFormat::Jsonl),
+            "bincode" | "binary" => Some(HistoryFormat::Bincode),
+            "off" | "none" | "" => Some(HistoryFormat::Off),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            HistoryFormat::Bincode => "bin",
+            _ => "jsonl",
+        }
+    }
+}
+
+// Saved as Envelope<&[T]>, loaded as Envelope<Vec<T>>
+#[derive(Serialize, Deserialize)]
+struct Envelope<R> {
+    magic: [u8; 4],
+    version: u32,
+    records: R,
+}
+
+// TradeRecord as written by version 1 files, upgraded on load
+#[derive(Deserialize)]
+struct TradeRecordV1 {
+    id: String,
+    timestamp: String,
+    symbol: String,
+    action: String,
+    quantity: f64,
+    price: f64,
+    pnl: f64,
+    fill_price: Option<f64>,
+    slippage_flagged: bool,
+}
+
+impl From<TradeRecordV1> for TradeRecord {
+    fn from(t: TradeRecordV1) -> Self {
+        TradeRecord {
+            id: t.id,
+            timestamp: t.timestamp,
+            symbol: t.symbol,
+            action: t.action,
+            quantity: t.quantity,
+            price: t.price,
+            pnl: t.pnl,
+            fill_price: t.fill_price,
+            slippage_flagged: t.slippage_flagged,
+            slippage_pct: None,
+            reason: None,
+            asset_class: String::new(),
+        }
+    }
+}
+
+// bincode writes struct fields back to back with no framing, so nesting the
+// older layout reads the same bytes as listing its fields inline
+#[derive(Deserialize)]
+struct TradeRecordV2 {
+    v1: TradeRecordV1,
+    slippage_pct: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct TradeRecordV3 {
+    v2: TradeRecordV2,
+    reason: Option<TradeReason>,
+}
+
+// Magic and version are the first 8 bytes of every binary file (bincode
+// writes the [u8; 4] and the u32 little-endian, unprefixed)
+fn binary_version(bytes: &[u8]) -> Result<u32> {
+    if bytes.len() < 8 || bytes[..4] != BINARY_MAGIC {
+        bail!("not a history file");
+    }
+    Ok(u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]))
+}
+
+// Trade and portfolio-snapshot history on disk, rewritten whole (to a temp
+// file, then renamed) so a crash never leaves a half-written file behind
+#[derive(Clone)]
+pub struct HistoryStore {
+    dir: PathBuf,
+    format: HistoryFormat,
+}
+
+impl HistoryStore {
+    pub fn new(dir: impl Into<PathBuf>, format: HistoryFormat) -> Self {
+        Self { dir: dir.into(), format }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.format != HistoryFormat::Off
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, self.format.extension()))
+    }
+
+    pub fn load_trades(&self) -> Vec<TradeRecord> {
+        self.load_or_empty("trades", |bytes, version| match version {
+            1 => Ok(decode::<TradeRecordV1>(bytes)?.into_iter().map(TradeRecord::from).collect()),
+            2 => Ok(decode::<TradeRecordV2>(bytes)?.into_iter()
+                .map(|t| TradeRecord { slippage_pct: t.slippage_pct, ..TradeRecord::from(t.v1) })
+                .collect()),
+            3 => Ok(decode::<TradeRecordV3>(bytes)?.into_iter()
+                .map(|t| TradeRecord { slippage_pct: t.v2.slippage_pct, reason: t.reason, ..TradeRecord::from(t.v2.v1) })
+                .collect()),
+            TRADES_VERSION => decode(bytes),
+            _ => bail!("unsupported trades version {} (expected {})", version, TRADES_VERSION),
+        })
+    }
+
+    pub fn load_snapshots(&self) -> Vec<PortfolioSnapshot> {
+        self.load_or_empty("snapshots", |bytes, version| match version {
+            SNAPSHOTS_VERSION => decode(bytes),
+            _ => bail!("unsupported snapshots version {} (expected {})", version, SNAPSHOTS_VERSION),
+        })
+    }
+
+    pub fn load_pnl(&self) -> Vec<PnlSnapshot> {
+        self.load_or_empty("pnl_attribution", |bytes, version| match version {
+            PNL_VERSION => decode(bytes),
+            _ => bail!("unsupported pnl_attribution version {} (expected {})", version, PNL_VERSION),
+        })
+    }
+
+    pub fn save_trades(&self, trades: &[TradeRecord]) -> Result<()> {
+        self.save("trades", TRADES_VERSION, trades)
+    }
+
+    pub fn save_snapshots(&self, snapshots: &[PortfolioSnapshot]) -> Result<()> {
+        self.save("snapshots", SNAPSHOTS_VERSION, snapshots)
+    }
+
+    pub fn save_pnl(&self, snapshots: &[PnlSnapshot]) -> Result<()> {
+        self.save("pnl_attribution", PNL_VERSION, snapshots)
+    }
+
+    // `decode_binary` gets the whole file and its header version
+    fn load_or_empty<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        decode_binary: impl Fn(&[u8], u32) -> Result<Vec<T>>,
+    ) -> Vec<T> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+        let path = self.path(name);
+        if !path.exists() {
+            return vec![];
+        }
+        match self.load(&path, decode_binary) {
+            Ok(records) => {
+                info!("💾 Loaded {} {} from {}", records.len(), name, path.display());
+                records
+            }
+            Err(e) => {
+                warn!("⚠️  Could not load {} from {}: {:#}", name, path.display(), e);
+                vec![]
+            }
+        }
+    }
+
+    fn load<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+        decode_binary: impl Fn(&[u8], u32) -> Result<Vec<T>>,
+    ) -> Result<Vec<T>> {
+        let bytes = std::fs::read(path)?;
+        match self.format {
+            HistoryFormat::Bincode => decode_binary(&bytes, binary_version(&bytes)?),
+            _ => String::from_utf8(bytes)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| serde_json::from_str(line).map_err(anyhow::Error::from))
+                .collect(),
+        }
+    }
+
+    fn save<T: Serialize>(&self, name: &str, version: u32, records: &[T]) -> Result<()> {
+        let bytes = match self.format {
+            HistoryFormat::Off => return Ok(()),
+            HistoryFormat::Bincode => bincode::serialize(&Envelope {
+                magic: BINARY_MAGIC,
+                version,
+                records,
+            })?,
+            HistoryFormat::Jsonl => {
+                let mut out = String::new();
+                for record in records {
+                    out.push_str(&serde_json::to_string(record)?);
+                    out.push('\n');
+                }
+                out.into_bytes()
+            }
+        };
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<Vec<T>> {
+    let envelope: Envelope<Vec<T>> = bincode::deserialize(bytes).context("bad binary history file")?;
+    Ok(envelope.records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::{TimeZone, Utc};
+    use std::collections::BTreeMap;
+
+    fn temp_store(format: HistoryFormat) -> HistoryStore {
+        HistoryStore::new(std::env::temp_dir().join(format!("ladybug-history-{}", uuid::Uuid::new_v4())), format)
+    }
+
+    // Every optional field set, so the round trips cover them
+    fn trade(id: &str) -> TradeRecord {
+        TradeRecord {
+            id: id.to_string(),
+            pnl: 250.0,
+            fill_price: Some(61_010.0),
+            slippage_flagged: true,
+            slippage_pct: Some(-0.02),
+            reason: Some(TradeReason::ProfitTake),
+            ..test_support::trade("BTC/USD", "SELL", 0.25, 61_000.0, Utc.with_ymd_and_hms(2024, 6, 12, 14, 30, 0).unwrap())
+        }
+    }
+
+    fn json<T: Serialize>(records: &[T]) -> serde_json::Value {
+        serde_json::to_value(records).unwrap()
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let trades = vec![trade("t-1"), TradeRecord { reason: None, fill_price: None, ..trade("t-2") }];
+        let snapshots = vec![PortfolioSnapshot { timestamp: "2024-06-12T14:30:00Z".to_string(), total_value: 100_500.0, cash: 50_000.0, positions_value: 50_500.0 }];
+        let pnl = vec![PnlSnapshot { timestamp: "2024-06-12T14:30:00Z".to_string(), unrealized: BTreeMap::from([("AAPL".to_string(), 12.5)]) }];
+
+        for format in [HistoryFormat::Jsonl, HistoryFormat::Bincode] {
+            let store = temp_store(format);
+            store.save_trades(&trades).unwrap();
+            store.save_snapshots(&snapshots).unwrap();
+            store.save_pnl(&pnl).unwrap();
+            assert_eq!(json(&store.load_trades()), json(&trades), "{:?}", format);
+            assert_eq!(json(&store.load_snapshots()), json(&snapshots), "{:?}", format);
+            assert_eq!(json(&store.load_pnl()), json(&pnl), "{:?}", format);
+            let _ = std::fs::remove_dir_all(&store.dir);
+        }
+
+        let off = temp_store(HistoryFormat::Off);
+        off.save_trades(&trades).unwrap();
+        assert!(off.load_trades().is_empty());
+        assert!(!off.dir.exists());
+    }
+
+    // Older layouts, written field by field the way bincode laid them out
+    type V1 = (String, String, String, String, f64, f64, f64, Option<f64>, bool);
+    type V2 = (V1, Option<f64>);
+    type V3 = (V2, Option<TradeReason>);
+
+    fn v1(t: &TradeRecord) -> V1 {
+        (t.id.clone(), t.timestamp.clone(), t.symbol.clone(), t.action.clone(), t.quantity, t.price, t.pnl, t.fill_price, t.slippage_flagged)
+    }
+
+    fn write_binary<R: Serialize>(store: &HistoryStore, version: u32, records: &[R]) {
+        std::fs::create_dir_all(&store.dir).unwrap();
+        let bytes = bincode::serialize(&Envelope { magic: BINARY_MAGIC, version, records }).unwrap();
+        std::fs::write(store.path("trades"), bytes).unwrap();
+    }
+
+    #[test]
+    fn older_binary_versions_upgrade_on_load() {
+        let t = trade("t-1");
+        let store = temp_store(HistoryFormat::Bincode);
+
+        write_binary(&store, 1, &[v1(&t)]);
+        let loaded = &store.load_trades()[0];
+        assert_eq!((loaded.id.as_str(), loaded.pnl, loaded.fill_price, loaded.slippage_flagged), ("t-1", 250.0, Some(61_010.0), true));
+        assert_eq!((loaded.slippage_pct, loaded.reason, loaded.asset_class.as_str()), (None, None, ""));
+
+        write_binary::<V2>(&store, 2, &[(v1(&t), Some(-0.02))]);
+        let loaded = &store.load_trades()[0];
+        assert_eq!((loaded.slippage_pct, loaded.reason), (Some(-0.02), None));
+
+        write_binary::<V3>(&store, 3, &[((v1(&t), Some(-0.02)), Some(TradeReason::ProfitTake))]);
+        let loaded = &store.load_trades()[0];
+        assert_eq!((loaded.slippage_pct, loaded.reason, loaded.asset_class.as_str()), (Some(-0.02), Some(TradeReason::ProfitTake), ""));
+
+        // Files from a newer build are left alone rather than misread
+        write_binary(&store, TRADES_VERSION + 1, &[v1(&t)]);
+        assert!(store.load_trades().is_empty());
+        let _ = std::fs::remove_dir_all(&store.dir);
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::engine::TradeRecord;
+use crate::symbols;
+
+// Quantities this small count as flat (float dust from partial sells)
+const FLAT: f64 = 1e-9;
+
+// When the current holding in `symbol` was opened according to the trade
+// history: the first buy since the tracked quantity was last flat. None if
+// the history shows nothing held.
+pub fn opened_at(trades: &[TradeRecord], symbol: &str) -> Option<DateTime<Utc>> {
+    let mut qty = 0.0;
+    let mut opened = None;
+    for trade in trades.iter().filter(|t| symbols::same_symbol(&t.symbol, symbol)) {
+        if trade.action == "BUY" {
+            if qty <= FLAT {
+                opened = DateTime::parse_from_rfc3339(&trade.timestamp).ok().map(|ts| ts.with_timezone(&Utc));
+            }
+            qty += trade.quantity;
+        } else {
+            qty = (qty - trade.quantity).max(0.0);
+            if qty <= FLAT {
+                opened = None;
+            }
+        }
+    }
+    opened
+}
+
+// How much of the minimum hold is left at `now`, None once it has elapsed
+pub fn hold_remaining(opened: DateTime<Utc>, min_hold: Duration, now: DateTime<Utc>) -> Option<Duration> {
+    let left = min_hold - now.signed_duration_since(opened);
+    (left > Duration::zero()).then_some(left)
+}
@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response};
+use std::time::Instant;
+use tracing::info;
+
+// Header values that must never reach the logs
+const SENSITIVE_HEADERS: &[&str] = &["apca-api-key-id", "apca-api-secret-key", "authorization"];
+
+// Drop-in for `RequestBuilder::send` that, when enabled (ALPACA_DEBUG_REQUESTS),
+// logs the outgoing request with credentials redacted plus status and latency
+#[async_trait]
+pub trait SendLogged {
+    async fn send_logged(self, enabled: bool) -> reqwest::Result<Response>;
+}
+
+#[async_trait]
+impl SendLogged for RequestBuilder {
+    async fn send_logged(self, enabled: bool) -> reqwest::Result<Response> {
+        #[cfg(feature = "fault-injection")]
+        {
+            let url = self.try_clone().and_then(|r| r.build().ok()).map(|r| r.url().to_string()).unwrap_or_default();
+            if let Some(response) = crate::fault::inject(&url).await {
+                return Ok(response);
+            }
+        }
+
+        if !enabled {
+            return self.send().await;
+        }
+
+        let (client, request) = self.build_split();
+        let request = request?;
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let headers = describe_headers(request.headers());
+        info!("🛰️  → {} {} [{}]", method, url, headers);
+
+        let start = Instant::now();
+        let result = client.execute(request).await;
+        let elapsed_ms = start.elapsed().as_millis();
+        match &result {
+            Ok(response) => info!("🛰️  ← {} {} {} ({}ms)", method, url, response.status(), elapsed_ms),
+            Err(e) => info!("🛰️  ← {} {} failed: {} ({}ms)", method, url, e, elapsed_ms),
+        }
+        result
+    }
+}
+
+// "name: value" pairs for the log line, credentials redacted
+fn describe_headers(headers: &HeaderMap) -> String {
+    headers.iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                "<redacted>"
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use axum::routing::get;
+    use axum::Router;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    // Log output captured while `send` runs
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn text(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+        }
+    }
+
+    async fn send(url: &str, enabled: bool) -> String {
+        let logs = Captured::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        reqwest::Client::new()
+            .get(url)
+            .header("APCA-API-KEY-ID", "key-id-123")
+            .header("APCA-API-SECRET-KEY", "secret-456")
+            .header("Accept", "application/json")
+            .send_logged(enabled)
+            .await
+            .unwrap();
+        logs.text()
+    }
+
+    #[tokio::test]
+    async fn logs_requests_with_credentials_redacted() {
+        let host = test_support::serve(Router::new().route("/v2/account", get(|| async { "{}" }))).await;
+        let logs = send(&format!("{}/v2/account", host), true).await;
+
+        assert!(logs.contains("GET"));
+        assert!(logs.contains("/v2/account"));
+        assert!(logs.contains("apca-api-key-id: <redacted>"));
+        assert!(logs.contains("accept: application/json"));
+        assert!(logs.contains("200 OK"));
+        assert!(!logs.contains("key-id-123"));
+        assert!(!logs.contains("secret-456"));
+    }
+
+    #[tokio::test]
+    async fn disabled_logging_is_silent() {
+        let host = test_support::serve(Router::new().route("/v2/account", get(|| async { "{}" }))).await;
+        assert_eq!(send(&format!("{}/v2/account", host), false).await, "");
+    }
+}
@@ -0,0 +1,62 @@
+// LadyBug trading engine as a library. The `ladybug-engine` binary is a thin
+// wrapper that builds an `Engine` from the environment and serves `api::router`.
+
+mod activity;
+mod alpaca;
+mod api;
+mod approval;
+mod backoff;
+mod config;
+mod config_history;
+mod correlation;
+mod crypto;
+mod decision;
+mod engine;
+mod engine_state;
+mod entitlement;
+mod external;
+#[cfg(feature = "fault-injection")]
+mod fault;
+#[cfg(feature = "frozen-prices")]
+mod frozen;
+mod gap;
+mod halt;
+mod history_store;
+mod holding;
+mod http_log;
+mod market_stream;
+mod metrics;
+mod money;
+mod news;
+mod notes;
+mod pdt;
+mod portfolio;
+mod profile;
+mod promotion;
+mod recorder;
+mod replay;
+mod rotation;
+mod screener;
+mod session;
+mod sizing;
+mod slippage;
+mod statsd;
+mod stops;
+mod strategy;
+mod symbols;
+mod tax;
+mod technical;
+#[cfg(test)]
+mod test_support;
+mod throttle;
+mod volatility;
+
+// Internal modules stay private; this is the whole embedding surface
+pub use alpaca::Bar;
+pub use api::router;
+pub use config::Config;
+pub use decision::{decide_action, signal_zone, Thresholds, TradeAction};
+pub use engine::{run_crypto_cycle, run_stock_cycle, AppState, CycleSummary, Engine, TradingMode};
+pub use replay::{replay_file, ReplayReport};
+pub use strategy::{Strategy, StrategyContext};
+pub use technical::SignalExplanation;
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::{interval, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info, warn};
+
+use crate::engine::TradingMode;
+use crate::symbols;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LivePrice {
+    pub price: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Latest streamed price per symbol. Keys are stored without the "/" so that
+// "BTC/USD" (stream format) and "BTCUSD" (positions format) resolve the same.
+#[derive(Clone, Default)]
+pub struct LivePriceCache {
+    prices: Arc<DashMap<String, LivePrice>>,
+}
+
+impl LivePriceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(symbol: &str) -> String {
+        symbols::normalize(symbol)
+    }
+
+    pub fn update(&self, symbol: &str, price: f64) {
+        if price > 0.0 {
+            self.prices.insert(Self::key(symbol), LivePrice { price, updated_at: Utc::now() });
+        }
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<LivePrice> {
+        self.prices.get(&Self::key(symbol)).map(|p| *p)
+    }
+
+    // Returns the price only if it was updated within max_age_secs
+    pub fn get_fresh(&self, symbol: &str, max_age_secs: u64) -> Option<f64> {
+        self.get(symbol).and_then(|p| {
+            let age = Utc::now().signed_duration_since(p.updated_at).num_seconds();
+            (age <= max_age_secs as i64).then_some(p.price)
+        })
+    }
+
+    // Applies one Alpaca stream frame (a JSON array of events) to the cache.
+    // Trades ("t") use the trade price, quotes ("q") the bid/ask midpoint.
+    // Returns how many prices were updated.
+    pub fn apply_message(&self, text: &str) -> usize {
+        let events: Vec<serde_json::Value> = match serde_json::from_str(text) {
+            Ok(serde_json::Value::Array(events)) => events,
+            Ok(other) => vec![other],
+            Err(e) => {
+                warn!("📡 Unparseable market stream frame: {}", e);
+                return 0;
+            }
+        };
+
+        let mut updated = 0;
+        for event in &events {
+            let symbol = event["S"].as_str().unwrap_or_default();
+            match event["T"].as_str() {
+                Some("t") => {
+                    if let Some(price) = event["p"].as_f64() {
+                        self.update(symbol, price);
+                        updated += 1;
+                    }
+                }
+                Some("q") => {
+                    let bid = event["bp"].as_f64().unwrap_or(0.0);
+                    let ask = event["ap"].as_f64().unwrap_or(0.0);
+                    // One-sided quotes are unreliable, only use a full book
+                    if bid > 0.0 && ask > 0.0 {
+                        self.update(symbol, (bid + ask) / 2.0);
+                        updated += 1;
+                    }
+                }
+                Some("error") => warn!("📡 Market stream error: {}", event),
+                Some("subscription") => info!("📡 Market stream subscriptions: {}", event),
+                _ => debug!("📡 Market stream message: {}", event),
+            }
+        }
+        updated
+    }
+}
+
+pub struct MarketStream {
+    name: &'static str,
+    url: String,
+    api_key: String,
+    api_secret: String,
+    cache: LivePriceCache,
+}
+
+impl MarketStream {
+    pub fn stocks(feed: &str, api_key: String, api_secret: String, cache: LivePriceCache) -> Self {
+        Self {
+            name: "stocks",
+            url: format!("wss://stream.data.alpaca.markets/v2/{}", feed),
+            api_key,
+            api_secret,
+            cache,
+        }
+    }
+
+    pub fn crypto(api_key: String, api_secret: String, cache: LivePriceCache) -> Self {
+        Self {
+            name: "crypto",
+            url: "wss://stream.data.alpaca.markets/v1beta3/crypto/us".to_string(),
+            api_key,
+            api_secret,
+            cache,
+        }
+    }
+
+    // Runs forever, reconnecting with exponential backoff. Subscriptions follow
+    // the active trading mode, so a mode switch re-subscribes automatically.
+    pub async fn run(&self, mode: Arc<RwLock<TradingMode>>, select: fn(&TradingMode) -> Vec<&'static str>) {
+        info!("📡 Starting {} market data stream", self.name);
+        let mut backoff = 1;
+
+        loop {
+            match self.session(&mode, select, &mut backoff).await {
+                Ok(()) => warn!("📡 {} market stream closed, reconnecting in {}s", self.name, backoff),
+                Err(e) => warn!("📡 {} market stream error: {} - reconnecting in {}s", self.name, e, backoff),
+            }
+            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            backoff = (backoff * 2).min(60);
+        }
+    }
+
+    async fn session(
+        &self,
+        mode: &Arc<RwLock<TradingMode>>,
+        select: fn(&TradingMode) -> Vec<&'static str>,
+        backoff: &mut u64,
+    ) -> Result<()> {
+        let (ws, _) = connect_async(self.url.as_str())
+            .await
+            .context("Failed to connect to market stream")?;
+        let (mut write, mut read) = ws.split();
+
+        let auth = json!({ "action": "auth", "key": self.api_key, "secret": self.api_secret });
+        write.send(Message::Text(auth.to_string())).await?;
+
+        // Wait for the auth acknowledgement before subscribing
+        loop {
+            let text = match read.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+                None => anyhow::bail!("Stream closed during authentication"),
+            };
+            if text.contains("\"authenticated\"") {
+                break;
+            }
+            if text.contains("\"error\"") {
+                anyhow::bail!("Authentication rejected: {}", text);
+            }
+        }
+        info!("✅ {} market stream authenticated", self.name);
+        *backoff = 1;
+
+        let mut subscribed: HashSet<String> = HashSet::new();
+        let mut resync = interval(Duration::from_secs(30));
+
+        loop {
+            tokio::select! {
+                _ = resync.tick() => {
+                    let desired: HashSet<String> = select(&*mode.read().await)
+                        .into_iter()
+                        .map(|s| s.to_string())
+                        .collect();
+                    let added: Vec<&String> = desired.difference(&subscribed).collect();
+                    let removed: Vec<&String> = subscribed.difference(&desired).collect();
+
+                    if !removed.is_empty() {
+                        let msg = json!({ "action": "unsubscribe", "trades": removed, "quotes": removed });
+                        write.send(Message::Text(msg.to_string())).await?;
+                    }
+                    if !added.is_empty() {
+                        info!("📡 {} stream subscribing to {} symbols", self.name, added.len());
+                        let msg = json!({ "action": "subscribe", "trades": added, "quotes": added });
+                        write.send(Message::Text(msg.to_string())).await?;
+                    }
+                    subscribed = desired;
+                }
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => {
+                            self.cache.apply_message(&text);
+                        }
+                        Some(Ok(Message::Ping(data))) => write.send(Message::Pong(data)).await?,
+                        Some(Ok(Message::Close(_))) | None => return Ok(()),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trade_updates_price() {
+        let cache = LivePriceCache::new();
+        let n = cache.apply_message(r#"[{"T":"t","S":"AAPL","p":187.25,"s":100}]"#);
+        assert_eq!(n, 1);
+        assert_eq!(cache.get("AAPL").unwrap().price, 187.25);
+    }
+
+    #[test]
+    fn two_sided_quote_uses_midpoint() {
+        let cache = LivePriceCache::new();
+        let n = cache.apply_message(r#"[{"T":"q","S":"MSFT","bp":400.0,"ap":401.0}]"#);
+        assert_eq!(n, 1);
+        assert_eq!(cache.get("MSFT").unwrap().price, 400.5);
+    }
+
+    #[test]
+    fn one_sided_quote_is_ignored() {
+        let cache = LivePriceCache::new();
+        cache.update("MSFT", 399.0);
+        let n = cache.apply_message(r#"[{"T":"q","S":"MSFT","bp":0,"ap":401.0},{"T":"q","S":"MSFT","bp":400.0}]"#);
+        assert_eq!(n, 0);
+        assert_eq!(cache.get("MSFT").unwrap().price, 399.0);
+    }
+
+    #[test]
+    fn slash_and_plain_crypto_keys_resolve_the_same() {
+        let cache = LivePriceCache::new();
+        cache.apply_message(r#"[{"T":"t","S":"BTC/USD","p":65000.0}]"#);
+        assert_eq!(cache.get("BTCUSD").unwrap().price, 65000.0);
+        assert_eq!(cache.get_fresh("BTC/USD", 5), Some(65000.0));
+    }
+
+    #[test]
+    fn garbage_frame_updates_nothing() {
+        let cache = LivePriceCache::new();
+        assert_eq!(cache.apply_message("not json"), 0);
+        assert!(cache.get("AAPL").is_none());
+    }
+}
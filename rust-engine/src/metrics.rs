@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Minimal Prometheus-compatible histogram registry. Series are keyed by
+// metric name + rendered label set and created on first observation.
+pub struct Metrics {
+    enabled: bool,
+    buckets: Vec<f64>,
+    histograms: Mutex<BTreeMap<(&'static str, String), Histogram>>,
+}
+
+#[derive(Clone)]
+struct Histogram {
+    counts: Vec<u64>,  // Non-cumulative per-bucket counts
+    sum: f64,
+    count: u64,
+}
+
+pub const SYMBOL_PROCESSING_SECONDS: &str = "ladybug_symbol_processing_seconds";
+pub const CYCLE_DURATION_SECONDS: &str = "ladybug_cycle_duration_seconds";
+pub const ALPACA_REQUEST_SECONDS: &str = "ladybug_alpaca_request_seconds";
+pub const ALPACA_QUOTA_REMAINING: &str = "ladybug_alpaca_quota_remaining";
+pub const ALPACA_QUOTA_LIMIT: &str = "ladybug_alpaca_quota_limit";
+
+fn help(name: &str) -> &'static str {
+    match name {
+        SYMBOL_PROCESSING_SECONDS => "Time to analyze (and possibly trade) one symbol",
+        CYCLE_DURATION_SECONDS => "Time for one full analysis cycle",
+        ALPACA_REQUEST_SECONDS => "Alpaca HTTP request latency by endpoint",
+        ALPACA_QUOTA_REMAINING => "Requests left in the current window, as last reported by Alpaca",
+        ALPACA_QUOTA_LIMIT => "Requests allowed per window, as last reported by Alpaca",
+        _ => "",
+    }
+}
+
+impl Metrics {
+    pub fn new(enabled: bool, mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|b| b.is_finite());
+        buckets.sort_by(|a, b| a.total_cmp(b));
+        buckets.dedup();
+        Self {
+            enabled,
+            buckets,
+            histograms: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn observe(&self, name: &'static str, labels: &[(&str, &str)], secs: f64) {
+        if !self.enabled {
+            return;
+        }
+        let labels = labels.iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        let histogram = histograms.entry((name, labels)).or_insert_with(|| Histogram {
+            counts: vec![0; self.buckets.len()],
+            sum: 0.0,
+            count: 0,
+        });
+        if let Some(i) = self.buckets.iter().position(|b| secs <= *b) {
+            histogram.counts[i] += 1;
+        }
+        histogram.sum += secs;
+        histogram.count += 1;
+    }
+
+    pub fn observe_since(&self, name: &'static str, labels: &[(&str, &str)], start: Instant) {
+        self.observe(name, labels, start.elapsed().as_secs_f64());
+    }
+
+    // Prometheus text exposition format (version 0.0.4)
+    pub fn render(&self) -> String {
+        let histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut out = String::new();
+        let mut last_name = "";
+
+        for ((name, labels), histogram) in &histograms {
+            if *name != last_name {
+                let _ = writeln!(out, "# HELP {} {}", name, help(name));
+                let _ = writeln!(out, "# TYPE {} histogram", name);
+                last_name = name;
+            }
+            let sep = if labels.is_empty() { "" } else { "," };
+
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&histogram.counts) {
+                cumulative += count;
+                let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, sep, bound, cumulative);
+            }
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, sep, histogram.count);
+            let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+            let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+        }
+        out
+    }
+
+    // StatsD gauge lines: `<name>.count` and `<name>.sum` per series, with
+    // the Prometheus labels turned into DogStatsD tags (`|#endpoint:bars`)
+    pub fn statsd_lines(&self) -> Vec<String> {
+        let histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut lines = Vec::new();
+        for ((name, labels), histogram) in &histograms {
+            let tags = if labels.is_empty() {
+                String::new()
+            } else {
+                let tags = labels.split("\",")
+                    .map(|pair| pair.replacen("=\"", ":", 1).trim_end_matches('"').to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("|#{}", tags)
+            };
+            lines.push(format!("{}.count:{}|g{}", name, histogram.count, tags));
+            lines.push(format!("{}.sum:{}|g{}", name, histogram.sum, tags));
+        }
+        lines
+    }
+}
+
+// A gauge in the same text format, one sample per `category` label
+pub fn render_gauge(name: &'static str, samples: &[(&str, f64)]) -> String {
+    if samples.is_empty() {
+        return String::new();
+    }
+    let mut out = String::new();
+    let _ = writeln!(out, "# HELP {} {}", name, help(name));
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (category, value) in samples {
+        let _ = writeln!(out, "{}{{category=\"{}\"}} {}", name, category, value);
+    }
+    out
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new(false, vec![])
+    }
+}
+
+// Performance stats over closing trades' realized P&L, oldest first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TradeStats {
+    pub closed_trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub win_rate: f64,                // 0-1
+    pub current_streak: i64,          // +N for N wins in a row, -N for losses, 0 after a breakeven
+    pub average_win: f64,
+    pub average_loss: f64,            // Negative
+    pub profit_factor: Option<f64>,   // Gross profit / gross loss, None with no losses
+    pub expectancy: f64,              // Expected P&L per closed trade
+}
+
+pub fn trade_stats(pnls: &[f64]) -> TradeStats {
+    if pnls.is_empty() {
+        return TradeStats::default();
+    }
+    let wins: Vec<f64> = pnls.iter().copied().filter(|p| *p > 0.0).collect();
+    let losses: Vec<f64> = pnls.iter().copied().filter(|p| *p < 0.0).collect();
+    let gross_profit: f64 = wins.iter().sum();
+    let gross_loss: f64 = losses.iter().sum();
+    let average = |v: &[f64], total: f64| if v.is_empty() { 0.0 } else { total / v.len() as f64 };
+
+    let win_rate = wins.len() as f64 / pnls.len() as f64;
+    let loss_rate = losses.len() as f64 / pnls.len() as f64;
+    let average_win = average(&wins, gross_profit);
+    let average_loss = average(&losses, gross_loss);
+
+    TradeStats {
+        closed_trades: pnls.len(),
+        wins: wins.len(),
+        losses: losses.len(),
+        win_rate,
+        current_streak: current_streak(pnls),
+        average_win,
+        average_loss,
+        profit_factor: (gross_loss < 0.0).then(|| gross_profit / -gross_loss),
+        expectancy: win_rate * average_win + loss_rate * average_loss,
+    }
+}
+
+// Realized P&L totals for one group of closed trades (by reason, symbol, ...)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupStats {
+    pub trades: usize,
+    pub realized_pnl: f64,
+    pub wins: usize,
+    pub win_rate: f64,  // 0-1
+}
+
+// Closed-trade P&L grouped by key, keys sorted
+pub fn group_stats<K: Into<String>>(pnls: impl IntoIterator<Item = (K, f64)>) -> BTreeMap<String, GroupStats> {
+    let mut groups: BTreeMap<String, GroupStats> = BTreeMap::new();
+    for (key, pnl) in pnls {
+        let group = groups.entry(key.into()).or_default();
+        group.trades += 1;
+        group.realized_pnl += pnl;
+        if pnl > 0.0 {
+            group.wins += 1;
+        }
+    }
+    for group in groups.values_mut() {
+        group.win_rate = group.wins as f64 / group.trades as f64;
+    }
+    groups
+}
+
+fn current_streak(pnls: &[f64]) -> i64 {
+    let Some(last) = pnls.last() else {
+        return 0;
+    };
+    let sign = if *last > 0.0 { 1 } else if *last < 0.0 { -1 } else { return 0 };
+    let run = pnls.iter().rev()
+        .take_while(|p| (**p > 0.0 && sign > 0) || (**p < 0.0 && sign < 0))
+        .count() as i64;
+    sign * run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observations_render_as_cumulative_histogram() {
+        let metrics = Metrics::new(true, vec![1.0, 0.1, 0.5]);
+        metrics.observe(SYMBOL_PROCESSING_SECONDS, &[("asset_class", "stock")], 0.05);
+        metrics.observe(SYMBOL_PROCESSING_SECONDS, &[("asset_class", "stock")], 0.3);
+        metrics.observe(SYMBOL_PROCESSING_SECONDS, &[("asset_class", "stock")], 7.0);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE ladybug_symbol_processing_seconds histogram"));
+        assert!(text.contains("ladybug_symbol_processing_seconds_bucket{asset_class=\"stock\",le=\"0.1\"} 1"));
+        assert!(text.contains("ladybug_symbol_processing_seconds_bucket{asset_class=\"stock\",le=\"0.5\"} 2"));
+        assert!(text.contains("ladybug_symbol_processing_seconds_bucket{asset_class=\"stock\",le=\"1\"} 2"));
+        assert!(text.contains("ladybug_symbol_processing_seconds_bucket{asset_class=\"stock\",le=\"+Inf\"} 3"));
+        assert!(text.contains("ladybug_symbol_processing_seconds_count{asset_class=\"stock\"} 3"));
+    }
+
+    #[test]
+    fn each_label_set_is_its_own_series() {
+        let metrics = Metrics::new(true, vec![1.0]);
+        metrics.observe(ALPACA_REQUEST_SECONDS, &[("endpoint", "bars")], 0.2);
+        metrics.observe(ALPACA_REQUEST_SECONDS, &[("endpoint", "orders")], 0.2);
+
+        let text = metrics.render();
+        assert_eq!(text.matches("# TYPE ladybug_alpaca_request_seconds").count(), 1);
+        assert!(text.contains("ladybug_alpaca_request_seconds_count{endpoint=\"bars\"} 1"));
+        assert!(text.contains("ladybug_alpaca_request_seconds_count{endpoint=\"orders\"} 1"));
+    }
+
+    #[test]
+    fn disabled_registry_records_nothing() {
+        let metrics = Metrics::new(false, vec![1.0]);
+        metrics.observe(CYCLE_DURATION_SECONDS, &[], 0.2);
+        assert!(metrics.render().is_empty());
+    }
+
+    #[test]
+    fn trade_stats_summarize_closed_pnl() {
+        let stats = trade_stats(&[10.0, -5.0, 20.0, -5.0, -10.0]);
+        assert_eq!((stats.closed_trades, stats.wins, stats.losses), (5, 2, 3));
+        assert!((stats.win_rate - 0.4).abs() < 1e-9);
+        assert_eq!(stats.current_streak, -2);
+        assert!((stats.average_win - 15.0).abs() < 1e-9);
+        assert!((stats.average_loss + 20.0 / 3.0).abs() < 1e-9);
+        assert!((stats.profit_factor.unwrap() - 1.5).abs() < 1e-9);
+        // Same as the mean P&L per trade
+        assert!((stats.expectancy - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn streaks_and_edge_cases() {
+        assert_eq!(trade_stats(&[-1.0, 2.0, 3.0, 4.0]).current_streak, 3);
+        assert_eq!(trade_stats(&[2.0, 0.0]).current_streak, 0);
+        assert_eq!(trade_stats(&[2.0, 3.0]).profit_factor, None);
+        assert_eq!(trade_stats(&[]).closed_trades, 0);
+    }
+}
@@ -0,0 +1,36 @@
+// Monetary amounts leave the API rounded to MONEY_DECIMALS places, as JSON
+// numbers, whatever shape they came in (Alpaca strings, computed floats).
+// Per-unit prices are left alone: a 2-place DOGE or SHIB price is useless.
+
+pub fn round(value: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    let rounded = (value * factor).round() / factor;
+    // Keep -0.0 out of the JSON
+    if rounded == 0.0 { 0.0 } else { rounded }
+}
+
+// For Alpaca's string amounts; unparseable values become 0
+pub fn parse_round(value: &str, decimals: u32) -> f64 {
+    round(value.parse().unwrap_or(0.0), decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_amounts_to_the_configured_precision() {
+        assert_eq!(round(1234.5678, 2), 1234.57);
+        assert_eq!(round(0.1 + 0.2, 2), 0.3);
+        assert_eq!(round(-12.344, 2), -12.34);
+        assert_eq!(round(1234.5678, 0), 1235.0);
+        assert_eq!(round(0.123456, 4), 0.1235);
+        assert!(round(-0.001, 2).is_sign_positive());
+    }
+
+    #[test]
+    fn parses_alpaca_string_amounts() {
+        assert_eq!(parse_round("50123.456", 2), 50123.46);
+        assert_eq!(parse_round("n/a", 2), 0.0);
+    }
+}
@@ -1,30 +1,178 @@
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use tokio::time::{interval, Duration};
 use tracing::{info, error, warn};
 use serde::{Deserialize, Serialize};
 
+use crate::recorder::EventRecorder;
+use crate::replay::ReplayEvent;
+use crate::symbols;
+
 #[derive(Clone)]
 pub struct NewsAggregator {
     sentiment_cache: Arc<DashMap<String, f64>>,
     client: reqwest::Client,
     sentiment_service_url: String,
+    half_life_mins: f64,
+    articles: Arc<DashMap<String, Vec<ScoredArticle>>>,  // Per symbol, oldest first
+    seen: Arc<DashMap<(String, String), DateTime<Utc>>>,  // (symbol, article id) -> first seen
+    dedup_ttl_mins: i64,
+    recorder: EventRecorder,
+    debounce_ms: u64,
+    pending: Arc<DashMap<String, u32>>,  // Symbol -> updates waiting on the debounce window
+}
+
+// Articles kept per symbol for the weighted average
+const MAX_ARTICLES_PER_SYMBOL: usize = 10;
+
+#[derive(Debug, Clone)]
+struct ScoredArticle {
+    score: f64,
+    published: Option<DateTime<Utc>>,
+}
+
+// Exponential decay by article age: an article `half_life_mins` old counts
+// half as much as a brand new one. Unknown timestamps count as fresh.
+pub fn recency_weight(published: Option<DateTime<Utc>>, now: DateTime<Utc>, half_life_mins: f64) -> f64 {
+    match published {
+        Some(published) if half_life_mins > 0.0 => {
+            let age_mins = (now - published).num_seconds().max(0) as f64 / 60.0;
+            0.5f64.powf(age_mins / half_life_mins)
+        }
+        _ => 1.0,
+    }
+}
+
+// Weighted mean of (score, published) pairs, 0.0 when there's nothing to weigh
+pub fn weighted_sentiment(scored: &[(f64, Option<DateTime<Utc>>)], now: DateTime<Utc>, half_life_mins: f64) -> f64 {
+    let (sum, weights) = scored.iter().fold((0.0, 0.0), |(sum, weights), (score, published)| {
+        let w = recency_weight(*published, now, half_life_mins);
+        (sum + score * w, weights + w)
+    });
+    if weights > 0.0 { sum / weights } else { 0.0 }
 }
 
 #[derive(Debug, Deserialize)]
+#[allow(dead_code)]
 struct SentimentResponse {
     score: f64,
     sentiment: String,
     confidence: f64,
 }
 
+impl Default for NewsAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NewsAggregator {
     pub fn new() -> Self {
         Self {
             sentiment_cache: Arc::new(DashMap::new()),
             client: reqwest::Client::new(),
             sentiment_service_url: "http://localhost:5000".to_string(),
+            half_life_mins: 0.0,
+            articles: Arc::new(DashMap::new()),
+            seen: Arc::new(DashMap::new()),
+            dedup_ttl_mins: 1440,
+            recorder: EventRecorder::disabled(),
+            debounce_ms: 0,
+            pending: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn with_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = recorder;
+        self
+    }
+
+    // How long an article id is remembered for deduplication
+    pub fn with_dedup_ttl(mut self, ttl_mins: i64) -> Self {
+        self.dedup_ttl_mins = ttl_mins;
+        self
+    }
+
+    // Coalesce a symbol's sentiment updates over this window; 0 writes each
+    // one immediately (what replay wants)
+    pub fn with_debounce(mut self, debounce_ms: u64) -> Self {
+        self.debounce_ms = debounce_ms;
+        self
+    }
+
+    fn is_seen(&self, symbol: &str, article_id: &str) -> bool {
+        self.seen.contains_key(&(symbol.to_string(), article_id.to_string()))
+    }
+
+    // Every delivery path (the RSS poller, any push feed) goes through here so
+    // an article moves a symbol's sentiment once, however often it arrives.
+    // Returns false for a duplicate.
+    pub fn ingest(&self, symbol: &str, article_id: &str, score: f64, published: Option<DateTime<Utc>>) -> bool {
+        let now = Utc::now();
+        let ttl = chrono::Duration::minutes(self.dedup_ttl_mins);
+        self.seen.retain(|_, first_seen| now - *first_seen < ttl);
+        
+        let key = (symbol.to_string(), article_id.to_string());
+        if self.seen.contains_key(&key) {
+            return false;
+        }
+        self.seen.insert(key, now);
+        self.recorder.record(ReplayEvent::News { symbol: symbol.to_string(), id: article_id.to_string(), score });
+        
+        let mut articles = self.articles.entry(symbol.to_string()).or_default();
+        articles.push(ScoredArticle { score, published });
+        if articles.len() > MAX_ARTICLES_PER_SYMBOL {
+            let excess = articles.len() - MAX_ARTICLES_PER_SYMBOL;
+            articles.drain(..excess);
         }
+        drop(articles);
+        
+        self.schedule_refresh(symbol);
+        true
+    }
+
+    // Articles are already stored, so the blended score is the same however
+    // many arrived; only the first update in a window schedules the write.
+    fn schedule_refresh(&self, symbol: &str) {
+        if self.debounce_ms == 0 {
+            self.refresh_sentiment(symbol);
+            return;
+        }
+        let mut count = self.pending.entry(symbol.to_string()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            return;
+        }
+        drop(count);
+        
+        let this = self.clone();
+        let symbol = symbol.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(this.debounce_ms)).await;
+            let updates = this.pending.remove(&symbol).map_or(0, |(_, n)| n);
+            if let Some(sentiment) = this.refresh_sentiment(&symbol) {
+                info!("🤖 {} - Sentiment: {:.3} (recency weighted, {} new)", symbol, sentiment, updates);
+            }
+        });
+    }
+
+    // Recomputes the cached score so older articles keep decaying
+    fn refresh_sentiment(&self, symbol: &str) -> Option<f64> {
+        let scored: Vec<(f64, Option<DateTime<Utc>>)> = self.articles
+            .get(symbol)?
+            .iter()
+            .map(|a| (a.score, a.published))
+            .collect();
+        let sentiment = weighted_sentiment(&scored, Utc::now(), self.half_life_mins);
+        self.sentiment_cache.insert(symbol.to_string(), sentiment);
+        Some(sentiment)
+    }
+
+    // 0 = plain average of the headlines
+    pub fn with_half_life(mut self, half_life_mins: f64) -> Self {
+        self.half_life_mins = half_life_mins;
+        self
     }
 
     pub async fn start(&self) {
@@ -98,30 +246,45 @@ impl NewsAggregator {
             return Ok(());
         }
         
-        // Get the latest 5 headlines
-        let headlines: Vec<String> = channel.items()
+        // Latest 5 headlines we haven't scored yet. The guid identifies an
+        // item across polls; link or title stand in when a feed omits it.
+        let fresh: Vec<(String, String, Option<DateTime<Utc>>)> = channel.items()
             .iter()
             .take(5)
-            .filter_map(|item| item.title().map(|t| t.to_string()))
+            .filter_map(|item| {
+                let title = item.title()?.to_string();
+                let id = item.guid().map(|g| g.value().to_string())
+                    .or_else(|| item.link().map(|l| l.to_string()))
+                    .unwrap_or_else(|| title.clone());
+                let published = item.pub_date()
+                    .and_then(|d| DateTime::parse_from_rfc2822(d).ok())
+                    .map(|d| d.with_timezone(&Utc));
+                Some((id, title, published))
+            })
+            .filter(|(id, _, _)| !self.is_seen(symbol, id))
             .collect();
         
-        if headlines.is_empty() {
+        if fresh.is_empty() {
+            // Nothing new, but let the existing scores decay
+            self.refresh_sentiment(symbol);
             return Ok(());
         }
         
-        info!("📰 {} - Found {} headlines", symbol, headlines.len());
+        info!("📰 {} - Found {} new headlines", symbol, fresh.len());
         
-        // Analyze sentiment of all headlines
+        // Analyze sentiment of the new headlines only
+        let headlines: Vec<String> = fresh.iter().map(|(_, title, _)| title.clone()).collect();
         let sentiments = self.analyze_batch_sentiment(&headlines).await?;
         
-        // Average the sentiment scores
-        if !sentiments.is_empty() {
-            let avg_sentiment: f64 = sentiments.iter().sum::<f64>() / sentiments.len() as f64;
-            
-            self.sentiment_cache.insert(symbol.to_string(), avg_sentiment);
-            
-            info!("🤖 {} - Sentiment: {:.3} (from {} headlines)", 
-                  symbol, avg_sentiment, sentiments.len());
+        for ((id, _, published), score) in fresh.into_iter().zip(sentiments) {
+            self.ingest(symbol, &id, score, published);
+        }
+        
+        // Debounced updates log when they land
+        if self.debounce_ms == 0 {
+            if let Some(sentiment) = self.refresh_sentiment(symbol) {
+                info!("🤖 {} - Sentiment: {:.3} (recency weighted)", symbol, sentiment);
+            }
         }
         
         Ok(())
@@ -164,6 +327,17 @@ impl NewsAggregator {
         Ok(scores)
     }
 
+    // None when nothing has been scored for the symbol, which get_sentiment
+    // can't tell apart from genuinely neutral news. Matches "BTCUSD" to "BTC/USD".
+    pub fn sentiment_for(&self, symbol: &str) -> Option<f64> {
+        if let Some(score) = self.sentiment_cache.get(symbol) {
+            return Some(*score);
+        }
+        self.sentiment_cache.iter()
+            .find(|s| symbols::same_symbol(s.key(), symbol))
+            .map(|s| *s.value())
+    }
+
     pub fn get_sentiment(&self, symbol: &str) -> f64 {
         self.sentiment_cache
             .get(symbol)
@@ -171,3 +345,57 @@ impl NewsAggregator {
             .unwrap_or(0.0) // Default to neutral if no data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_positive_article_outweighs_older_neutral_ones() {
+        let now = Utc::now();
+        let hours_ago = |h: i64| Some(now - chrono::Duration::hours(h));
+        let scored = vec![(0.0, hours_ago(3)), (0.0, hours_ago(4)), (0.0, hours_ago(5)), (0.9, Some(now))];
+
+        // Equal weights: the breaking headline is diluted four ways
+        assert!((weighted_sentiment(&scored, now, 0.0) - 0.225).abs() < 1e-9);
+        let weighted = weighted_sentiment(&scored, now, 60.0);
+        assert!(weighted > 0.7, "weighted {}", weighted);
+    }
+
+    #[test]
+    fn recency_weight_halves_every_half_life() {
+        let now = Utc::now();
+        assert_eq!(recency_weight(Some(now), now, 30.0), 1.0);
+        assert!((recency_weight(Some(now - chrono::Duration::minutes(60)), now, 30.0) - 0.25).abs() < 1e-9);
+        assert_eq!(recency_weight(None, now, 30.0), 1.0);
+    }
+
+    #[test]
+    fn duplicate_article_moves_sentiment_once() {
+        let news = NewsAggregator::new();
+        assert!(news.ingest("AAPL", "article-1", 0.8, None));
+        assert!(news.is_seen("AAPL", "article-1"));
+        // The same story arriving again, e.g. from another feed
+        assert!(!news.ingest("AAPL", "article-1", 0.8, None));
+        assert!(news.ingest("AAPL", "article-2", 0.0, None));
+
+        assert!((news.get_sentiment("AAPL") - 0.4).abs() < 1e-9);
+        // Ids are per symbol
+        assert!(news.ingest("MSFT", "article-1", 0.8, None));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_of_ten_articles_is_one_sentiment_write() {
+        let news = NewsAggregator::new().with_debounce(500);
+        for i in 0..10 {
+            assert!(news.ingest("AAPL", &format!("article-{}", i), if i < 5 { 0.8 } else { 0.2 }, None));
+        }
+        // All ten are waiting on a single scheduled write
+        assert_eq!(news.sentiment_for("AAPL"), None);
+        assert_eq!(*news.pending.get("AAPL").unwrap(), 10);
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!((news.sentiment_for("AAPL").unwrap() - 0.5).abs() < 1e-9);
+        assert!(news.pending.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::symbols;
+
+// Exempts a position from automatic exits (profit taking, signal sells)
+pub const NO_AUTO_SELL: &str = "no-auto-sell";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionNote {
+    pub note: String,
+    pub tags: Vec<String>,
+    pub updated_at: String,
+}
+
+// Operator annotations per symbol, saved to a JSON file on every change so
+// they survive restarts. Keys have the "/" stripped like the live price cache.
+#[derive(Clone)]
+pub struct NoteStore {
+    path: PathBuf,
+    notes: Arc<DashMap<String, PositionNote>>,
+}
+
+impl NoteStore {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let notes: BTreeMap<String, PositionNote> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            notes: Arc::new(notes.into_iter().collect()),
+        }
+    }
+
+    fn key(symbol: &str) -> String {
+        symbols::normalize(symbol)
+    }
+
+    // Empty note and no tags removes the entry
+    pub fn set(&self, symbol: &str, note: String, tags: Vec<String>) -> Option<PositionNote> {
+        let tags: Vec<String> = tags.into_iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let entry = if note.trim().is_empty() && tags.is_empty() {
+            self.notes.remove(&Self::key(symbol));
+            None
+        } else {
+            let entry = PositionNote { note, tags, updated_at: Utc::now().to_rfc3339() };
+            self.notes.insert(Self::key(symbol), entry.clone());
+            Some(entry)
+        };
+        self.save();
+        entry
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<PositionNote> {
+        self.notes.get(&Self::key(symbol)).map(|n| n.clone())
+    }
+
+    pub fn has_tag(&self, symbol: &str, tag: &str) -> bool {
+        self.notes.get(&Self::key(symbol)).is_some_and(|n| n.tags.iter().any(|t| t == tag))
+    }
+
+    // Swaps in a whole set of notes (a state import) and saves it
+    pub fn replace(&self, notes: BTreeMap<String, PositionNote>) {
+        self.notes.clear();
+        for (symbol, note) in notes {
+            self.notes.insert(symbol, note);
+        }
+        self.save();
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, PositionNote> {
+        self.notes.iter().map(|n| (n.key().clone(), n.value().clone())).collect()
+    }
+
+    fn save(&self) {
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let result = serde_json::to_string_pretty(&self.snapshot())
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std::fs::write(&self.path, json).map_err(anyhow::Error::from));
+        if let Err(e) = result {
+            warn!("⚠️  Failed to save position notes to {}: {}", self.path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use std::collections::HashSet;
+
+use crate::engine::TradeRecord;
+use crate::symbols;
+
+// FINRA looks at day trades over a rolling five business days
+pub const WINDOW_BUSINESS_DAYS: u32 = 5;
+
+pub fn ny_date(timestamp: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&chrono_tz::America::New_York).date_naive())
+}
+
+pub fn today(now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&chrono_tz::America::New_York).date_naive()
+}
+
+// First day of the window ending `today`, counting back over weekdays
+// (exchange holidays aren't known here, which only makes the window longer)
+pub fn window_start(today: NaiveDate) -> NaiveDate {
+    let mut day = today;
+    let mut counted = 1;
+    while counted < WINDOW_BUSINESS_DAYS {
+        day -= Duration::days(1);
+        if !matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            counted += 1;
+        }
+    }
+    day
+}
+
+// Stock day trades on or after `since`: each sell of a symbol that was
+// bought earlier the same New York day. Crypto isn't subject to PDT.
+pub fn day_trades(trades: &[TradeRecord], since: NaiveDate) -> usize {
+    let mut bought: HashSet<(String, NaiveDate)> = HashSet::new();
+    let mut count = 0;
+    for trade in trades.iter().filter(|t| !symbols::is_crypto_symbol(&t.symbol)) {
+        let Some(day) = ny_date(&trade.timestamp).filter(|d| *d >= since) else {
+            continue;
+        };
+        let key = (symbols::normalize(&trade.symbol), day);
+        if trade.action == "BUY" {
+            bought.insert(key);
+        } else if bought.remove(&key) {
+            count += 1;
+        }
+    }
+    count
+}
+
+// Whether selling `symbol` on `day` would be a day trade
+pub fn bought_on(trades: &[TradeRecord], symbol: &str, day: NaiveDate) -> bool {
+    trades.iter().rev()
+        .filter(|t| t.action == "BUY" && symbols::same_symbol(&t.symbol, symbol))
+        .any(|t| ny_date(&t.timestamp) == Some(day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::TimeZone;
+
+    fn trade(action: &str, symbol: &str, at: DateTime<Utc>) -> TradeRecord {
+        test_support::trade(symbol, action, 1.0, 100.0, at)
+    }
+
+    // 15:00 UTC is mid-session in New York
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, 15, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn window_counts_back_five_weekdays() {
+        // Wednesday 2024-06-12 back over the weekend to Thursday the 6th
+        assert_eq!(window_start(NaiveDate::from_ymd_opt(2024, 6, 12).unwrap()), NaiveDate::from_ymd_opt(2024, 6, 6).unwrap());
+        assert_eq!(window_start(NaiveDate::from_ymd_opt(2024, 6, 14).unwrap()), NaiveDate::from_ymd_opt(2024, 6, 10).unwrap());
+    }
+
+    #[test]
+    fn same_day_round_trips_count_as_day_trades() {
+        let trades = vec![
+            trade("BUY", "AAPL", at(5)), trade("SELL", "AAPL", at(5)),  // Before the window
+            trade("BUY", "AAPL", at(6)), trade("SELL", "AAPL", at(6)),
+            trade("BUY", "MSFT", at(7)), trade("SELL", "MSFT", at(10)), // Held overnight
+            trade("BUY", "NVDA", at(10)), trade("SELL", "NVDA", at(10)),
+            trade("BUY", "BTC/USD", at(11)), trade("SELL", "BTC/USD", at(11)),  // Crypto is exempt
+            trade("BUY", "TSLA", at(12)), trade("SELL", "TSLA", at(12)),
+        ];
+
+        let today = today(at(12));
+        assert_eq!(day_trades(&trades, window_start(today)), 3);
+        assert!(bought_on(&trades, "TSLA", today));
+        assert!(!bought_on(&trades, "MSFT", today));
+    }
+}
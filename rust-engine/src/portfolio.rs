@@ -0,0 +1,275 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::engine::{PnlSnapshot, PortfolioSnapshot, TradeRecord};
+use crate::external;
+use crate::symbols;
+
+// How far `current` sits below the highest value seen, in percent (>= 0)
+pub fn drawdown_pct(values: &[f64], current: f64) -> f64 {
+    let high = values.iter().copied().fold(current, f64::max);
+    if high > 0.0 { (high - current) / high * 100.0 } else { 0.0 }
+}
+
+// Which drawdown alert levels have fired this session, so each fires once
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrawdownAlerts {
+    session: Option<NaiveDate>,
+    fired: Vec<f64>,
+}
+
+impl DrawdownAlerts {
+    // Levels (percent) newly crossed by `drawdown`. A new session date
+    // forgets everything that fired before.
+    pub fn newly_crossed(&mut self, session: NaiveDate, drawdown: f64, levels: &[f64]) -> Vec<f64> {
+        if self.session != Some(session) {
+            self.session = Some(session);
+            self.fired.clear();
+        }
+        let crossed: Vec<f64> = levels.iter()
+            .copied()
+            .filter(|level| *level > 0.0 && drawdown >= *level && !self.fired.contains(level))
+            .collect();
+        self.fired.extend(&crossed);
+        crossed
+    }
+}
+
+// (cash, positions value) of a portfolio that only exists in the trade
+// history, as in demo mode: cash starts at `starting_cash` and moves with
+// every buy and sell at its fill (or quoted) price, and what's still held is
+// marked at `mark`'s price, or at average cost when there's none
+pub fn simulated_value(trades: &[TradeRecord], starting_cash: f64, mark: impl Fn(&str) -> Option<f64>) -> (f64, f64) {
+    let cash = trades.iter().fold(starting_cash, |cash, t| {
+        let notional = t.quantity * t.fill_price.unwrap_or(t.price);
+        if t.action == "BUY" { cash - notional } else { cash + notional }
+    });
+    let positions_value = external::tracked_positions(trades).iter()
+        .filter(|(_, held)| held.qty > 0.0)
+        .map(|(symbol, held)| match mark(symbol) {
+            Some(price) => held.qty * price,
+            None => held.cost,
+        })
+        .sum();
+    (cash, positions_value)
+}
+
+// Average of (weight, value) pairs; None when the weights sum to nothing
+pub fn weighted_average(items: &[(f64, f64)]) -> Option<f64> {
+    let total_weight: f64 = items.iter().map(|(w, _)| w).sum();
+    (total_weight > 0.0).then(|| items.iter().map(|(w, v)| w * v).sum::<f64>() / total_weight)
+}
+
+// Parses a resolution like "30s", "1m", "5m", "1h" or "1d" into seconds
+pub fn parse_resolution(resolution: &str) -> Option<i64> {
+    let resolution = resolution.trim();
+    let split = resolution.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = resolution.split_at(split);
+    let amount: i64 = amount.parse().ok().filter(|a| *a > 0)?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    Some(amount * unit_secs)
+}
+
+// Downsamples snapshots into fixed time buckets, keeping the last snapshot
+// of each bucket. Snapshots with unparseable timestamps are dropped.
+pub fn resample(snapshots: &[PortfolioSnapshot], bucket_secs: i64) -> Vec<PortfolioSnapshot> {
+    let mut buckets: BTreeMap<i64, PortfolioSnapshot> = BTreeMap::new();
+
+    for snapshot in snapshots {
+        let Ok(ts) = DateTime::parse_from_rfc3339(&snapshot.timestamp) else {
+            continue;
+        };
+        let bucket = ts.timestamp().div_euclid(bucket_secs);
+        // Later snapshots overwrite earlier ones, leaving the last value per bucket
+        buckets.insert(bucket, snapshot.clone());
+    }
+
+    buckets.into_values().collect()
+}
+
+// One bucket of total_value as OHLC, for an equity candlestick chart
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityCandle {
+    pub timestamp: String,  // Bucket start
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+// Snapshots are assumed to be in time order (as kept in portfolio_history)
+pub fn candles(snapshots: &[PortfolioSnapshot], bucket_secs: i64) -> Vec<EquityCandle> {
+    let mut buckets: BTreeMap<i64, EquityCandle> = BTreeMap::new();
+
+    for snapshot in snapshots {
+        let Ok(ts) = DateTime::parse_from_rfc3339(&snapshot.timestamp) else {
+            continue;
+        };
+        let bucket = ts.timestamp().div_euclid(bucket_secs);
+        let value = snapshot.total_value;
+        buckets.entry(bucket)
+            .and_modify(|c| {
+                c.high = c.high.max(value);
+                c.low = c.low.min(value);
+                c.close = value;
+            })
+            .or_insert_with(|| EquityCandle {
+                timestamp: Utc.timestamp_opt(bucket * bucket_secs, 0)
+                    .single()
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default(),
+                open: value,
+                high: value,
+                low: value,
+                close: value,
+            });
+    }
+
+    buckets.into_values().collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionPoint {
+    pub timestamp: String,
+    pub unrealized_pnl: f64,        // 0 while the symbol isn't held
+    pub total_unrealized_pnl: f64,  // Across all positions at that moment
+}
+
+// One symbol's contribution to the open P&L at every recorded moment
+pub fn attribution_series(history: &[PnlSnapshot], symbol: &str) -> Vec<AttributionPoint> {
+    let symbol = symbols::normalize(symbol);
+    history.iter()
+        .map(|snapshot| AttributionPoint {
+            timestamp: snapshot.timestamp.clone(),
+            unrealized_pnl: snapshot.unrealized.get(&symbol).copied().unwrap_or(0.0),
+            total_unrealized_pnl: snapshot.unrealized.values().sum(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn snapshots_every(step_secs: i64, count: i64) -> Vec<PortfolioSnapshot> {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 13, 0, 0).unwrap();
+        (0..count)
+            .map(|i| PortfolioSnapshot {
+                timestamp: (start + Duration::seconds(i * step_secs)).to_rfc3339(),
+                total_value: 100_000.0 + i as f64,
+                cash: 50_000.0,
+                positions_value: 50_000.0 + i as f64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hourly_resolution_returns_one_point_per_hour() {
+        // 15s snapshots over three hours
+        let snapshots = snapshots_every(15, 3 * 240);
+        let hourly = resample(&snapshots, parse_resolution("1h").unwrap());
+
+        assert_eq!(hourly.len(), 3);
+        // Last value of each hour
+        assert_eq!(hourly[0].total_value, 100_239.0);
+        assert_eq!(hourly[2].total_value, 100_719.0);
+    }
+
+    #[test]
+    fn candles_track_open_high_low_close_per_bucket() {
+        let mut snapshots = snapshots_every(60, 10);
+        for (snapshot, value) in snapshots.iter_mut().zip([100.0, 104.0, 97.0, 101.0, 102.0, 110.0, 90.0, 95.0, 96.0, 99.0]) {
+            snapshot.total_value = value;
+        }
+        let candles = candles(&snapshots, parse_resolution("5m").unwrap());
+
+        let ohlc: Vec<(f64, f64, f64, f64)> = candles.iter().map(|c| (c.open, c.high, c.low, c.close)).collect();
+        assert_eq!(ohlc, vec![(100.0, 104.0, 97.0, 102.0), (110.0, 110.0, 90.0, 99.0)]);
+        assert_eq!(candles[0].timestamp, "2024-05-01T13:00:00+00:00");
+        assert_eq!(candles[1].timestamp, "2024-05-01T13:05:00+00:00");
+    }
+
+    #[test]
+    fn attribution_follows_one_symbol_through_the_snapshots() {
+        let snapshot = |timestamp: &str, unrealized: &[(&str, f64)]| PnlSnapshot {
+            timestamp: timestamp.to_string(),
+            unrealized: unrealized.iter().map(|(s, pnl)| (s.to_string(), *pnl)).collect(),
+        };
+        let history = vec![
+            snapshot("t0", &[("AAPL", 10.0)]),
+            snapshot("t1", &[("AAPL", 25.0), ("BTCUSD", -5.0)]),
+            snapshot("t2", &[("BTCUSD", 40.0)]),  // AAPL sold
+        ];
+
+        let points: Vec<(f64, f64)> = attribution_series(&history, "aapl").iter()
+            .map(|p| (p.unrealized_pnl, p.total_unrealized_pnl))
+            .collect();
+        assert_eq!(points, vec![(10.0, 10.0), (25.0, 20.0), (0.0, 40.0)]);
+        // Either symbol form finds the crypto position
+        let btc: Vec<f64> = attribution_series(&history, "BTC/USD").iter().map(|p| p.unrealized_pnl).collect();
+        assert_eq!(btc, vec![0.0, -5.0, 40.0]);
+    }
+
+    #[test]
+    fn parses_resolutions() {
+        assert_eq!(parse_resolution("30s"), Some(30));
+        assert_eq!(parse_resolution("5m"), Some(300));
+        assert_eq!(parse_resolution("1d"), Some(86_400));
+        assert_eq!(parse_resolution("0m"), None);
+        assert_eq!(parse_resolution("h"), None);
+        assert_eq!(parse_resolution("5x"), None);
+    }
+
+    #[test]
+    fn each_drawdown_level_fires_once_per_session() {
+        let mut alerts = DrawdownAlerts::default();
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let levels = [2.0, 5.0, 10.0];
+
+        assert!(alerts.newly_crossed(monday, 1.5, &levels).is_empty());
+        assert_eq!(alerts.newly_crossed(monday, 2.5, &levels), vec![2.0]);
+        assert!(alerts.newly_crossed(monday, 3.0, &levels).is_empty());
+        // Recovering and falling back doesn't re-fire
+        assert!(alerts.newly_crossed(monday, 0.5, &levels).is_empty());
+        assert_eq!(alerts.newly_crossed(monday, 12.0, &levels), vec![5.0, 10.0]);
+
+        let tuesday = monday.succ_opt().unwrap();
+        assert_eq!(alerts.newly_crossed(tuesday, 6.0, &levels), vec![2.0, 5.0]);
+    }
+
+    #[test]
+    fn weighted_average_weights_by_value() {
+        assert_eq!(weighted_average(&[(1_000.0, 2.0), (3_000.0, 0.5)]), Some(0.875));
+        assert_eq!(weighted_average(&[(1.0, 4.0)]), Some(4.0));
+        assert_eq!(weighted_average(&[]), None);
+        assert_eq!(weighted_average(&[(0.0, 4.0)]), None);
+    }
+
+    #[test]
+    fn simulated_value_moves_cash_and_marks_what_is_held() {
+        let trade = |symbol: &str, action: &str, quantity: f64, price: f64| {
+            crate::test_support::trade(symbol, action, quantity, price, Utc::now())
+        };
+        let trades = vec![
+            trade("AAPL", "BUY", 10.0, 100.0),
+            trade("AAPL", "SELL", 4.0, 110.0),
+            trade("BTC/USD", "BUY", 0.5, 60_000.0),
+        ];
+        let mark = |symbol: &str| (symbol == "AAPL").then_some(120.0);
+
+        let (cash, positions_value) = simulated_value(&trades, 100_000.0, mark);
+        // 100k - 1000 + 440 - 30k
+        assert_eq!(cash, 69_440.0);
+        // 6 AAPL at the $120 mark, and BTC with no mark at its $30k cost
+        assert_eq!(positions_value, 6.0 * 120.0 + 30_000.0);
+        assert_eq!(simulated_value(&[], 100_000.0, mark), (100_000.0, 0.0));
+    }
+}
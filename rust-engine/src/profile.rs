@@ -0,0 +1,114 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::{
+    Config, CryptoExitConfig, EquityFloorConfig, IndicatorConfig, ProfitTargetConfig, ThresholdConfig,
+};
+
+// Named bundle of the signal/risk tunables that are safe to swap while the
+// engine runs. Settings read once at startup (feeds, stream, backoff, clients)
+// are deliberately not part of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyProfile {
+    pub thresholds: ThresholdConfig,
+    pub indicators: IndicatorConfig,
+    pub profit_target: ProfitTargetConfig,
+    pub crypto_exit: CryptoExitConfig,
+    pub equity_floor: EquityFloorConfig,
+}
+
+impl StrategyProfile {
+    pub fn capture(config: &Config) -> Self {
+        Self {
+            thresholds: config.thresholds.clone(),
+            indicators: config.indicators.clone(),
+            profit_target: config.profit_target.clone(),
+            crypto_exit: config.crypto_exit.clone(),
+            equity_floor: config.equity_floor.clone(),
+        }
+    }
+
+    // Caller holds the config write lock, so the cycles see all of it or none
+    pub fn apply(self, config: &mut Config) {
+        config.thresholds = self.thresholds;
+        config.indicators = self.indicators;
+        config.profit_target = self.profit_target;
+        config.crypto_exit = self.crypto_exit;
+        config.equity_floor = self.equity_floor;
+    }
+}
+
+// One JSON file per profile in PROFILES_DIR
+#[derive(Clone)]
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    // Names become file names, so keep them to a safe alphabet
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            bail!("invalid profile name '{}': use letters, digits, '-' or '_'", name);
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<StrategyProfile>> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&text)?))
+    }
+
+    pub fn save(&self, name: &str, profile: &StrategyProfile) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(path, serde_json::to_string_pretty(profile)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::technical::MovingAverage;
+
+    #[test]
+    fn activating_a_saved_profile_replaces_every_value() {
+        let mut aggressive = StrategyProfile::capture(&Config::from_env());
+        aggressive.thresholds.stock_buy = 0.05;
+        aggressive.thresholds.crypto_sell = -0.05;
+        aggressive.thresholds.entry_confirmation_cycles = 3;
+        aggressive.indicators.momentum_windows = vec![3, 7];
+        aggressive.indicators.crossover_average = MovingAverage::Wma;
+        aggressive.profit_target.stock_pct = 4.0;
+        aggressive.profit_target.atr_modes = vec!["volatile".to_string()];
+        aggressive.crypto_exit.tiers = vec![(5.0, 0.25), (10.0, 0.5)];
+        aggressive.equity_floor.min_equity = 30_000.0;
+        aggressive.equity_floor.flatten = true;
+
+        let dir = std::env::temp_dir().join(format!("ladybug-profiles-{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::new(&dir);
+        store.save("aggressive", &aggressive).unwrap();
+        assert!(store.load("missing").unwrap().is_none());
+        assert!(store.save("../escape", &aggressive).is_err());
+
+        let mut config = Config::from_env();
+        store.load("aggressive").unwrap().unwrap().apply(&mut config);
+        assert_eq!(
+            serde_json::to_value(StrategyProfile::capture(&config)).unwrap(),
+            serde_json::to_value(&aggressive).unwrap(),
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+use crate::config::PromotionConfig;
+use crate::metrics::TradeStats;
+
+// One item of the paper-to-live checklist
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+// Prerequisites for leaving paper trading, judged on the realized paper
+// results. Every item is reported, not just the first failure.
+pub fn checklist(stats: &TradeStats, credentials_valid: bool, config: &PromotionConfig) -> Vec<Check> {
+    vec![
+        Check {
+            name: "credentials",
+            passed: credentials_valid,
+            detail: if credentials_valid {
+                "Alpaca account reachable with the configured keys".to_string()
+            } else {
+                "Alpaca credentials missing or rejected".to_string()
+            },
+        },
+        Check {
+            name: "paper_trades",
+            passed: stats.closed_trades >= config.min_trades,
+            detail: format!("{} closed paper trades (need {})", stats.closed_trades, config.min_trades),
+        },
+        Check {
+            name: "win_rate",
+            passed: stats.win_rate * 100.0 >= config.min_win_rate_pct,
+            detail: format!("{:.1}% paper win rate (need {:.1}%)", stats.win_rate * 100.0, config.min_win_rate_pct),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::trade_stats;
+
+    const GATE: PromotionConfig = PromotionConfig { min_trades: 5, min_win_rate_pct: 50.0 };
+
+    fn failed(checks: &[Check]) -> Vec<&'static str> {
+        checks.iter().filter(|c| !c.passed).map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn thin_or_losing_paper_record_is_refused() {
+        // Two wins out of four
+        let few = checklist(&trade_stats(&[10.0, -5.0, 8.0, -2.0]), true, &GATE);
+        assert_eq!(failed(&few), vec!["paper_trades"]);
+        assert_eq!(few[1].detail, "4 closed paper trades (need 5)");
+
+        // One win out of five
+        let losing = checklist(&trade_stats(&[10.0, -5.0, -8.0, -2.0, -1.0]), false, &GATE);
+        assert_eq!(failed(&losing), vec!["credentials", "win_rate"]);
+    }
+
+    #[test]
+    fn meeting_every_threshold_passes() {
+        let checks = checklist(&trade_stats(&[10.0, -5.0, 8.0, 3.0, -1.0]), true, &GATE);
+        assert!(failed(&checks).is_empty());
+        assert_eq!(checks.len(), 3);
+    }
+}
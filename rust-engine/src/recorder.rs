@@ -0,0 +1,181 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::alpaca::Bar;
+use crate::replay::{RecordedEvent, ReplayEvent};
+use crate::symbols;
+
+// Events waiting to be written; past this the recorder drops rather than
+// slowing the trading loops down
+const QUEUE_CAPACITY: usize = 10_000;
+
+// Writes bars, quotes, news and decisions as they happen to JSON Lines files
+// that REPLAY_FILE can consume. Recording is fire-and-forget: callers only
+// push onto a channel and a background task does the buffered writing,
+// starting a new timestamped file once the current one reaches max_bytes.
+#[derive(Clone)]
+pub struct EventRecorder {
+    tx: Option<mpsc::Sender<RecordedEvent>>,
+    rx: Arc<Mutex<Option<mpsc::Receiver<RecordedEvent>>>>,
+    dir: PathBuf,
+    max_bytes: u64,
+    last_bar: Arc<DashMap<String, String>>,  // Newest bar time recorded per symbol
+}
+
+impl EventRecorder {
+    pub fn disabled() -> Self {
+        Self {
+            tx: None,
+            rx: Arc::new(Mutex::new(None)),
+            dir: PathBuf::new(),
+            max_bytes: 0,
+            last_bar: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        Self {
+            tx: Some(tx),
+            rx: Arc::new(Mutex::new(Some(rx))),
+            dir: dir.into(),
+            max_bytes: max_bytes.max(1),
+            last_bar: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tx.is_some()
+    }
+
+    // Spawns the writer. Events recorded before this are queued, not lost.
+    pub fn start(&self) {
+        let Some(rx) = self.rx.lock().unwrap().take() else {
+            return;
+        };
+        info!("🎙️  Recording events to {}", self.dir.display());
+        tokio::spawn(write_loop(rx, self.dir.clone(), self.max_bytes));
+    }
+
+    pub fn record(&self, event: ReplayEvent) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.try_send(RecordedEvent { t: Utc::now(), event });
+        }
+    }
+
+    // Only bars newer than the last one recorded for the symbol, so replay
+    // rebuilds the same rolling window instead of seeing duplicates
+    pub fn record_bars(&self, symbol: &str, bars: &[Bar]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let key = symbols::normalize(symbol);
+        let last = self.last_bar.get(&key).map(|t| t.clone());
+        let fresh = bars.iter().filter(|b| last.as_ref().is_none_or(|t| b.t > *t));
+        for bar in fresh {
+            self.record(ReplayEvent::Bar { symbol: symbol.to_string(), bar: bar.clone() });
+        }
+        if let Some(newest) = bars.last() {
+            self.last_bar.insert(key, newest.t.clone());
+        }
+    }
+}
+
+async fn open_file(dir: &Path) -> std::io::Result<BufWriter<File>> {
+    tokio::fs::create_dir_all(dir).await?;
+    let path = dir.join(format!("events-{}.jsonl", Utc::now().format("%Y%m%d-%H%M%S%.3f")));
+    info!("🎙️  New event file {}", path.display());
+    Ok(BufWriter::new(File::create(path).await?))
+}
+
+async fn write_loop(mut rx: mpsc::Receiver<RecordedEvent>, dir: PathBuf, max_bytes: u64) {
+    let mut current: Option<(BufWriter<File>, u64)> = None;
+
+    while let Some(event) = rx.recv().await {
+        let Ok(mut line) = serde_json::to_string(&event) else {
+            continue;
+        };
+        line.push('\n');
+
+        // Rotate once the next line would overflow the current file
+        if current.as_ref().is_none_or(|(_, size)| *size > 0 && size + line.len() as u64 > max_bytes) {
+            if let Some((mut writer, _)) = current.take() {
+                let _ = writer.flush().await;
+            }
+            match open_file(&dir).await {
+                Ok(writer) => current = Some((writer, 0)),
+                Err(e) => {
+                    warn!("⚠️  Could not open event file in {}: {}", dir.display(), e);
+                    continue;
+                }
+            }
+        }
+
+        let Some((writer, size)) = current.as_mut() else {
+            continue;
+        };
+        if let Err(e) = writer.write_all(line.as_bytes()).await {
+            warn!("⚠️  Failed to write event: {}", e);
+            continue;
+        }
+        *size += line.len() as u64;
+
+        // Flush whenever we catch up, so the file is complete between bursts
+        if rx.is_empty() {
+            let _ = writer.flush().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay;
+
+    fn bar(minute: u32, close: f64) -> Bar {
+        Bar { t: format!("2024-06-12T14:{:02}:00Z", minute), o: close, h: close, l: close, c: close, v: 100 }
+    }
+
+    // Waits for the writer to flush `expected` lines and loads them back
+    async fn recorded(dir: &Path, expected: usize) -> Vec<RecordedEvent> {
+        for _ in 0..100 {
+            let file = std::fs::read_dir(dir).ok().and_then(|mut entries| entries.next()).and_then(|e| e.ok());
+            if let Some(file) = file {
+                let events = replay::load(&file.path().display().to_string()).unwrap();
+                if events.len() >= expected {
+                    return events;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("recorder never wrote {} events", expected);
+    }
+
+    #[tokio::test]
+    async fn recorded_events_load_back_for_replay() {
+        let dir = std::env::temp_dir().join(format!("ladybug-recorder-{}", uuid::Uuid::new_v4()));
+        let recorder = EventRecorder::new(&dir, 1 << 20);
+        recorder.start();
+
+        recorder.record_bars("AAPL", &[bar(0, 100.0), bar(5, 101.0)]);
+        // Overlapping fetch: only the new bar is recorded
+        recorder.record_bars("AAPL", &[bar(5, 101.0), bar(10, 102.0)]);
+        recorder.record(ReplayEvent::Quote { symbol: "AAPL".to_string(), price: 102.5 });
+        recorder.record(ReplayEvent::News { symbol: "AAPL".to_string(), id: "n-1".to_string(), score: 0.4 });
+
+        let events = recorded(&dir, 5).await;
+        let closes: Vec<f64> = events.iter()
+            .filter_map(|e| match &e.event { ReplayEvent::Bar { bar, .. } => Some(bar.c), _ => None })
+            .collect();
+        assert_eq!(closes, vec![100.0, 101.0, 102.0]);
+        assert!(matches!(&events[3].event, ReplayEvent::Quote { price, .. } if *price == 102.5));
+        assert!(matches!(&events[4].event, ReplayEvent::News { id, .. } if id == "n-1"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::metrics::GroupStats;
+use crate::symbols;
+
+// Stock universe swaps made by performance rotation, keyed by the trading
+// mode's original symbol. Rotating out a replacement rewrites the original
+// key, so a slot never chains.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rotation {
+    pub swaps: BTreeMap<String, String>,  // Original symbol -> current replacement
+    pub last_run: Option<String>,
+}
+
+impl Rotation {
+    // The mode's universe with swaps applied, order kept
+    pub fn apply(&self, universe: &[&str]) -> Vec<String> {
+        universe.iter()
+            .map(|s| self.swaps.get(*s).cloned().unwrap_or_else(|| s.to_string()))
+            .collect()
+    }
+
+    pub fn record(&mut self, out: &str, replacement: &str) {
+        let slot = self.swaps.iter()
+            .find(|(_, current)| symbols::same_symbol(current, out))
+            .map(|(original, _)| original.clone())
+            .unwrap_or_else(|| out.to_string());
+        if symbols::same_symbol(&slot, replacement) {
+            self.swaps.remove(&slot);  // Back to the original
+        } else {
+            self.swaps.insert(slot, replacement.to_string());
+        }
+    }
+}
+
+// Active symbols that keep losing: at least `min_trades` closed trades, a net
+// realized loss and a win rate under `min_win_rate` (0-1). Worst P&L first.
+pub fn underperformers(
+    stats: &BTreeMap<String, GroupStats>,
+    active: &[String],
+    min_trades: usize,
+    min_win_rate: f64,
+) -> Vec<String> {
+    let mut losers: Vec<(&String, f64)> = active.iter()
+        .filter_map(|symbol| {
+            let s = stats.get(&symbols::normalize(symbol))?;
+            (s.trades >= min_trades && s.realized_pnl < 0.0 && s.win_rate < min_win_rate)
+                .then_some((symbol, s.realized_pnl))
+        })
+        .collect();
+    losers.sort_by(|a, b| a.1.total_cmp(&b.1));
+    losers.into_iter().map(|(symbol, _)| symbol.clone()).collect()
+}
+
+// Pairs each loser (worst first) with the strongest remaining candidate.
+// Only candidates with a bullish signal (> 0) qualify.
+pub fn plan_swaps(losers: &[String], candidates: &[(String, f64)], max_swaps: usize) -> Vec<(String, String)> {
+    let mut ranked: Vec<&(String, f64)> = candidates.iter().filter(|(_, signal)| *signal > 0.0).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    losers.iter()
+        .zip(ranked)
+        .take(max_swaps)
+        .map(|(out, (replacement, _))| (out.clone(), replacement.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::group_stats;
+
+    #[test]
+    fn losing_symbol_is_rotated_out_for_the_best_candidate() {
+        let stats = group_stats([
+            ("AAPL", 40.0), ("AAPL", -10.0), ("AAPL", 25.0),
+            ("INTC", -30.0), ("INTC", -20.0), ("INTC", 5.0),
+            ("F", -5.0),  // One trade isn't a pattern
+        ]);
+        let active: Vec<String> = ["AAPL", "INTC", "F"].iter().map(|s| s.to_string()).collect();
+
+        let losers = underperformers(&stats, &active, 3, 0.4);
+        assert_eq!(losers, vec!["INTC"]);
+        let candidates = vec![("AMD".to_string(), 0.2), ("NVDA".to_string(), 0.7), ("PLTR".to_string(), -0.3)];
+        let swaps = plan_swaps(&losers, &candidates, 2);
+        assert_eq!(swaps, vec![("INTC".to_string(), "NVDA".to_string())]);
+
+        let mut rotation = Rotation::default();
+        rotation.record("INTC", "NVDA");
+        assert_eq!(rotation.apply(&["AAPL", "INTC", "F"]), vec!["AAPL", "NVDA", "F"]);
+        // Rotating the replacement out again reuses INTC's slot
+        rotation.record("NVDA", "AMD");
+        assert_eq!(rotation.apply(&["AAPL", "INTC", "F"]), vec!["AAPL", "AMD", "F"]);
+        rotation.record("AMD", "INTC");
+        assert!(rotation.swaps.is_empty());
+    }
+}
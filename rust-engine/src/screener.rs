@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// What the screener computed for one candidate
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenerRow {
+    pub symbol: String,
+    pub price: f64,          // Last close
+    pub daily_volume: f64,   // Estimated from recent intraday bars
+    pub rsi: Option<f64>,
+    pub signal: f64,
+}
+
+// Query filters for GET /screener; unset bounds don't filter
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScreenerFilter {
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub min_volume: Option<f64>,
+    pub min_rsi: Option<f64>,
+    pub max_rsi: Option<f64>,
+}
+
+impl ScreenerFilter {
+    // Rows without an RSI fail any RSI bound
+    pub fn matches(&self, row: &ScreenerRow) -> bool {
+        self.min_price.is_none_or(|min| row.price >= min)
+            && self.max_price.is_none_or(|max| row.price <= max)
+            && self.min_volume.is_none_or(|min| row.daily_volume >= min)
+            && self.min_rsi.is_none_or(|min| row.rsi.is_some_and(|rsi| rsi >= min))
+            && self.max_rsi.is_none_or(|max| row.rsi.is_some_and(|rsi| rsi <= max))
+    }
+}
+
+// Matching rows, strongest (most bullish) signal first
+pub fn rank(rows: &[ScreenerRow], filter: &ScreenerFilter) -> Vec<ScreenerRow> {
+    let mut matched: Vec<ScreenerRow> = rows.iter().filter(|r| filter.matches(r)).cloned().collect();
+    matched.sort_by(|a, b| b.signal.total_cmp(&a.signal));
+    matched
+}
+
+type Scan = (Instant, Vec<ScreenerRow>);
+
+// The last scan, reused for any filter until it's older than the TTL
+#[derive(Clone, Default)]
+pub struct ScreenerCache {
+    last: Arc<Mutex<Option<Scan>>>,
+}
+
+impl ScreenerCache {
+    pub fn get(&self, ttl: Duration) -> Option<Vec<ScreenerRow>> {
+        let last = self.last.lock().unwrap();
+        last.as_ref()
+            .filter(|(at, _)| at.elapsed() < ttl)
+            .map(|(_, rows)| rows.clone())
+    }
+
+    pub fn set(&self, rows: Vec<ScreenerRow>) {
+        *self.last.lock().unwrap() = Some((Instant::now(), rows));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(symbol: &str, price: f64, rsi: Option<f64>, signal: f64) -> ScreenerRow {
+        ScreenerRow { symbol: symbol.to_string(), price, daily_volume: 1_000_000.0, rsi, signal }
+    }
+
+    #[test]
+    fn only_rows_inside_the_price_and_rsi_bounds_come_back_ranked() {
+        let rows = vec![
+            row("AAPL", 190.0, Some(45.0), 0.2),
+            row("NVDA", 900.0, Some(40.0), 0.6),   // Too expensive
+            row("F", 12.0, Some(35.0), 0.4),
+            row("PLTR", 25.0, Some(75.0), 0.5),    // Overbought
+            row("NEW", 30.0, None, 0.9),           // No RSI yet
+        ];
+        let filter = ScreenerFilter { min_price: Some(10.0), max_price: Some(500.0), max_rsi: Some(50.0), ..Default::default() };
+
+        let symbols: Vec<String> = rank(&rows, &filter).into_iter().map(|r| r.symbol).collect();
+        assert_eq!(symbols, vec!["F", "AAPL"]);
+        assert_eq!(rank(&rows, &ScreenerFilter::default()).len(), rows.len());
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::TradeRecord;
+
+// End-of-day recap, built when the stock session closes
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_start: String,
+    pub session_end: String,
+    pub trades: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub realized_pnl: f64,
+    pub win_rate: f64,  // Share of closing trades with positive P&L, 0-1
+    pub best_trade: Option<TradeRecord>,
+    pub worst_trade: Option<TradeRecord>,
+    pub ending_equity: f64,
+}
+
+// Today's 9:30 AM New York open, in UTC
+pub fn session_open(now: DateTime<Utc>) -> DateTime<Utc> {
+    let ny = now.with_timezone(&chrono_tz::America::New_York);
+    let open = ny.date_naive().and_time(NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+    chrono_tz::America::New_York
+        .from_local_datetime(&open)
+        .single()
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or(now)
+}
+
+// Summarizes the trades recorded between `since` and `until`. Only SELLs
+// carry realized P&L, so win rate and best/worst are over closing trades.
+pub fn summarize(
+    trades: &[TradeRecord],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    ending_equity: f64,
+) -> SessionSummary {
+    let session: Vec<&TradeRecord> = trades.iter()
+        .filter(|t| {
+            DateTime::parse_from_rfc3339(&t.timestamp)
+                .map(|ts| ts >= since && ts <= until)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let closes: Vec<&TradeRecord> = session.iter().copied().filter(|t| t.action == "SELL").collect();
+    let wins = closes.iter().filter(|t| t.pnl > 0.0).count();
+
+    SessionSummary {
+        session_start: since.to_rfc3339(),
+        session_end: until.to_rfc3339(),
+        trades: session.len(),
+        buys: session.iter().filter(|t| t.action == "BUY").count(),
+        sells: closes.len(),
+        realized_pnl: closes.iter().map(|t| t.pnl).sum(),
+        win_rate: if closes.is_empty() { 0.0 } else { wins as f64 / closes.len() as f64 },
+        best_trade: closes.iter().max_by(|a, b| a.pnl.total_cmp(&b.pnl)).map(|t| (*t).clone()),
+        worst_trade: closes.iter().min_by(|a, b| a.pnl.total_cmp(&b.pnl)).map(|t| (*t).clone()),
+        ending_equity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::Duration;
+
+    fn trade(action: &str, pnl: f64, at: DateTime<Utc>) -> TradeRecord {
+        TradeRecord { pnl, ..test_support::trade("AAPL", action, 1.0, 100.0, at) }
+    }
+
+    #[test]
+    fn session_opens_at_930_new_york() {
+        // 15:00 UTC on a June day is 11:00 EDT
+        let now = Utc.with_ymd_and_hms(2024, 6, 12, 15, 0, 0).unwrap();
+        assert_eq!(session_open(now), Utc.with_ymd_and_hms(2024, 6, 12, 13, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn summarizes_only_the_session() {
+        let close = Utc.with_ymd_and_hms(2024, 6, 12, 20, 0, 0).unwrap();
+        let open = session_open(close);
+        let trades = vec![
+            trade("SELL", 99.0, open - Duration::minutes(5)),  // Yesterday's business
+            trade("BUY", 0.0, open + Duration::minutes(10)),
+            trade("SELL", 30.0, open + Duration::hours(2)),
+            trade("SELL", -10.0, open + Duration::hours(3)),
+        ];
+
+        let summary = summarize(&trades, open, close, 101_000.0);
+        assert_eq!((summary.trades, summary.buys, summary.sells), (3, 1, 2));
+        assert_eq!(summary.realized_pnl, 20.0);
+        assert_eq!(summary.win_rate, 0.5);
+        assert_eq!(summary.best_trade.unwrap().pnl, 30.0);
+        assert_eq!(summary.worst_trade.unwrap().pnl, -10.0);
+    }
+}
@@ -0,0 +1,153 @@
+// Order quantity helpers shared by the stock and crypto buy paths
+
+// Rounds a raw quantity down to the nearest multiple of `increment`.
+// A small epsilon keeps float noise (e.g. 0.3 / 0.1 = 2.999..) from
+// dropping a whole increment.
+pub fn round_down_to_increment(qty: f64, increment: f64) -> f64 {
+    if !qty.is_finite() || qty <= 0.0 {
+        return 0.0;
+    }
+    if increment <= 0.0 {
+        return qty;
+    }
+    let units = (qty / increment + 1e-9).floor();
+    units * increment
+}
+
+// Quantity for selling `fraction` of a holding, rounded down to the increment
+// and never more than what's held
+pub fn partial_qty(held: f64, fraction: f64, increment: f64) -> f64 {
+    round_down_to_increment(held * fraction.clamp(0.0, 1.0), increment).min(held)
+}
+
+// Largest order that keeps the holding at or under `max_units`, rounded down
+pub fn clamp_to_max_units(qty: f64, held: f64, max_units: f64, increment: f64) -> f64 {
+    let room = (max_units - held.abs()).max(0.0);
+    if qty <= room {
+        qty
+    } else {
+        round_down_to_increment(room, increment)
+    }
+}
+
+// Regular US session in 5-minute bars (6.5h)
+pub const STOCK_BARS_PER_DAY: f64 = 78.0;
+
+// Average daily volume estimated from recent intraday bars: mean bar volume
+// scaled to a full session
+pub fn estimate_daily_volume(volumes: &[f64], bars_per_day: f64) -> f64 {
+    if volumes.is_empty() {
+        return 0.0;
+    }
+    volumes.iter().sum::<f64>() / volumes.len() as f64 * bars_per_day
+}
+
+// Units that lose exactly `risk_dollars` if price falls from `entry` to `stop`
+pub fn risk_qty(risk_dollars: f64, entry: f64, stop: f64) -> f64 {
+    let per_unit = entry - stop;
+    if per_unit <= 0.0 || risk_dollars <= 0.0 {
+        return 0.0;
+    }
+    risk_dollars / per_unit
+}
+
+// Largest order that stays within `max_pct` of average daily volume, rounded down
+pub fn clamp_to_participation(qty: f64, daily_volume: f64, max_pct: f64, increment: f64) -> f64 {
+    let limit = daily_volume * max_pct / 100.0;
+    if qty <= limit {
+        qty
+    } else {
+        round_down_to_increment(limit, increment)
+    }
+}
+
+// Gross leverage if `added` more notional were bought: everything held (long
+// or short, by absolute value) over equity. None without positive equity.
+pub fn leverage_after(held_notional: f64, added: f64, equity: f64) -> Option<f64> {
+    if equity <= 0.0 {
+        return None;
+    }
+    Some((held_notional.abs() + added.abs()) / equity)
+}
+
+// Formats a quantity with exactly as many decimals as the increment needs,
+// so "0.30000000000000004" never reaches the order API
+pub fn format_qty(qty: f64, increment: f64) -> String {
+    let decimals = if increment > 0.0 && increment < 1.0 {
+        (-increment.log10()).ceil() as usize
+    } else {
+        0
+    };
+    format!("{:.*}", decimals, qty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lot_size_100_rounds_down_to_whole_lots() {
+        assert_eq!(round_down_to_increment(250.0, 100.0), 200.0);
+        assert_eq!(round_down_to_increment(100.0, 100.0), 100.0);
+        assert_eq!(format_qty(200.0, 100.0), "200");
+    }
+
+    #[test]
+    fn lot_size_100_below_one_lot_rounds_to_zero() {
+        assert_eq!(round_down_to_increment(99.9, 100.0), 0.0);
+    }
+
+    #[test]
+    fn crypto_step_rounds_down_and_formats_cleanly() {
+        let qty = round_down_to_increment(0.123456, 0.001);
+        assert!((qty - 0.123).abs() < 1e-12);
+        assert_eq!(format_qty(qty, 0.001), "0.123");
+        // Float noise must not lose a whole step
+        assert_eq!(format_qty(round_down_to_increment(0.3, 0.1), 0.1), "0.3");
+        assert_eq!(round_down_to_increment(0.0004, 0.001), 0.0);
+    }
+
+    #[test]
+    fn half_of_a_crypto_holding_rounds_down_to_the_step() {
+        let qty = partial_qty(0.0123, 0.5, 0.0001);
+        assert!((qty - 0.0061).abs() < 1e-12);
+        assert_eq!(format_qty(qty, 0.0001), "0.0061");
+        assert_eq!(format_qty(partial_qty(0.0123, 0.5, 0.000001), 0.000001), "0.006150");
+        // Fractions over 1 never sell more than is held
+        assert_eq!(partial_qty(0.0123, 1.5, 0.0001), 0.0123);
+    }
+
+    #[test]
+    fn scaling_in_stops_at_the_unit_cap() {
+        let mut held = 0.0;
+        let mut orders = Vec::new();
+        for _ in 0..4 {
+            let qty = clamp_to_max_units(40.0, held, 100.0, 1.0);
+            held += qty;
+            orders.push(qty);
+        }
+        assert_eq!(orders, vec![40.0, 40.0, 20.0, 0.0]);
+        assert_eq!(held, 100.0);
+    }
+
+    #[test]
+    fn participation_cap_downsizes_thin_names() {
+        let daily = estimate_daily_volume(&[100.0, 300.0], STOCK_BARS_PER_DAY);
+        assert_eq!(daily, 15_600.0);
+        assert_eq!(clamp_to_participation(500.0, daily, 1.0, 1.0), 156.0);
+        assert_eq!(clamp_to_participation(100.0, daily, 1.0, 1.0), 100.0);
+        assert_eq!(clamp_to_participation(500.0, daily, 1.0, 100.0), 100.0);
+    }
+
+    #[test]
+    fn leverage_counts_held_and_new_notional_against_equity() {
+        let max_leverage = 1.5;
+        // $100k held on $100k equity: a $40k buy stays under 1.5x, $60k goes over
+        assert_eq!(leverage_after(100_000.0, 40_000.0, 100_000.0), Some(1.4));
+        assert!(leverage_after(100_000.0, 40_000.0, 100_000.0).unwrap() <= max_leverage);
+        assert!(leverage_after(100_000.0, 60_000.0, 100_000.0).unwrap() > max_leverage);
+        // Shorts count by absolute value
+        assert_eq!(leverage_after(-50_000.0, 50_000.0, 100_000.0), Some(1.0));
+        assert_eq!(leverage_after(100_000.0, 1.0, 0.0), None);
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlippageFlag {
+    pub symbol: String,
+    pub side: String,
+    pub quote_price: f64,
+    pub fill_price: f64,
+    pub slippage_pct: f64,
+    pub flagged_at: String,
+}
+
+// Compares confirmed fills against the pre-trade quote. A symbol that fills
+// worse than the tolerance is flagged and gets limit orders from then on.
+pub struct SlippageGuard {
+    max_slippage_pct: f64,
+    flagged: Arc<DashMap<String, SlippageFlag>>,
+}
+
+impl SlippageGuard {
+    pub fn new(max_slippage_pct: f64) -> Self {
+        Self {
+            max_slippage_pct: max_slippage_pct.max(0.0),
+            flagged: Arc::new(DashMap::new()),
+        }
+    }
+
+    // Adverse slippage in percent: paying more on a buy, receiving less on a sell
+    pub fn slippage_pct(side: &str, quote_price: f64, fill_price: f64) -> f64 {
+        if quote_price <= 0.0 {
+            return 0.0;
+        }
+        let diff = if side == "sell" { quote_price - fill_price } else { fill_price - quote_price };
+        (diff / quote_price) * 100.0
+    }
+
+    // Records a confirmed fill. Returns the slippage if it breached the tolerance.
+    pub fn check_fill(&self, symbol: &str, side: &str, quote_price: f64, fill_price: f64) -> Option<f64> {
+        let slippage_pct = Self::slippage_pct(side, quote_price, fill_price);
+        if slippage_pct <= self.max_slippage_pct {
+            return None;
+        }
+        self.flagged.insert(symbol.to_string(), SlippageFlag {
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            quote_price,
+            fill_price,
+            slippage_pct,
+            flagged_at: Utc::now().to_rfc3339(),
+        });
+        Some(slippage_pct)
+    }
+
+    // Limit price for a flagged symbol: the quote plus the tolerance in the
+    // adverse direction. None means a market order is fine.
+    pub fn limit_price(&self, symbol: &str, side: &str, quote_price: f64) -> Option<f64> {
+        if !self.flagged.contains_key(symbol) {
+            return None;
+        }
+        let tolerance = self.max_slippage_pct / 100.0;
+        Some(if side == "sell" { quote_price * (1.0 - tolerance) } else { quote_price * (1.0 + tolerance) })
+    }
+
+    pub fn snapshot(&self) -> Vec<SlippageFlag> {
+        let mut flags: Vec<SlippageFlag> = self.flagged.iter().map(|f| f.value().clone()).collect();
+        flags.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        flags
+    }
+}
+
+// How far a limit sits past the quote in the costly direction (above it for
+// a buy, below it for a sell), in percent, when that exceeds `band_pct`.
+// A limit on the passive side just might not fill, so it always passes.
+pub fn limit_outside_band(side: &str, limit_price: f64, quote_price: f64, band_pct: f64) -> Option<f64> {
+    if quote_price <= 0.0 || band_pct <= 0.0 {
+        return None;
+    }
+    let deviation = SlippageGuard::slippage_pct(side, quote_price, limit_price);
+    (deviation > band_pct).then_some(deviation)
+}
+
+// Alpaca rejects sub-penny limit prices above $1
+pub fn format_limit_price(price: f64) -> String {
+    if price >= 1.0 {
+        format!("{:.2}", price)
+    } else {
+        format!("{:.4}", price)
+    }
+}
+
+// Nearest-rank percentile (0-100) of already sorted values
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+// Distribution of recorded slippage (%) for one symbol or overall
+#[derive(Debug, Clone, Serialize)]
+pub struct SlippageStats {
+    pub fills: usize,
+    pub average_pct: f64,
+    pub p50_pct: f64,
+    pub p95_pct: f64,
+    pub worst_pct: f64,
+}
+
+pub fn slippage_stats(values: &[f64]) -> Option<SlippageStats> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    Some(SlippageStats {
+        fills: sorted.len(),
+        average_pct: sorted.iter().sum::<f64>() / sorted.len().max(1) as f64,
+        p50_pct: percentile(&sorted, 50.0)?,
+        p95_pct: percentile(&sorted, 95.0)?,
+        worst_pct: *sorted.last()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_fill_flags_symbol_and_switches_to_limit_orders() {
+        let guard = SlippageGuard::new(1.0);
+        assert_eq!(guard.limit_price("GME", "buy", 20.0), None);
+
+        // Quoted at $20, filled at $21: 5% adverse
+        let slippage = guard.check_fill("GME", "buy", 20.0, 21.0).unwrap();
+        assert!((slippage - 5.0).abs() < 1e-9);
+        assert_eq!(guard.snapshot().len(), 1);
+
+        let limit = guard.limit_price("GME", "buy", 20.0).unwrap();
+        assert!((limit - 20.2).abs() < 1e-9);
+        let limit = guard.limit_price("GME", "sell", 20.0).unwrap();
+        assert!((limit - 19.8).abs() < 1e-9);
+        assert_eq!(guard.limit_price("AAPL", "buy", 190.0), None);
+    }
+
+    #[test]
+    fn fills_within_tolerance_or_favorable_are_not_flagged() {
+        let guard = SlippageGuard::new(1.0);
+        assert_eq!(guard.check_fill("AAPL", "buy", 100.0, 100.5), None);
+        assert_eq!(guard.check_fill("AAPL", "sell", 100.0, 101.0), None);
+        assert!(guard.snapshot().is_empty());
+        assert_eq!(guard.limit_price("AAPL", "buy", 100.0), None);
+    }
+
+    #[test]
+    fn limits_past_the_band_on_the_costly_side_are_caught() {
+        // Buying: a limit 3% over the quote is out, 0.5% over is fine
+        assert!((limit_outside_band("buy", 103.0, 100.0, 2.0).unwrap() - 3.0).abs() < 1e-9);
+        assert_eq!(limit_outside_band("buy", 100.5, 100.0, 2.0), None);
+        // Selling: the costly side is below the quote
+        assert!(limit_outside_band("sell", 97.0, 100.0, 2.0).is_some());
+        // Passive limits always pass, however far away
+        assert_eq!(limit_outside_band("buy", 80.0, 100.0, 2.0), None);
+        assert_eq!(limit_outside_band("sell", 120.0, 100.0, 2.0), None);
+        // Band of 0 turns the check off
+        assert_eq!(limit_outside_band("buy", 150.0, 100.0, 0.0), None);
+    }
+
+    #[test]
+    fn stats_report_nearest_rank_percentiles() {
+        // 0.1% .. 2.0%, recorded out of order
+        let mut values: Vec<f64> = (1..=20).map(|i| i as f64 / 10.0).collect();
+        values.reverse();
+        let stats = slippage_stats(&values).unwrap();
+
+        assert_eq!(stats.fills, 20);
+        assert!((stats.average_pct - 1.05).abs() < 1e-9);
+        assert_eq!(stats.p50_pct, 1.0);
+        assert_eq!(stats.p95_pct, 1.9);
+        assert_eq!(stats.worst_pct, 2.0);
+
+        assert_eq!(percentile(&[0.3], 95.0), Some(0.3));
+        assert!(slippage_stats(&[]).is_none());
+    }
+}
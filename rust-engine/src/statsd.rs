@@ -0,0 +1,108 @@
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+use crate::metrics::Metrics;
+use crate::throttle::RateLimiter;
+
+// Keeps datagrams under a typical MTU so nothing gets fragmented
+const MAX_DATAGRAM_BYTES: usize = 1400;
+
+// Pushes the /metrics series to a StatsD agent over UDP as gauges, with
+// labels as DogStatsD-style tags. Fire-and-forget: a missing agent just
+// drops the packets.
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl StatsdExporter {
+    pub async fn connect(addr: &str) -> anyhow::Result<Self> {
+        let target = tokio::net::lookup_host(addr).await?
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("{} did not resolve", addr))?;
+        let bind = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        Ok(Self { socket: UdpSocket::bind(bind).await?, target })
+    }
+
+    // One line per histogram count and sum, plus the Alpaca quota gauges
+    pub fn lines(metrics: &Metrics, limiter: &RateLimiter) -> Vec<String> {
+        let mut lines = metrics.statsd_lines();
+        for (category, quota) in limiter.quotas() {
+            if let Some(remaining) = quota.remaining {
+                lines.push(format!("ladybug_alpaca_quota_remaining:{}|g|#category:{}", remaining, category));
+            }
+            if let Some(limit) = quota.limit {
+                lines.push(format!("ladybug_alpaca_quota_limit:{}|g|#category:{}", limit, category));
+            }
+        }
+        lines
+    }
+
+    // Newline-joined lines, split across as many datagrams as needed
+    pub async fn send(&self, lines: &[String]) -> std::io::Result<()> {
+        let mut packet = String::new();
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_DATAGRAM_BYTES {
+                self.socket.send_to(packet.as_bytes(), self.target).await?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(line);
+        }
+        if !packet.is_empty() {
+            self.socket.send_to(packet.as_bytes(), self.target).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::ALPACA_REQUEST_SECONDS;
+    use crate::throttle::RateCategory;
+    use reqwest::header::HeaderMap;
+
+    #[tokio::test]
+    async fn gauges_reach_a_local_agent() {
+        let metrics = Metrics::new(true, vec![0.1, 1.0]);
+        metrics.observe(ALPACA_REQUEST_SECONDS, &[("endpoint", "bars")], 0.25);
+        let limiter = RateLimiter::new(&[]);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "150".parse().unwrap());
+        limiter.record_quota(RateCategory::Trading, &headers);
+
+        let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let exporter = StatsdExporter::connect(&agent.local_addr().unwrap().to_string()).await.unwrap();
+        let lines = StatsdExporter::lines(&metrics, &limiter);
+        exporter.send(&lines).await.unwrap();
+
+        let mut buf = [0u8; MAX_DATAGRAM_BYTES];
+        let len = agent.recv(&mut buf).await.unwrap();
+        let packet = std::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(packet.lines().collect::<Vec<_>>(), vec![
+            "ladybug_alpaca_request_seconds.count:1|g|#endpoint:bars",
+            "ladybug_alpaca_request_seconds.sum:0.25|g|#endpoint:bars",
+            "ladybug_alpaca_quota_remaining:150|g|#category:trading",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn long_batches_split_under_the_datagram_limit() {
+        let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let exporter = StatsdExporter::connect(&agent.local_addr().unwrap().to_string()).await.unwrap();
+        let lines: Vec<String> = (0..100).map(|i| format!("ladybug_test_gauge_{:03}:{}|g", i, i)).collect();
+        exporter.send(&lines).await.unwrap();
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 2 * MAX_DATAGRAM_BYTES];
+        while received.len() < lines.len() {
+            let len = agent.recv(&mut buf).await.unwrap();
+            assert!(len <= MAX_DATAGRAM_BYTES);
+            received.extend(std::str::from_utf8(&buf[..len]).unwrap().lines().map(String::from));
+        }
+        assert_eq!(received, lines);
+    }
+}
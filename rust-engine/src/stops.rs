@@ -0,0 +1,75 @@
+// Protective exits for an open long position. Before the trailing stop is
+// armed a fixed stop-loss below entry applies; once the position has been
+// up `activation_pct`, the stop trails `trail_pct` below the highest price
+// seen since, so it protects the gain instead of the entry.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopHit {
+    StopLoss { stop: f64 },
+    Trailing { stop: f64, high: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StopRules {
+    pub stop_loss_pct: f64,   // Below entry, 0 = no fixed stop
+    pub trail_pct: f64,       // Below the high-water mark, 0 = no trailing stop
+    pub activation_pct: f64,  // Gain the high must reach before trailing starts, 0 = from entry
+}
+
+impl StopRules {
+    // Whether the trailing stop is live for a position that has reached `high`
+    pub fn trailing_armed(&self, entry: f64, high: f64) -> bool {
+        self.trail_pct > 0.0 && entry > 0.0 && high >= entry * (1.0 + self.activation_pct / 100.0)
+    }
+
+    // The stop `price` has fallen through, if any
+    pub fn check(&self, entry: f64, high: f64, price: f64) -> Option<StopHit> {
+        if entry <= 0.0 || price <= 0.0 {
+            return None;
+        }
+        if self.trailing_armed(entry, high) {
+            let stop = high * (1.0 - self.trail_pct / 100.0);
+            return (price <= stop).then_some(StopHit::Trailing { stop, high });
+        }
+        if self.stop_loss_pct > 0.0 {
+            let stop = entry * (1.0 - self.stop_loss_pct / 100.0);
+            return (price <= stop).then_some(StopHit::StopLoss { stop });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: StopRules = StopRules { stop_loss_pct: 5.0, trail_pct: 3.0, activation_pct: 4.0 };
+
+    #[test]
+    fn fixed_stop_until_armed_then_the_stop_trails_the_high() {
+        // Up 2%: not armed, and a 1% dip is nowhere near the 5% stop-loss
+        assert!(!RULES.trailing_armed(100.0, 102.0));
+        assert_eq!(RULES.check(100.0, 102.0, 99.0), None);
+        assert_eq!(RULES.check(100.0, 102.0, 95.0), Some(StopHit::StopLoss { stop: 95.0 }));
+
+        // Up 4%: armed, 3% under the $104 high
+        assert!(RULES.trailing_armed(100.0, 104.0));
+        assert_eq!(RULES.check(100.0, 104.0, 101.0), None);
+        let Some(StopHit::Trailing { stop, high }) = RULES.check(100.0, 104.0, 100.8) else {
+            panic!("expected the trailing stop");
+        };
+        assert!((stop - 100.88).abs() < 1e-9);
+        assert_eq!(high, 104.0);
+
+        // A higher high lifts the stop with it
+        assert!(RULES.check(100.0, 110.0, 106.5).is_some());
+        assert_eq!(RULES.check(100.0, 110.0, 107.0), None);
+    }
+
+    #[test]
+    fn zero_percentages_turn_the_stops_off() {
+        let off = StopRules { stop_loss_pct: 0.0, trail_pct: 0.0, activation_pct: 0.0 };
+        assert!(!off.trailing_armed(100.0, 150.0));
+        assert_eq!(off.check(100.0, 150.0, 1.0), None);
+    }
+}
@@ -0,0 +1,47 @@
+// Alpaca names crypto pairs "BTC/USD" in market data and orders but "BTCUSD"
+// in positions. Anything that matches symbols across the two must compare
+// the normalized form.
+pub fn normalize(symbol: &str) -> String {
+    symbol.replace('/', "").to_uppercase()
+}
+
+pub fn same_symbol(a: &str, b: &str) -> bool {
+    normalize(a) == normalize(b)
+}
+
+// "BTC/USD" or Alpaca's position form "BTCUSD". Symbols starting with USD
+// and bare "USD" are not pairs.
+pub fn is_crypto_symbol(symbol: &str) -> bool {
+    symbol.contains('/')
+        || (symbol.ends_with("USD") && !symbol.starts_with("USD") && symbol.len() > 3)
+}
+
+pub fn asset_class(symbol: &str) -> &'static str {
+    if is_crypto_symbol(symbol) { "crypto" } else { "stock" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_and_market_data_forms_are_the_same_symbol() {
+        assert!(same_symbol("BTCUSD", "BTC/USD"));
+        assert!(same_symbol("btc/usd", "BTCUSD"));
+        assert!(!same_symbol("BTCUSD", "ETH/USD"));
+        assert_eq!(normalize("BTC/USD"), "BTCUSD");
+    }
+
+    #[test]
+    fn classifies_a_mixed_universe() {
+        for symbol in ["BTC/USD", "BTCUSD", "ETH/USD", "SOLUSD"] {
+            assert!(is_crypto_symbol(symbol), "{}", symbol);
+            assert_eq!(asset_class(symbol), "crypto");
+        }
+        // Tickers that merely contain or start with USD are stocks
+        for symbol in ["AAPL", "USD", "USDU", "USDC", "MSFT"] {
+            assert!(!is_crypto_symbol(symbol), "{}", symbol);
+            assert_eq!(asset_class(symbol), "stock");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::engine::TradeRecord;
+use crate::symbols;
+
+// Held longer than this is long-term
+const LONG_TERM_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LotMethod {
+    Fifo,
+    Lifo,
+}
+
+impl LotMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "fifo" => Some(LotMethod::Fifo),
+            "lifo" => Some(LotMethod::Lifo),
+            _ => None,
+        }
+    }
+}
+
+// A sale matched against (part of) one buy lot
+#[derive(Debug, Clone, Serialize)]
+pub struct RealizedLot {
+    pub symbol: String,
+    pub quantity: f64,
+    pub acquired: String,
+    pub sold: String,
+    pub cost_basis: f64,
+    pub proceeds: f64,
+    pub gain: f64,
+    pub holding_days: i64,
+    pub long_term: bool,
+}
+
+// Sold quantity with no buy in the history to match, e.g. a position opened
+// before the engine started recording
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmatchedSale {
+    pub symbol: String,
+    pub quantity: f64,
+    pub sold: String,
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    quantity: f64,
+    price: f64,
+    acquired: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LotReport {
+    pub realized: Vec<RealizedLot>,
+    pub unmatched: Vec<UnmatchedSale>,
+}
+
+// Confirmed fill when known, else the quoted price
+fn execution_price(trade: &TradeRecord) -> f64 {
+    trade.fill_price.unwrap_or(trade.price)
+}
+
+// Matches every SELL against earlier BUY lots of the same symbol, oldest
+// first (FIFO) or newest first (LIFO), splitting lots on partial closes.
+// `year` keeps only sales in that calendar year (UTC); matching still uses
+// the full history so earlier sales consume their lots.
+pub fn match_lots(trades: &[TradeRecord], method: LotMethod, year: Option<i32>) -> LotReport {
+    let mut ordered: Vec<(DateTime<Utc>, &TradeRecord)> = trades.iter()
+        .filter_map(|t| Some((DateTime::parse_from_rfc3339(&t.timestamp).ok()?.with_timezone(&Utc), t)))
+        .collect();
+    ordered.sort_by_key(|(t, _)| *t);
+
+    let mut open: HashMap<String, VecDeque<Lot>> = HashMap::new();
+    let mut report = LotReport::default();
+
+    for (at, trade) in ordered {
+        let lots = open.entry(symbols::normalize(&trade.symbol)).or_default();
+        if trade.action == "BUY" {
+            lots.push_back(Lot { quantity: trade.quantity, price: execution_price(trade), acquired: at });
+            continue;
+        }
+        if trade.action != "SELL" {
+            continue;
+        }
+
+        let in_year = year.is_none_or(|y| at.year() == y);
+        let sale_price = execution_price(trade);
+        let mut remaining = trade.quantity;
+        while remaining > 1e-12 {
+            let lot = match method {
+                LotMethod::Fifo => lots.front_mut(),
+                LotMethod::Lifo => lots.back_mut(),
+            };
+            let Some(lot) = lot else {
+                break;
+            };
+            let quantity = remaining.min(lot.quantity);
+            if in_year {
+                let holding_days = (at - lot.acquired).num_days();
+                report.realized.push(RealizedLot {
+                    symbol: trade.symbol.clone(),
+                    quantity,
+                    acquired: lot.acquired.to_rfc3339(),
+                    sold: at.to_rfc3339(),
+                    cost_basis: quantity * lot.price,
+                    proceeds: quantity * sale_price,
+                    gain: quantity * (sale_price - lot.price),
+                    holding_days,
+                    long_term: holding_days > LONG_TERM_DAYS,
+                });
+            }
+            lot.quantity -= quantity;
+            remaining -= quantity;
+            if lot.quantity <= 1e-12 {
+                match method {
+                    LotMethod::Fifo => lots.pop_front(),
+                    LotMethod::Lifo => lots.pop_back(),
+                };
+            }
+        }
+        if remaining > 1e-12 && in_year {
+            report.unmatched.push(UnmatchedSale { symbol: trade.symbol.clone(), quantity: remaining, sold: at.to_rfc3339() });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::trade;
+    use chrono::TimeZone;
+
+    fn day(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 15, 0, 0).unwrap()
+    }
+
+    // Two buys, a partial sale, a third buy, then a sale spanning lots
+    fn interleaved() -> Vec<TradeRecord> {
+        vec![
+            trade("AAPL", "BUY", 10.0, 100.0, day(2023, 1, 10)),
+            trade("AAPL", "BUY", 10.0, 120.0, day(2023, 6, 1)),
+            trade("AAPL", "SELL", 5.0, 130.0, day(2023, 7, 1)),
+            trade("AAPL", "BUY", 10.0, 110.0, day(2023, 8, 1)),
+            trade("AAPL", "SELL", 15.0, 140.0, day(2024, 3, 1)),
+        ]
+    }
+
+    fn matched(report: &LotReport) -> Vec<(f64, f64, bool)> {
+        report.realized.iter().map(|lot| (lot.quantity, lot.gain, lot.long_term)).collect()
+    }
+
+    #[test]
+    fn fifo_sells_the_oldest_lots_first() {
+        let report = match_lots(&interleaved(), LotMethod::Fifo, None);
+        assert_eq!(matched(&report), vec![(5.0, 150.0, false), (5.0, 200.0, true), (10.0, 200.0, false)]);
+        assert!(report.unmatched.is_empty());
+    }
+
+    #[test]
+    fn lifo_sells_the_newest_lots_first() {
+        let report = match_lots(&interleaved(), LotMethod::Lifo, None);
+        assert_eq!(matched(&report), vec![(5.0, 50.0, false), (10.0, 300.0, false), (5.0, 100.0, false)]);
+    }
+
+    #[test]
+    fn year_filter_still_consumes_earlier_lots() {
+        let mut trades = interleaved();
+        trades.push(trade("AAPL", "SELL", 20.0, 150.0, day(2024, 4, 1)));
+
+        let report = match_lots(&trades, LotMethod::Fifo, Some(2024));
+        assert!(report.realized.iter().all(|lot| lot.sold.starts_with("2024")));
+        assert_eq!(report.realized.iter().map(|lot| lot.quantity).sum::<f64>(), 25.0);
+        assert_eq!(report.unmatched.len(), 1);
+        assert_eq!(report.unmatched[0].quantity, 10.0);
+    }
+}
@@ -1,7 +1,67 @@
+use anyhow::Result;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
 use crate::alpaca::Bar;
+use crate::config::{IndicatorConfig, RegimeThresholds};
 
 pub struct TechnicalAnalysis;
 
+// Average used for the 20/50 crossover. `sma` weights every bar in the window
+// equally; `wma` weights them linearly (newest = period, oldest = 1), so it
+// turns sooner after a trend change - earlier crossovers, but more whipsaw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovingAverage {
+    #[default]
+    Sma,
+    Wma,
+}
+
+impl FromStr for MovingAverage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sma" => Ok(MovingAverage::Sma),
+            "wma" | "lwma" => Ok(MovingAverage::Wma),
+            other => anyhow::bail!("Unknown moving average: {}", other),
+        }
+    }
+}
+
+// What kind of market a symbol is in, from ADX and Bollinger band width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MarketRegime {
+    Trending,  // Directional: momentum logic fits
+    Ranging,   // Quiet and sideways: mean reversion fits
+    Volatile,  // Wide swings without a direction
+}
+
+// Per-component contributions to a signal; `total` is the clamped sum
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SignalExplanation {
+    pub rsi: Option<f64>,  // Raw RSI value, if there were enough bars
+    pub rsi_score: f64,
+    pub crossover_score: f64,
+    pub momentum_score: f64,
+    pub sentiment_score: f64,
+    pub noise: f64,
+    pub total: f64,
+    pub confidence: f64,  // 0-1, see signal_confidence
+    pub available: Vec<String>,          // Indicators that had enough bars
+    pub omitted: Vec<OmittedIndicator>,  // Ones that scored 0 for lack of history
+}
+
+// An indicator left out of a signal because there weren't enough bars
+#[derive(Debug, Clone, Serialize)]
+pub struct OmittedIndicator {
+    pub name: String,
+    pub bars_needed: usize,
+    pub bars_available: usize,
+}
+
 impl TechnicalAnalysis {
     pub fn calculate_rsi(bars: &[Bar], period: usize) -> Option<f64> {
         if bars.len() < period + 1 {
@@ -42,7 +102,27 @@ impl TechnicalAnalysis {
         Some(sum / period as f64)
     }
 
-    #[allow(dead_code)]
+    // Linearly weighted: the newest bar counts `period` times, the oldest once
+    pub fn calculate_wma(bars: &[Bar], period: usize) -> Option<f64> {
+        if period == 0 || bars.len() < period {
+            return None;
+        }
+
+        let weighted: f64 = bars[bars.len() - period..].iter()
+            .enumerate()
+            .map(|(i, b)| b.c * (i + 1) as f64)
+            .sum();
+        let weight_sum = (period * (period + 1) / 2) as f64;
+        Some(weighted / weight_sum)
+    }
+
+    pub fn moving_average(bars: &[Bar], period: usize, kind: MovingAverage) -> Option<f64> {
+        match kind {
+            MovingAverage::Sma => Self::calculate_sma(bars, period),
+            MovingAverage::Wma => Self::calculate_wma(bars, period),
+        }
+    }
+
     pub fn calculate_ema(bars: &[Bar], period: usize) -> Option<f64> {
         if bars.len() < period {
             return None;
@@ -58,46 +138,408 @@ impl TechnicalAnalysis {
         Some(ema)
     }
 
-    pub fn generate_signal(bars: &[Bar], sentiment: f64) -> f64 {
-        // Lowered requirement from 50 to 20 bars for more activity
-        if bars.len() < 20 {
+    // Average True Range: mean of max(high-low, |high-prev close|, |low-prev close|)
+    // over the last `period` bars
+    pub fn calculate_atr(bars: &[Bar], period: usize) -> Option<f64> {
+        if period == 0 || bars.len() < period + 1 {
+            return None;
+        }
+
+        let true_ranges: Vec<f64> = bars.windows(2)
+            .map(|w| {
+                let (prev, bar) = (&w[0], &w[1]);
+                (bar.h - bar.l)
+                    .max((bar.h - prev.c).abs())
+                    .max((bar.l - prev.c).abs())
+            })
+            .collect();
+
+        Some(true_ranges.iter().rev().take(period).sum::<f64>() / period as f64)
+    }
+
+    // ATR as a percent of the last close, comparable across price levels
+    pub fn atr_pct(bars: &[Bar], period: usize) -> Option<f64> {
+        let atr = Self::calculate_atr(bars, period)?;
+        let close = bars.last()?.c;
+        (close > 0.0).then(|| atr / close * 100.0)
+    }
+
+    // Wilder's Average Directional Index over `period`: trend strength from
+    // 0 to 100 regardless of direction. Needs 2 x period + 1 bars.
+    pub fn calculate_adx(bars: &[Bar], period: usize) -> Option<f64> {
+        if period == 0 || bars.len() < 2 * period + 1 {
+            return None;
+        }
+
+        let mut tr = Vec::with_capacity(bars.len() - 1);
+        let mut plus_dm = Vec::with_capacity(bars.len() - 1);
+        let mut minus_dm = Vec::with_capacity(bars.len() - 1);
+        for w in bars.windows(2) {
+            let (prev, bar) = (&w[0], &w[1]);
+            tr.push((bar.h - bar.l).max((bar.h - prev.c).abs()).max((bar.l - prev.c).abs()));
+            let up = bar.h - prev.h;
+            let down = prev.l - bar.l;
+            plus_dm.push(if up > down && up > 0.0 { up } else { 0.0 });
+            minus_dm.push(if down > up && down > 0.0 { down } else { 0.0 });
+        }
+
+        // Wilder smoothing: seed with the first `period` sum, then roll
+        let n = period as f64;
+        let mut tr_s: f64 = tr[..period].iter().sum();
+        let mut plus_s: f64 = plus_dm[..period].iter().sum();
+        let mut minus_s: f64 = minus_dm[..period].iter().sum();
+        let dx = |tr_s: f64, plus_s: f64, minus_s: f64| {
+            if tr_s <= 0.0 {
+                return 0.0;
+            }
+            let (plus_di, minus_di) = (100.0 * plus_s / tr_s, 100.0 * minus_s / tr_s);
+            let sum = plus_di + minus_di;
+            if sum <= 0.0 { 0.0 } else { 100.0 * (plus_di - minus_di).abs() / sum }
+        };
+
+        let mut dxs = vec![dx(tr_s, plus_s, minus_s)];
+        for i in period..tr.len() {
+            tr_s = tr_s - tr_s / n + tr[i];
+            plus_s = plus_s - plus_s / n + plus_dm[i];
+            minus_s = minus_s - minus_s / n + minus_dm[i];
+            dxs.push(dx(tr_s, plus_s, minus_s));
+        }
+
+        let mut adx = dxs[..period].iter().sum::<f64>() / n;
+        for value in &dxs[period..] {
+            adx = (adx * (n - 1.0) + value) / n;
+        }
+        Some(adx)
+    }
+
+    // Bollinger band width as a percent of the middle band: the bands sit
+    // `k` standard deviations either side of the `period`-bar SMA
+    pub fn bollinger_width_pct(bars: &[Bar], period: usize, k: f64) -> Option<f64> {
+        let mean = Self::calculate_sma(bars, period)?;
+        if mean <= 0.0 {
+            return None;
+        }
+        let variance = bars.iter().rev().take(period).map(|b| (b.c - mean).powi(2)).sum::<f64>() / period as f64;
+        Some(2.0 * k * variance.sqrt() / mean * 100.0)
+    }
+
+    // Trending if ADX clears the trend cutoff, else Volatile if the bands are
+    // wide, else Ranging. None without enough bars for ADX.
+    pub fn classify_regime(bars: &[Bar], thresholds: &RegimeThresholds) -> Option<MarketRegime> {
+        let adx = Self::calculate_adx(bars, thresholds.adx_period)?;
+        if adx >= thresholds.trend_adx {
+            return Some(MarketRegime::Trending);
+        }
+        let width = Self::bollinger_width_pct(bars, 20, 2.0)?;
+        if width >= thresholds.volatile_width_pct {
+            Some(MarketRegime::Volatile)
+        } else {
+            Some(MarketRegime::Ranging)
+        }
+    }
+
+    // Weighted average of the fractional price change over several lookback
+    // windows, so no single window choice dominates. Windows longer than the
+    // available history are skipped; weights default to equal.
+    pub fn calculate_momentum(bars: &[Bar], windows: &[usize], weights: &[f64]) -> Option<f64> {
+        let use_weights = weights.len() == windows.len();
+        let mut total = 0.0;
+        let mut weight_sum = 0.0;
+
+        for (i, &window) in windows.iter().enumerate() {
+            if window == 0 || bars.len() < window {
+                continue;
+            }
+            let past = bars[bars.len() - window].c;
+            if past <= 0.0 {
+                continue;
+            }
+            let change = (bars[bars.len() - 1].c - past) / past;
+            let weight = if use_weights { weights[i] } else { 1.0 };
+            total += change * weight;
+            weight_sum += weight;
+        }
+
+        if weight_sum > 0.0 {
+            Some(total / weight_sum)
+        } else {
+            None
+        }
+    }
+
+    // One value per bar: `indicator` applied to every prefix of `bars`, so
+    // entry i is what a signal computed at bar i would have seen
+    pub fn series(bars: &[Bar], indicator: impl Fn(&[Bar]) -> Option<f64>) -> Vec<Option<f64>> {
+        (1..=bars.len()).map(|n| indicator(&bars[..n])).collect()
+    }
+
+    // MACD line (EMA 12 - EMA 26) and its 9-period EMA signal line, per bar
+    pub fn macd_series(bars: &[Bar]) -> (Vec<Option<f64>>, Vec<Option<f64>>) {
+        let macd: Vec<Option<f64>> = Self::series(bars, |b| {
+            Some(Self::calculate_ema(b, 12)? - Self::calculate_ema(b, 26)?)
+        });
+
+        let multiplier = 2.0 / (9.0 + 1.0);
+        let mut seed = Vec::new();
+        let mut ema: Option<f64> = None;
+        let signal = macd.iter()
+            .map(|value| {
+                let value = (*value)?;
+                ema = match ema {
+                    Some(prev) => Some((value - prev) * multiplier + prev),
+                    None => {
+                        seed.push(value);
+                        (seed.len() == 9).then(|| seed.iter().sum::<f64>() / 9.0)
+                    }
+                };
+                ema
+            })
+            .collect();
+        (macd, signal)
+    }
+
+    // How strongly the indicator components back the final score: the share
+    // of components pointing the same way as `total`, times the share of the
+    // components' combined magnitude they carry. Noise is left out, so a
+    // score that only cleared the threshold on noise reads as low conviction.
+    pub fn signal_confidence(components: &[f64], total: f64) -> f64 {
+        if total == 0.0 || components.is_empty() {
+            return 0.0;
+        }
+        let magnitude: f64 = components.iter().map(|c| c.abs()).sum();
+        if magnitude == 0.0 {
             return 0.0;
         }
+        let agreeing: Vec<f64> = components.iter()
+            .copied()
+            .filter(|c| *c != 0.0 && c.signum() == total.signum())
+            .collect();
+        let breadth = agreeing.len() as f64 / components.len() as f64;
+        let strength = agreeing.iter().map(|c| c.abs()).sum::<f64>() / magnitude;
+        breadth * strength
+    }
+
+    // `rng` drives the synthetic boost below; pass a seeded one for reproducible runs
+    pub fn generate_signal(bars: &[Bar], sentiment: f64, config: &IndicatorConfig, rng: &mut impl Rng) -> f64 {
+        Self::explain_signal(bars, sentiment, config, rng).total
+    }
 
-        let mut score = 0.0;
+    // explain_signal over only the most recent `window` bars (all of them if
+    // there are fewer), to compare lookbacks on the same data. Under 50 bars
+    // the 20/50 crossover can't be computed and scores 0; under 20 nothing does.
+    pub fn explain_signal_window(bars: &[Bar], window: usize, sentiment: f64, config: &IndicatorConfig, rng: &mut impl Rng) -> SignalExplanation {
+        Self::explain_signal(&bars[bars.len().saturating_sub(window)..], sentiment, config, rng)
+    }
+
+    // Bars each signal component needs, named as /indicators names them.
+    // Nothing scores under 20 bars, so no requirement is lower than that.
+    pub fn indicator_requirements(config: &IndicatorConfig) -> Vec<(String, usize)> {
+        let average = match config.crossover_average {
+            MovingAverage::Sma => "sma",
+            MovingAverage::Wma => "wma",
+        };
+        let mut requirements = vec![
+            ("rsi".to_string(), 15),
+            (format!("{}_20", average), 20),
+            (format!("{}_50", average), 50),
+        ];
+        requirements.extend(config.momentum_windows.iter()
+            .filter(|w| **w > 0)
+            .map(|w| (format!("momentum_{}", w), *w)));
+        requirements.into_iter().map(|(name, needed)| (name, needed.max(20))).collect()
+    }
+
+    // Same scoring as generate_signal, keeping each component
+    pub fn explain_signal(bars: &[Bar], sentiment: f64, config: &IndicatorConfig, rng: &mut impl Rng) -> SignalExplanation {
+        let mut explanation = SignalExplanation::default();
+        for (name, needed) in Self::indicator_requirements(config) {
+            if bars.len() >= needed {
+                explanation.available.push(name);
+            } else {
+                explanation.omitted.push(OmittedIndicator { name, bars_needed: needed, bars_available: bars.len() });
+            }
+        }
+        
+        // Lowered requirement from 50 to 20 bars for more activity
+        if bars.len() < 20 {
+            return explanation;
+        }
 
         // RSI
-        if let Some(rsi) = Self::calculate_rsi(bars, 14) {
+        explanation.rsi = Self::calculate_rsi(bars, 14);
+        if let Some(rsi) = explanation.rsi {
             if rsi < 30.0 {
-                score += 0.3; // Oversold - bullish
+                explanation.rsi_score = 0.3; // Oversold - bullish
             } else if rsi > 70.0 {
-                score -= 0.3; // Overbought - bearish
+                explanation.rsi_score = -0.3; // Overbought - bearish
             }
         }
 
         // Moving average crossover
-        if let (Some(sma_20), Some(sma_50)) = (Self::calculate_sma(bars, 20), Self::calculate_sma(bars, 50)) {
-            if sma_20 > sma_50 {
-                score += 0.2; // Bullish crossover
+        let average = config.crossover_average;
+        if let (Some(fast), Some(slow)) = (Self::moving_average(bars, 20, average), Self::moving_average(bars, 50, average)) {
+            if fast > slow {
+                explanation.crossover_score = 0.2; // Bullish crossover
             } else {
-                score -= 0.2; // Bearish crossover
+                explanation.crossover_score = -0.2; // Bearish crossover
             }
         }
 
-        // Price momentum
-        if bars.len() >= 10 {
-            let recent_change = (bars[bars.len() - 1].c - bars[bars.len() - 10].c) / bars[bars.len() - 10].c;
-            score += recent_change.clamp(-0.3, 0.3);
+        // Price momentum (multi-window)
+        if let Some(momentum) = Self::calculate_momentum(bars, &config.momentum_windows, &config.momentum_weights) {
+            explanation.momentum_score = momentum.clamp(-0.3, 0.3);
         }
 
         // News sentiment
-        score += sentiment * 0.2;
+        explanation.sentiment_score = sentiment * 0.2;
 
         // AGGRESSIVE: Add synthetic momentum for demonstration
         // This ensures we ALWAYS get trading activity
-        let momentum_boost = (rand::random::<f64>() - 0.5) * 0.25; // -0.125 to +0.125
-        score += momentum_boost;
+        explanation.noise = (rng.gen::<f64>() - 0.5) * 0.25; // -0.125 to +0.125
+
+        explanation.total = (explanation.rsi_score
+            + explanation.crossover_score
+            + explanation.momentum_score
+            + explanation.sentiment_score
+            + explanation.noise)
+            .clamp(-1.0, 1.0);
+        explanation.confidence = Self::signal_confidence(
+            &[explanation.rsi_score, explanation.crossover_score, explanation.momentum_score, explanation.sentiment_score],
+            explanation.total,
+        );
+        explanation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars(closes: &[f64]) -> Vec<Bar> {
+        closes.iter()
+            .map(|&c| Bar { t: String::new(), o: c, h: c + 0.5, l: c - 0.5, c, v: 1_000 })
+            .collect()
+    }
+
+    #[test]
+    fn trending_series_has_positive_multi_window_momentum() {
+        let closes: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let momentum = TechnicalAnalysis::calculate_momentum(&bars(&closes), &[5, 10, 20], &[]).unwrap();
+        assert!(momentum > 0.05, "momentum {}", momentum);
+    }
+
+    #[test]
+    fn choppy_series_has_near_zero_momentum() {
+        let closes: Vec<f64> = (0..30).map(|i| if i % 2 == 0 { 100.0 } else { 101.0 }).collect();
+        let momentum = TechnicalAnalysis::calculate_momentum(&bars(&closes), &[5, 10, 20], &[]).unwrap();
+        assert!(momentum.abs() < 0.01, "momentum {}", momentum);
+    }
+
+    #[test]
+    fn momentum_skips_windows_longer_than_history() {
+        let closes: Vec<f64> = (0..8).map(|i| 100.0 + i as f64).collect();
+        let short = TechnicalAnalysis::calculate_momentum(&bars(&closes), &[5], &[]).unwrap();
+        let mixed = TechnicalAnalysis::calculate_momentum(&bars(&closes), &[5, 20], &[1.0, 3.0]).unwrap();
+        assert_eq!(short, mixed);
+        assert!(TechnicalAnalysis::calculate_momentum(&bars(&closes), &[20], &[]).is_none());
+    }
+
+    #[test]
+    fn wma_reacts_to_a_turn_before_sma() {
+        // Flat, then a jump over the last three bars
+        let mut closes = vec![100.0; 20];
+        closes.extend([104.0, 108.0, 112.0]);
+        let bars = bars(&closes);
+
+        let sma = TechnicalAnalysis::calculate_sma(&bars, 10).unwrap();
+        let wma = TechnicalAnalysis::calculate_wma(&bars, 10).unwrap();
+        assert!((sma - 102.4).abs() < 1e-9);
+        // (104*8 + 108*9 + 112*10 + 100*(1+..+7)) / 55
+        assert!((wma - 5724.0 / 55.0).abs() < 1e-9);
+        assert!(wma > sma);
+        assert_eq!(TechnicalAnalysis::calculate_wma(&bars[..5], 10), None);
+    }
+
+    #[test]
+    fn rsi_series_has_a_value_per_bar_once_warmed_up() {
+        // 15 rising closes, then 5 falling
+        let closes: Vec<f64> = (0..20).map(|i| if i < 15 { 100.0 + i as f64 } else { 114.0 - (i - 14) as f64 }).collect();
+        let series = TechnicalAnalysis::series(&bars(&closes), |b| TechnicalAnalysis::calculate_rsi(b, 14));
+
+        assert_eq!(series.len(), closes.len());
+        assert!(series[..14].iter().all(Option::is_none));
+        assert_eq!(series[14], Some(100.0));
+        // 13 gains and one loss of the same size
+        assert!((series[15].unwrap() - (100.0 - 100.0 / 14.0)).abs() < 1e-9);
+        assert_eq!(series[19], TechnicalAnalysis::calculate_rsi(&bars(&closes), 14));
+        assert!(series[15..].windows(2).all(|w| w[1] < w[0]));
+    }
+
+    #[test]
+    fn explanation_breaks_the_signal_into_its_components() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        let config = crate::config::Config::from_env().indicators;
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0);
+        let explanation = TechnicalAnalysis::explain_signal(&bars(&closes), 0.5, &config, &mut rng);
+
+        assert!(explanation.rsi.unwrap() > 70.0);
+        assert_eq!(explanation.rsi_score, -0.3);
+        assert_eq!(explanation.crossover_score, 0.2);
+        assert!(explanation.momentum_score > 0.0);
+        assert!((explanation.sentiment_score - 0.1).abs() < 1e-9);
+        let sum = explanation.rsi_score + explanation.crossover_score + explanation.momentum_score
+            + explanation.sentiment_score + explanation.noise;
+        assert!((explanation.total - sum).abs() < 1e-9);
+        assert!(explanation.omitted.is_empty());
+    }
+
+    #[test]
+    fn more_agreeing_components_mean_more_confidence() {
+        let total = 0.4;
+        let unanimous = TechnicalAnalysis::signal_confidence(&[0.1, 0.1, 0.1, 0.1], total);
+        let split = TechnicalAnalysis::signal_confidence(&[0.2, 0.2, -0.1, 0.1], total);
+        let lone = TechnicalAnalysis::signal_confidence(&[0.5, -0.1, 0.0, 0.0], total);
+
+        assert_eq!(unanimous, 1.0);
+        assert!(unanimous > split && split > lone, "{} {} {}", unanimous, split, lone);
+        assert_eq!(TechnicalAnalysis::signal_confidence(&[0.1, -0.1], 0.0), 0.0);
+    }
+
+    #[test]
+    fn steady_climb_is_trending_and_chop_is_ranging() {
+        let thresholds = RegimeThresholds::default();
+        let climb: Vec<f64> = (0..40).map(|i| 100.0 + i as f64).collect();
+        let chop: Vec<f64> = (0..40).map(|i| if i % 2 == 0 { 100.0 } else { 101.0 }).collect();
+
+        assert_eq!(TechnicalAnalysis::classify_regime(&bars(&climb), &thresholds), Some(MarketRegime::Trending));
+        assert_eq!(TechnicalAnalysis::classify_regime(&bars(&chop), &thresholds), Some(MarketRegime::Ranging));
+        // ADX needs 2 x 14 + 1 bars
+        assert_eq!(TechnicalAnalysis::classify_regime(&bars(&chop[..28]), &thresholds), None);
+    }
+
+    #[test]
+    fn short_history_omits_only_what_it_cannot_compute() {
+        let closes: Vec<f64> = (0..25).map(|i| 100.0 + i as f64).collect();
+        let config = crate::config::Config::from_env().indicators;
+        let mut rng = rand::rngs::mock::StepRng::new(0, 0);
+        let explanation = TechnicalAnalysis::explain_signal(&bars(&closes), 0.0, &config, &mut rng);
+
+        let sma_50 = explanation.omitted.iter().find(|o| o.name == "sma_50").unwrap();
+        assert_eq!((sma_50.bars_needed, sma_50.bars_available), (50, 25));
+        assert!(explanation.available.iter().any(|name| name == "sma_20"));
+        assert!(explanation.available.iter().any(|name| name == "rsi"));
+        assert_eq!(explanation.crossover_score, 0.0);
+        // Everything needs at least 20 bars
+        assert!(TechnicalAnalysis::indicator_requirements(&config).iter().all(|(_, needed)| *needed >= 20));
+    }
 
-        score.clamp(-1.0, 1.0)
+    #[test]
+    fn atr_pct_compares_volatility_across_price_levels() {
+        // The same $1 true range on a $100 and a $200 stock
+        let cheap = TechnicalAnalysis::atr_pct(&bars(&[100.0; 15]), 14).unwrap();
+        let pricey = TechnicalAnalysis::atr_pct(&bars(&[200.0; 15]), 14).unwrap();
+        assert!((cheap - 1.0).abs() < 1e-9);
+        assert!((pricey - 0.5).abs() < 1e-9);
+        assert_eq!(TechnicalAnalysis::atr_pct(&bars(&[100.0; 14]), 14), None);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,60 @@
+// Shared by the unit tests that talk HTTP: a throwaway server on a local port
+// standing in for Alpaca or a webhook receiver
+
+use axum::Router;
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use crate::engine::{Engine, TradeReason, TradeRecord};
+use crate::symbols;
+
+// Serves `app` in the background and returns its base URL
+pub async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+// Config from the environment defaults, pointed at `host` for both Alpaca
+// APIs, with persistence in a fresh temp dir and nothing calling out
+pub fn config(host: &str) -> Config {
+    let dir = std::env::temp_dir().join(format!("ladybug-test-{}", uuid::Uuid::new_v4()));
+    let mut config = Config::from_env();
+    config.credentials.api_key = "test-key".to_string();
+    config.credentials.api_secret = "test-secret".to_string();
+    config.data.trading_host = host.to_string();
+    config.data.data_host = host.to_string();
+    config.persistence.history_dir = dir.join("history").display().to_string();
+    config.persistence.notes_path = dir.join("notes.json").display().to_string();
+    config.persistence.profiles_dir = dir.join("profiles").display().to_string();
+    config.persistence.config_history_path = dir.join("config_history.json").display().to_string();
+    config.recorder.enabled = false;
+    config.approval.webhook_url = String::new();
+    config
+}
+
+// An engine whose Alpaca calls go to `host`; use "http://127.0.0.1:9" when
+// the test shouldn't reach Alpaca at all
+pub fn engine(host: &str) -> Engine {
+    Engine::new(config(host))
+}
+
+// A filled, signal-driven trade with no P&L; tests override the rest with
+// `TradeRecord { pnl, ..trade(..) }`
+pub fn trade(symbol: &str, action: &str, quantity: f64, price: f64, at: DateTime<Utc>) -> TradeRecord {
+    TradeRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: at.to_rfc3339(),
+        symbol: symbol.to_string(),
+        action: action.to_string(),
+        quantity,
+        price,
+        pnl: 0.0,
+        fill_price: None,
+        slippage_flagged: false,
+        slippage_pct: None,
+        reason: Some(TradeReason::Signal),
+        asset_class: symbols::asset_class(symbol).to_string(),
+    }
+}
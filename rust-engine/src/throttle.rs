@@ -0,0 +1,241 @@
+use chrono::{DateTime, TimeZone, Utc};
+use dashmap::DashMap;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+// Minimum spacing between any two order submissions, shared by the stock and
+// crypto clients. Separate from API request limits: this is about order
+// cadence, so a burst of signals doesn't turn into a burst of orders.
+#[derive(Clone, Default)]
+pub struct OrderThrottle {
+    min_interval: Duration,
+    last: Arc<Mutex<Option<Instant>>>,
+}
+
+impl OrderThrottle {
+    pub fn new(min_interval: Duration) -> Self {
+        Self { min_interval, last: Arc::new(Mutex::new(None)) }
+    }
+
+    // Waits until `min_interval` has passed since the previous submission and
+    // claims the slot. The lock is held while sleeping, so orders queue up
+    // and go out in arrival order.
+    pub async fn wait(&self, symbol: &str) {
+        if self.min_interval.is_zero() {
+            return;
+        }
+        let mut last = self.last.lock().await;
+        if let Some(previous) = *last {
+            let ready = previous + self.min_interval;
+            let now = Instant::now();
+            if ready > now {
+                tracing::info!("🚦 {} order deferred {}ms to keep orders {}ms apart",
+                               symbol, (ready - now).as_millis(), self.min_interval.as_millis());
+                tokio::time::sleep_until(ready).await;
+            }
+        }
+        *last = Some(Instant::now());
+    }
+}
+
+// Alpaca limits each endpoint category separately, so each gets its own bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateCategory {
+    StockData,
+    CryptoData,
+    Trading,
+}
+
+impl RateCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateCategory::StockData => "stock-data",
+            RateCategory::CryptoData => "crypto-data",
+            RateCategory::Trading => "trading",
+        }
+    }
+}
+
+// Alpaca's own view of a category's quota, from the X-RateLimit-* headers
+// on the most recent response
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Quota {
+    pub limit: Option<u32>,
+    pub remaining: Option<u32>,
+    pub reset: Option<DateTime<Utc>>,  // When `remaining` refills
+    pub observed_at: DateTime<Utc>,
+}
+
+// None when the response carries none of the headers
+pub fn parse_quota(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Quota> {
+    let number = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<i64>().ok();
+    let limit = number("x-ratelimit-limit");
+    let remaining = number("x-ratelimit-remaining");
+    let reset = number("x-ratelimit-reset");
+    if limit.is_none() && remaining.is_none() && reset.is_none() {
+        return None;
+    }
+    Some(Quota {
+        limit: limit.and_then(|v| u32::try_from(v).ok()),
+        remaining: remaining.and_then(|v| u32::try_from(v).ok()),
+        reset: reset.and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+        observed_at: now,
+    })
+}
+
+// Longest we'll sit out an exhausted quota; a reset further off than this is
+// more likely a bad header than a real wait
+const MAX_QUOTA_WAIT: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+    }
+}
+
+// Token bucket per RateCategory, shared by the stock and crypto clients.
+// Each refills at its own per-minute rate and can burst a full minute's
+// worth; a category with no bucket (rate 0) is unlimited. Calls in one
+// category never draw on another's tokens.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<HashMap<RateCategory, Mutex<Bucket>>>,
+    quotas: Arc<DashMap<RateCategory, Quota>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: &[(RateCategory, u32)]) -> Self {
+        let now = Instant::now();
+        let buckets = limits.iter()
+            .filter(|(_, per_min)| *per_min > 0)
+            .map(|&(category, per_min)| {
+                let capacity = per_min as f64;
+                (category, Mutex::new(Bucket { capacity, tokens: capacity, per_sec: capacity / 60.0, updated: now }))
+            })
+            .collect();
+        Self { buckets: Arc::new(buckets), quotas: Arc::new(DashMap::new()) }
+    }
+
+    // Keeps the quota Alpaca reported on a response, if it sent one
+    pub fn record_quota(&self, category: RateCategory, headers: &HeaderMap) {
+        if let Some(quota) = parse_quota(headers, Utc::now()) {
+            self.quotas.insert(category, quota);
+        }
+    }
+
+    pub fn quotas(&self) -> BTreeMap<&'static str, Quota> {
+        self.quotas.iter().map(|q| (q.key().as_str(), *q.value())).collect()
+    }
+
+    // Time left until an exhausted quota resets, capped at MAX_QUOTA_WAIT
+    fn quota_wait(&self, category: RateCategory) -> Option<Duration> {
+        let quota = *self.quotas.get(&category)?;
+        if quota.remaining != Some(0) {
+            return None;
+        }
+        let wait = (quota.reset? - Utc::now()).to_std().ok()?;
+        Some(wait.min(MAX_QUOTA_WAIT))
+    }
+
+    // Takes one token from `category`'s bucket, waiting for a refill if it's
+    // empty. Like OrderThrottle the lock is held while sleeping, so callers
+    // in the same category go out in arrival order. If Alpaca last reported
+    // the quota as used up, waits for its reset first - the headers are the
+    // real count, the bucket only an estimate.
+    pub async fn acquire(&self, category: RateCategory) {
+        if let Some(wait) = self.quota_wait(category) {
+            tracing::info!("🚦 {} quota exhausted, waiting {}ms for the reset", category.as_str(), wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+        let Some(bucket) = self.buckets.get(&category) else {
+            return;
+        };
+        let mut bucket = bucket.lock().await;
+        bucket.refill(Instant::now());
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.per_sec);
+            tracing::debug!("🚦 {} request deferred {}ms by the rate limit", category.as_str(), wait.as_millis());
+            tokio::time::sleep(wait).await;
+            bucket.refill(Instant::now());
+        }
+        bucket.tokens -= 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn back_to_back_orders_are_spaced_by_the_interval() {
+        let throttle = OrderThrottle::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        throttle.wait("AAPL").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        throttle.wait("MSFT").await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+        // Enough time has already passed
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let before = Instant::now();
+        throttle.wait("NVDA").await;
+        assert_eq!(before.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn categories_draw_on_separate_buckets() {
+        let limiter = RateLimiter::new(&[(RateCategory::StockData, 2), (RateCategory::Trading, 2)]);
+        let start = Instant::now();
+
+        limiter.acquire(RateCategory::StockData).await;
+        limiter.acquire(RateCategory::StockData).await;
+        // Stock data is drained; trading still has its full burst
+        limiter.acquire(RateCategory::Trading).await;
+        limiter.acquire(RateCategory::Trading).await;
+        // No bucket at all, so never waits
+        limiter.acquire(RateCategory::CryptoData).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // 2 per minute refills one token every 30s
+        limiter.acquire(RateCategory::StockData).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn quota_comes_from_the_rate_limit_headers() {
+        let now = Utc::now();
+        let mut headers = HeaderMap::new();
+        assert!(parse_quota(&headers, now).is_none());
+
+        headers.insert("X-RateLimit-Limit", "200".parse().unwrap());
+        headers.insert("X-RateLimit-Remaining", " 0 ".parse().unwrap());
+        headers.insert("X-RateLimit-Reset", "1700000000".parse().unwrap());
+        let quota = parse_quota(&headers, now).unwrap();
+        assert_eq!(quota.limit, Some(200));
+        assert_eq!(quota.remaining, Some(0));
+        assert_eq!(quota.reset, Utc.timestamp_opt(1_700_000_000, 0).single());
+        assert_eq!(quota.observed_at, now);
+
+        // A garbled header is dropped, the rest still count
+        headers.insert("X-RateLimit-Remaining", "-3".parse().unwrap());
+        headers.insert("X-RateLimit-Limit", "lots".parse().unwrap());
+        let quota = parse_quota(&headers, now).unwrap();
+        assert_eq!((quota.limit, quota.remaining), (None, None));
+        assert!(quota.reset.is_some());
+    }
+}
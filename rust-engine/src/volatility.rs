@@ -0,0 +1,30 @@
+use dashmap::DashMap;
+use std::sync::Arc;
+
+// Sample standard deviation; None with fewer than two values
+pub fn stdev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(var.sqrt())
+}
+
+// Whether stocks and crypto are each in a high-volatility regime, as decided
+// at the start of their cycles
+#[derive(Clone, Default)]
+pub struct VolatilityRegime {
+    high: Arc<DashMap<bool, bool>>,  // is_crypto -> high volatility
+}
+
+impl VolatilityRegime {
+    // Returns true when the regime changed
+    pub fn set(&self, is_crypto: bool, high: bool) -> bool {
+        self.high.insert(is_crypto, high).unwrap_or(false) != high
+    }
+
+    pub fn is_high(&self, is_crypto: bool) -> bool {
+        self.high.get(&is_crypto).is_some_and(|h| *h)
+    }
+}
@@ -0,0 +1,139 @@
+// Drives the engine as a library against a local stand-in for Alpaca's
+// trading and market data APIs. No API server, no network.
+
+use axum::extract::{Path, Query};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{Duration, Utc};
+use ladybug_engine::{run_stock_cycle, Bar, Config, Engine, Strategy, StrategyContext};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PRICE: f64 = 100.0;
+
+fn bars() -> Value {
+    let now = Utc::now();
+    let bars: Vec<Value> = (0..50)
+        .map(|i| {
+            let close = PRICE - 5.0 + i as f64 * 0.1;
+            json!({
+                "t": (now - Duration::minutes(5 * (49 - i))).to_rfc3339(),
+                "o": close - 0.05, "h": close + 0.2, "l": close - 0.2, "c": close, "v": 10_000,
+            })
+        })
+        .collect();
+    json!({ "bars": bars })
+}
+
+async fn latest_trades(Query(query): Query<HashMap<String, String>>) -> Json<Value> {
+    let trades: serde_json::Map<String, Value> = query["symbols"]
+        .split(',')
+        .map(|s| (s.to_string(), json!({ "p": PRICE })))
+        .collect();
+    Json(json!({ "trades": trades }))
+}
+
+async fn place_order(Json(order): Json<Value>) -> Json<Value> {
+    Json(json!({
+        "id": "00000000-test-order",
+        "symbol": order["symbol"],
+        "qty": order["qty"],
+        "side": order["side"],
+        "order_type": order["type"],
+        "status": "filled",
+        "filled_avg_price": PRICE.to_string(),
+        "filled_qty": order["qty"],
+    }))
+}
+
+// Serves both hosts; returns the base URL
+async fn mock_alpaca() -> String {
+    let app = Router::new()
+        .route("/v2/account", get(|| async {
+            Json(json!({ "buying_power": "50000", "cash": "50000", "portfolio_value": "100000" }))
+        }))
+        .route("/v2/positions", get(|| async { Json(json!([])) }))
+        .route("/v2/orders", post(place_order))
+        .route("/v2/orders/:id", get(|Path(id): Path<String>| async move {
+            Json(json!({ "id": id, "symbol": "", "qty": "0", "side": "buy", "order_type": "market", "status": "filled" }))
+        }))
+        .route("/v2/stocks/trades/latest", get(latest_trades))
+        .route("/v2/stocks/:symbol/bars", get(|| async { Json(bars()) }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+fn test_config(host: &str) -> Config {
+    let dir = std::env::temp_dir().join(format!("ladybug-engine-test-{}", uuid::Uuid::new_v4()));
+    let mut config = Config::from_env();
+    config.credentials.api_key = "test-key".to_string();
+    config.credentials.api_secret = "test-secret".to_string();
+    config.data.trading_host = host.to_string();
+    config.data.data_host = host.to_string();
+    config.persistence.history_dir = dir.join("history").display().to_string();
+    config.persistence.notes_path = dir.join("notes.json").display().to_string();
+    config.persistence.profiles_dir = dir.join("profiles").display().to_string();
+    config.persistence.config_history_path = dir.join("config_history.json").display().to_string();
+    config.recorder.enabled = false;
+    config.approval.webhook_url = String::new();
+    config
+}
+
+#[tokio::test(start_paused = true)]
+async fn stock_cycle_analyzes_every_symbol_without_the_api_server() {
+    let host = mock_alpaca().await;
+    let engine = Engine::new(test_config(&host));
+    let universe = engine.state().trading_mode.read().await.get_stocks().len();
+
+    let summary = run_stock_cycle(engine.state()).await;
+
+    assert_eq!(summary.failed, 0);
+    assert_eq!(summary.analyzed as usize, universe);
+    assert_eq!(engine.state().last_signals.len(), universe);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stock_cycle_records_latency_histograms() {
+    let host = mock_alpaca().await;
+    let mut config = test_config(&host);
+    config.metrics.enabled = true;
+    let engine = Engine::new(config);
+
+    run_stock_cycle(engine.state()).await;
+
+    let text = engine.state().metrics.render();
+    assert!(text.contains("ladybug_cycle_duration_seconds_count{asset_class=\"stock\"} 1"));
+    assert!(text.contains("ladybug_symbol_processing_seconds_count{asset_class=\"stock\"}"));
+    assert!(text.contains("ladybug_alpaca_request_seconds_count{endpoint=\"bars\"}"));
+}
+
+struct AlwaysBuy;
+
+impl Strategy for AlwaysBuy {
+    fn name(&self) -> &str {
+        "always_buy"
+    }
+
+    fn signal(&self, _bars: &[Bar], _sentiment: f64, _ctx: &StrategyContext) -> f64 {
+        1.0
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn custom_strategy_drives_trades() {
+    let host = mock_alpaca().await;
+    let mut config = test_config(&host);
+    config.entry_limits.grace_period_secs = 0;
+    config.thresholds.entry_confirmation_cycles = 1;
+    let engine = Engine::new(config).with_strategy(Arc::new(AlwaysBuy));
+
+    let summary = run_stock_cycle(engine.state()).await;
+
+    assert_eq!(summary.buy_signals, summary.analyzed);
+    let trades = engine.state().trade_history.read().await;
+    assert_eq!(trades.len(), summary.analyzed as usize);
+    assert!(trades.iter().all(|t| t.action == "BUY" && t.price == PRICE));
+}